@@ -0,0 +1,105 @@
+// A small game built on the terminal_games library, run with `cargo run --example catch`
+use std::{sync::{Arc, Mutex}, time::Duration};
+
+use rand::prelude::*;
+use terminal_games::{
+	common::{
+		config::*,
+		game_loop::*,
+		input::*,
+		screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::*, util::*}
+	},
+	screens::run_launcher,
+	Game, GameEntry, GameReturn, Registry, Score, WinState
+};
+
+static BOARD_SIZE: (u16, u16) = (20, 10);
+
+struct GameState {
+	board: Rect,
+	basket: u16,
+	drop: (u16, u16),
+	score: u32,
+	finished: bool
+}
+
+fn default_bindings() -> Bindings {
+	Bindings::new("catch", &[
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"])
+	])
+}
+
+fn write_screen(state: &GameState) {
+	let theme = current_theme();
+	clear_screen();
+	BoxPrint::in_rect(state.board.outer(Padding::uniform(1))).print();
+	let (x, y) = state.board.coords();
+	print_clipped(theme.glyphs.food, (x + state.drop.0, y + state.drop.1), Some(state.board), None, Some(&theme.accent));
+	print_clipped(theme.glyphs.block, (x + state.basket, y + state.board.height - 1), Some(state.board), None, None);
+	let score = format!("Score: {}", state.score);
+	// Below the board's bottom border
+	let status_rect = Rect::new((state.board.x, state.board.bottom() + 1), (state.board.width, 1));
+	TextPrint::in_rect(&score, status_rect, Align::Center).print();
+	if state.finished {
+		print_splash("Missed", &score);
+	}
+	cursor_to_end();
+}
+
+fn update_state(state: &mut GameState) {
+	state.drop.1 += 1;
+	if state.drop.1 < state.board.height - 1 {return};
+	if state.drop.0 == state.basket {
+		state.score += 1;
+		state.drop = (thread_rng().gen_range(0..state.board.width), 0);
+	} else {
+		state.finished = true;
+	}
+}
+
+struct Catch {}
+impl Game for Catch {
+	fn run(&self) -> GameReturn {
+		let board = Rect::screen().centered(BOARD_SIZE);
+		let locked_state = Arc::new(Mutex::new(GameState {
+			board,
+			basket: board.width / 2,
+			drop: (thread_rng().gen_range(0..board.width), 0),
+			score: 0,
+			finished: false
+		}));
+		let state_clone = locked_state.clone();
+		let bindings = default_bindings().load_or_default();
+		let input_handler = spawn_input_handler(move |key| {
+			let mut state = state_clone.lock().unwrap();
+			if state.finished {return false;}
+			match bindings.action_for(key) {
+				Some(Action::MoveLeft) => state.basket = state.basket.saturating_sub(1),
+				Some(Action::MoveRight) => state.basket = std::cmp::min(state.basket + 1, state.board.width - 1),
+				_ => {}
+			}
+			true
+		});
+
+		run_game_loop(&locked_state, |state| {
+			update_state(state);
+			write_screen(state);
+			if state.finished {Tick::Stop} else {Tick::Continue(Duration::from_millis(150))}
+		});
+		let _ = input_handler.join();
+		let score = locked_state.lock().unwrap().score;
+		GameReturn::new(Score::SinglePlayer(score as f32), WinState::None)
+	}
+}
+
+fn main() {
+	let mut registry = Registry::new();
+	let mut catch = GameEntry::new("catch", "Catch", "Move the basket to catch whatever falls", |_| Box::new(Catch {}), default_bindings);
+	catch.set_version("0.1.0");
+	registry.register(catch).expect("couldn't register catch");
+	terminal_games::games::register_all(&mut registry).expect("couldn't register games");
+	set_color_depth(ColorDepth::detect());
+	apply_config(Config::load().unwrap_or_default());
+	run_launcher(&registry, registry.find("catch"));
+}
@@ -0,0 +1,37 @@
+// Draws each of the screen widgets once, run with `cargo run --example widgets`
+use terminal_games::common::{
+	input::read_key,
+	screen::{bigtext::BigText, boxes::BoxPrint, layout::*, text::TextPrint, theme::current_theme, util::*}
+};
+
+fn main() {
+	let theme = current_theme();
+	clear_screen();
+	let frame = Rect::screen().centered((50, 16));
+	let [banner, body, footer] = Layout::vertical(&[Constraint::Fixed(5), Constraint::Min(3), Constraint::Fixed(1)])
+		.spacing(1)
+		.split(frame)[..] else {
+		unreachable!()
+	};
+	BigText::in_rect("Hello", banner, Align::Center)
+		.color_fg(&theme.accent)
+		.print();
+
+	let [left, right] = Layout::horizontal(&[Constraint::Percent(50), Constraint::Percent(50)])
+		.spacing(2)
+		.split(body)[..] else {
+		unreachable!()
+	};
+	BoxPrint::in_rect(left).print();
+	TextPrint::in_rect("Boxes draw with the current theme", left.inner(Padding::uniform(1)), Align::Start)
+		.set_wrap(true)
+		.print();
+	BoxPrint::in_rect(right).set_border_col(&theme.accent).print();
+	TextPrint::in_rect("Text wraps and clips to its rect", right.inner(Padding::uniform(1)), Align::Start)
+		.set_wrap(true)
+		.print();
+
+	TextPrint::in_rect("Press any key to exit", footer, Align::Center).print();
+	cursor_to_end();
+	read_key();
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::*;
+use super::registry::Registry;
+use super::screen::toast::show_toast;
+use super::terminal::current_session;
+
+// Unlocked once the game reports its event with a value of at least the goal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Achievement {
+	pub id: &'static str,
+	pub name: &'static str,
+	pub description: &'static str,
+	pub event: &'static str,
+	pub goal: u32
+}
+
+// Keyed by "<game id>.<achievement id>"
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct AchievementProgress {
+	pub unlocked: BTreeSet<String>,
+	// Best value reported towards each locked achievement
+	pub progress: BTreeMap<String, u32>
+}
+
+pub fn get_achievements_path() -> Option<PathBuf> {
+	get_data_dir().map(|dir| dir.join("achievements.toml"))
+}
+
+fn achievement_key(game_id: &str, achievement: &Achievement) -> String {
+	format!("{}.{}", game_id, achievement.id)
+}
+
+impl AchievementProgress {
+	pub fn load() -> Result<AchievementProgress, ConfigError> {
+		load_toml(get_achievements_path().ok_or(ConfigError::NoDataDir)?)
+	}
+	pub fn save(&self) -> Result<(), ConfigError> {
+		save_toml(get_achievements_path().ok_or(ConfigError::NoDataDir)?, self)
+	}
+	pub fn is_unlocked(&self, game_id: &str, achievement: &Achievement) -> bool {
+		self.unlocked.contains(&achievement_key(game_id, achievement))
+	}
+	pub fn progress(&self, game_id: &str, achievement: &Achievement) -> u32 {
+		if self.is_unlocked(game_id, achievement) {return achievement.goal};
+		self.progress.get(&achievement_key(game_id, achievement)).cloned().unwrap_or(0)
+	}
+	// Returns whether this unlocked the achievement
+	pub fn report(&mut self, game_id: &str, achievement: &Achievement, value: u32) -> bool {
+		let key = achievement_key(game_id, achievement);
+		if self.unlocked.contains(&key) {return false};
+		if value >= achievement.goal {
+			self.progress.remove(&key);
+			self.unlocked.insert(key);
+			return true;
+		}
+		let best = self.progress.entry(key).or_insert(0);
+		*best = std::cmp::max(*best, value);
+		false
+	}
+	// Keeps everything unlocked and the best progress from both
+	pub fn merge(&mut self, other: &AchievementProgress) {
+		self.unlocked.extend(other.unlocked.iter().cloned());
+		for (key, value) in other.progress.iter() {
+			let best = self.progress.entry(key.clone()).or_insert(0);
+			*best = std::cmp::max(*best, *value);
+		}
+		self.progress.retain(|key, _| !self.unlocked.contains(key));
+	}
+}
+
+pub(crate) struct Tracker {
+	achievements: Vec<(&'static str, Achievement)>,
+	progress: AchievementProgress
+}
+
+// For the process's own terminal, each served session has its own
+static TRACKER: Mutex<Option<Tracker>> = Mutex::new(None);
+
+fn with_tracker<R, F: FnOnce(&mut Option<Tracker>) -> R>(run: F) -> R {
+	match current_session() {
+		Some(session) => run(&mut session.tracker.lock().unwrap()),
+		None => run(&mut TRACKER.lock().unwrap())
+	}
+}
+
+// Events are ignored until tracking starts, so simulations and tests don't unlock anything
+pub fn start_tracking(registry: &Registry, progress: AchievementProgress) {
+	let achievements = registry.games().iter().flat_map(|entry| {
+		entry.achievements.iter().map(|achievement| (entry.id, *achievement))
+	}).collect();
+	with_tracker(|tracker| *tracker = Some(Tracker {
+		achievements,
+		progress
+	}));
+}
+
+pub fn is_tracking() -> bool {
+	with_tracker(|tracker| tracker.is_some())
+}
+
+// Progress so far, from the tracker when running or the saved file otherwise
+pub fn current_progress() -> Result<AchievementProgress, ConfigError> {
+	match with_tracker(|tracker| tracker.as_ref().map(|tracker| tracker.progress.clone())) {
+		Some(progress) => Ok(progress),
+		None => AchievementProgress::load()
+	}
+}
+
+// Called by games as things happen, a toast is shown for each achievement unlocked
+pub fn report_event(game_id: &str, event: &str, value: u32) {
+	let unlocked = with_tracker(|tracker| {
+		let Some(Tracker { achievements, progress }) = tracker.as_mut() else {return vec![]};
+		achievements.iter().filter(|(achievement_game, achievement)| {
+			*achievement_game == game_id && achievement.event == event && progress.report(game_id, achievement, value)
+		}).map(|(_, achievement)| achievement.name).collect::<Vec<&str>>()
+	});
+	for name in unlocked {
+		show_toast("Achievement unlocked", name);
+	}
+}
+
+// Merged with the saved file, which other sessions on a server may have saved to meanwhile
+pub fn save_progress() -> Result<(), ConfigError> {
+	with_tracker(|tracker| match tracker.as_mut() {
+		Some(tracker) => {
+			tracker.progress.merge(&AchievementProgress::load()?);
+			tracker.progress.save()
+		},
+		None => Ok(())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Barrier};
+	use std::thread;
+
+	use console::Key;
+
+	use super::*;
+	use crate::common::terminal::*;
+	use crate::games::snake;
+
+	static LONG_SNAKE: Achievement = Achievement {
+		id: "long",
+		name: "Long snake",
+		description: "Grow to a length of 50",
+		event: "length",
+		goal: 50
+	};
+
+	#[test]
+	fn tracks_best_progress_until_unlocked() {
+		let mut progress = AchievementProgress::default();
+		assert!(!progress.report("snake", &LONG_SNAKE, 20));
+		assert!(!progress.report("snake", &LONG_SNAKE, 10));
+		assert_eq!(progress.progress("snake", &LONG_SNAKE), 20);
+		assert!(progress.report("snake", &LONG_SNAKE, 50));
+		assert!(progress.is_unlocked("snake", &LONG_SNAKE));
+		assert!(!progress.report("snake", &LONG_SNAKE, 60));
+		assert_eq!(progress.progress("snake", &LONG_SNAKE), 50);
+	}
+
+	#[test]
+	fn progress_is_per_game() {
+		let mut progress = AchievementProgress::default();
+		progress.report("snake", &LONG_SNAKE, 50);
+		assert!(!progress.is_unlocked("other", &LONG_SNAKE));
+	}
+
+	struct ClosedTerminal;
+
+	impl Terminal for ClosedTerminal {
+		fn write(&self, _: &str) {}
+		fn size(&self) -> (u16, u16) {
+			(80, 24)
+		}
+		fn read_key(&self) -> Option<Key> {
+			None
+		}
+		fn is_closed(&self) -> bool {
+			true
+		}
+	}
+
+	#[test]
+	fn sessions_track_separately() {
+		let mut registry = Registry::new();
+		registry.register(snake::get_game_entry()).unwrap();
+		let achievement = registry.games()[0].achievements[0];
+		let (registry, barrier) = (&registry, &Barrier::new(2));
+		let results = thread::scope(|scope| {
+			[true, false].map(|plays| scope.spawn(move || with_terminal(Arc::new(ClosedTerminal), || {
+				start_tracking(registry, AchievementProgress::default());
+				// Both are tracking before either unlocks anything, and neither checks until both are done
+				barrier.wait();
+				if plays {
+					report_event("snake", achievement.event, achievement.goal);
+				}
+				barrier.wait();
+				let toasts = current_session().unwrap().toasts.lock().unwrap().len();
+				(current_progress().unwrap().is_unlocked("snake", &achievement), toasts)
+			}))).map(|handle| handle.join().unwrap())
+		});
+		assert_eq!(results, [(true, 1), (false, 0)]);
+		assert!(!is_tracking());
+	}
+
+	#[test]
+	fn merges_progress() {
+		let mut progress = AchievementProgress::default();
+		progress.report("snake", &LONG_SNAKE, 20);
+		let mut other = AchievementProgress::default();
+		other.report("snake", &LONG_SNAKE, 50);
+		other.progress.insert("pong.rally".to_owned(), 4);
+		progress.merge(&other);
+		assert!(progress.is_unlocked("snake", &LONG_SNAKE));
+		assert_eq!(progress.progress, BTreeMap::from([("pong.rally".to_owned(), 4)]));
+	}
+}
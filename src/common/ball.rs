@@ -0,0 +1,110 @@
+// Which way a ball mainly travels, side to side between Pong's paddles or up and down in Breakout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+	Horizontal,
+	Vertical
+}
+
+// A ball moving in straight lines at a steady speed, in board coordinates counting from 0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ball {
+	pub pos: (f32, f32),
+	pub axis: Axis,
+	// Heading towards higher coordinates along the axis, right or down
+	pub forward: bool,
+	// Radians off the axis, positive heads towards higher coordinates across it
+	pub angle: f32,
+	// How far it goes each step
+	pub speed: f32
+}
+
+impl Ball {
+	pub fn new(pos: (f32, f32), axis: Axis, forward: bool, angle: f32, speed: f32) -> Ball {
+		Ball { pos, axis, forward, angle, speed }
+	}
+	pub fn across(&self) -> f32 {
+		match self.axis {
+			Axis::Horizontal => self.pos.1,
+			Axis::Vertical => self.pos.0
+		}
+	}
+	fn along_mut(&mut self) -> &mut f32 {
+		match self.axis {
+			Axis::Horizontal => &mut self.pos.0,
+			Axis::Vertical => &mut self.pos.1
+		}
+	}
+	fn across_mut(&mut self) -> &mut f32 {
+		match self.axis {
+			Axis::Horizontal => &mut self.pos.1,
+			Axis::Vertical => &mut self.pos.0
+		}
+	}
+	// Moves one step, bouncing off the walls on either side of its path, which are `limit` apart
+	pub fn step(&mut self, limit: f32) {
+		let along_move = self.angle.cos() * self.speed;
+		let across_move = self.angle.sin() * self.speed;
+		*self.along_mut() += if self.forward {along_move} else {-along_move};
+		*self.across_mut() += across_move;
+		if self.across() > limit - across_move || self.across() < 0.0 {
+			self.angle *= -1.0;
+			*self.across_mut() -= across_move;
+		}
+	}
+	// Whether it's level with a paddle covering `length` cells from `start`, across its path
+	pub fn is_over(&self, start: f32, length: f32) -> bool {
+		let across = self.across().floor();
+		across >= start && across < start + length
+	}
+	// Sends it back the way it came. Hitting further from the middle of the paddle sends it off
+	// at a steeper angle, up to `max_angle` at the ends
+	pub fn bounce_off(&mut self, start: f32, length: f32, max_angle: f32) {
+		let half = length / 2.0;
+		let from_middle = ((self.across() - (start + half)) / half).clamp(-1.0, 1.0);
+		self.angle = from_middle * max_angle;
+		self.forward = !self.forward;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	static PI: f32 = std::f32::consts::PI;
+
+	#[test]
+	fn moves_along_its_axis() {
+		let mut ball = Ball::new((5.0, 5.0), Axis::Horizontal, false, 0.0, 1.5);
+		ball.step(10.0);
+		assert_eq!(ball.pos, (3.5, 5.0));
+		let mut ball = Ball::new((5.0, 5.0), Axis::Vertical, true, 0.0, 1.5);
+		ball.step(10.0);
+		assert_eq!(ball.pos, (5.0, 6.5));
+	}
+
+	#[test]
+	fn bounces_off_the_walls() {
+		let mut ball = Ball::new((0.2, 5.0), Axis::Vertical, true, -PI / 4.0, 1.0);
+		ball.step(10.0);
+		assert!(ball.angle > 0.0);
+		assert!((ball.across() - 0.2).abs() < 1e-6);
+		assert!(ball.pos.1 > 5.0);
+	}
+
+	#[test]
+	fn angle_depends_on_where_the_paddle_is_hit() {
+		let mut ball = Ball::new((4.5, 9.0), Axis::Vertical, true, 0.3, 1.0);
+		assert!(ball.is_over(3.0, 3.0));
+		assert!(!ball.is_over(5.0, 3.0));
+		ball.bounce_off(3.0, 3.0, PI / 3.0);
+		assert!(!ball.forward);
+		assert_eq!(ball.angle, 0.0);
+		ball.pos.0 = 6.0;
+		ball.bounce_off(3.0, 3.0, PI / 3.0);
+		assert!(ball.forward);
+		assert_eq!(ball.angle, PI / 3.0);
+		ball.pos.0 = 3.0;
+		ball.bounce_off(3.0, 3.0, PI / 3.0);
+		assert_eq!(ball.angle, -PI / 3.0);
+	}
+}
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::screen::{glyphs::*, theme::*};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+	Easy,
+	Normal,
+	Hard
+}
+
+impl Difficulty {
+	pub fn all() -> [Difficulty; 3] {
+		[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+	}
+	pub fn name(&self) -> &'static str {
+		match self {
+			Difficulty::Easy => "easy",
+			Difficulty::Normal => "normal",
+			Difficulty::Hard => "hard"
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SnakeConfig {
+	pub width: u16,
+	pub height: u16,
+	// Milliseconds between moves
+	pub speed: u16
+}
+
+impl Default for SnakeConfig {
+	fn default() -> SnakeConfig {
+		SnakeConfig {
+			width: 30,
+			height: 10,
+			speed: 50
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct PongConfig {
+	pub points_to_win: u16
+}
+
+impl Default for PongConfig {
+	fn default() -> PongConfig {
+		PongConfig {
+			points_to_win: 10
+		}
+	}
+}
+
+// 2048
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TilesConfig {
+	// Tiles along each side of the square board
+	pub size: u16
+}
+
+// The largest 2048 board fits an 80x24 terminal with its tiles squashed to a single row
+pub static MIN_TILES_SIZE: u16 = 2;
+pub static MAX_TILES_SIZE: u16 = 9;
+
+impl TilesConfig {
+	pub fn board_size(&self) -> u16 {
+		self.size.clamp(MIN_TILES_SIZE, MAX_TILES_SIZE)
+	}
+}
+
+impl Default for TilesConfig {
+	fn default() -> TilesConfig {
+		TilesConfig {
+			size: 4
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TronConfig {
+	// Rounds in a match, it's over once someone has won most of them
+	pub rounds: u16,
+	// A second player on the arrow keys rather than the computer
+	pub two_players: bool
+}
+
+impl Default for TronConfig {
+	fn default() -> TronConfig {
+		TronConfig {
+			rounds: 3,
+			two_players: false
+		}
+	}
+}
+
+// Custom Minesweeper boards, the largest fit an 80x24 terminal
+pub static MIN_MINES_SIZE: u16 = 5;
+pub static MAX_MINES_SIZE: (u16, u16) = (38, 18);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MinesLevel {
+	Beginner,
+	Intermediate,
+	Expert,
+	Custom
+}
+
+impl MinesLevel {
+	pub fn all() -> [MinesLevel; 4] {
+		[MinesLevel::Beginner, MinesLevel::Intermediate, MinesLevel::Expert, MinesLevel::Custom]
+	}
+	pub fn name(&self) -> &'static str {
+		match self {
+			MinesLevel::Beginner => "beginner",
+			MinesLevel::Intermediate => "intermediate",
+			MinesLevel::Expert => "expert",
+			MinesLevel::Custom => "custom"
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct MinesweeperConfig {
+	pub level: MinesLevel,
+	// The board for the custom level
+	pub width: u16,
+	pub height: u16,
+	pub mines: u16
+}
+
+impl MinesweeperConfig {
+	// Width, height and mines. Custom boards are kept small enough to draw, with room for a safe first click
+	pub fn board(&self) -> (u16, u16, u16) {
+		match self.level {
+			MinesLevel::Beginner => (9, 9, 10),
+			MinesLevel::Intermediate => (16, 16, 40),
+			MinesLevel::Expert => (30, 16, 99),
+			MinesLevel::Custom => {
+				let width = self.width.clamp(MIN_MINES_SIZE, MAX_MINES_SIZE.0);
+				let height = self.height.clamp(MIN_MINES_SIZE, MAX_MINES_SIZE.1);
+				(width, height, self.mines.clamp(1, width * height - 9))
+			}
+		}
+	}
+	pub fn describe(&self) -> String {
+		match self.level {
+			MinesLevel::Custom => {
+				let (width, height, mines) = self.board();
+				format!("custom {}x{}, {} mines", width, height, mines)
+			},
+			level => level.name().to_owned()
+		}
+	}
+}
+
+impl Default for MinesweeperConfig {
+	fn default() -> MinesweeperConfig {
+		MinesweeperConfig {
+			level: MinesLevel::Beginner,
+			width: 20,
+			height: 12,
+			mines: 40
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Config {
+	pub theme: String,
+	pub ascii: bool,
+	pub difficulty: Difficulty,
+	pub player_name: String,
+	// Address of a shared leaderboard server, empty to only keep scores locally
+	pub leaderboard_server: String,
+	// Game -> action -> keys
+	pub key_bindings: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+	pub snake: SnakeConfig,
+	pub pong: PongConfig,
+	pub tiles: TilesConfig,
+	pub minesweeper: MinesweeperConfig,
+	pub tron: TronConfig
+}
+
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			theme: "classic".to_owned(),
+			ascii: false,
+			difficulty: Difficulty::Normal,
+			player_name: "Player".to_owned(),
+			leaderboard_server: String::new(),
+			key_bindings: BTreeMap::new(),
+			snake: SnakeConfig::default(),
+			pong: PongConfig::default(),
+			tiles: TilesConfig::default(),
+			minesweeper: MinesweeperConfig::default(),
+			tron: TronConfig::default()
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+	NoConfigDir,
+	NoDataDir,
+	Io(PathBuf, std::io::Error),
+	Parse(PathBuf, toml::de::Error),
+	Serialize(toml::ser::Error)
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConfigError::NoConfigDir => write!(f, "couldn't find a config directory, set $XDG_CONFIG_HOME or $HOME"),
+			ConfigError::NoDataDir => write!(f, "couldn't find a data directory, set $XDG_DATA_HOME or $HOME"),
+			ConfigError::Io(path, err) => write!(f, "couldn't access {}: {}", path.display(), err),
+			ConfigError::Parse(path, err) => write!(f, "couldn't parse {}: {}", path.display(), err),
+			ConfigError::Serialize(err) => write!(f, "couldn't write config: {}", err)
+		}
+	}
+}
+
+// $XDG_CONFIG_HOME, falling back to ~/.config as the XDG spec says
+pub fn get_config_dir() -> Option<PathBuf> {
+	let xdg_dir = std::env::var_os("XDG_CONFIG_HOME")
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute());
+	let dir = match xdg_dir {
+		Some(dir) => dir,
+		None => PathBuf::from(std::env::var_os("HOME")?).join(".config")
+	};
+	Some(dir.join("terminal-games"))
+}
+
+pub fn get_config_path() -> Option<PathBuf> {
+	get_config_dir().map(|dir| dir.join("config.toml"))
+}
+
+// $XDG_DATA_HOME, falling back to ~/.local/share, for things the games record rather than settings
+pub fn get_data_dir() -> Option<PathBuf> {
+	let xdg_dir = std::env::var_os("XDG_DATA_HOME")
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute());
+	let dir = match xdg_dir {
+		Some(dir) => dir,
+		None => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("share")
+	};
+	Some(dir.join("terminal-games"))
+}
+
+// A missing file just means nothing has been saved yet
+pub fn load_toml<T: DeserializeOwned + Default>(path: PathBuf) -> Result<T, ConfigError> {
+	match std::fs::read_to_string(&path) {
+		Ok(text) => toml::from_str(&text).map_err(|err| ConfigError::Parse(path, err)),
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+		Err(err) => Err(ConfigError::Io(path, err))
+	}
+}
+
+pub fn save_toml<T: Serialize>(path: PathBuf, value: &T) -> Result<(), ConfigError> {
+	let text = toml::to_string_pretty(value).map_err(ConfigError::Serialize)?;
+	if let Some(dir) = path.parent() {
+		std::fs::create_dir_all(dir).map_err(|err| ConfigError::Io(dir.to_path_buf(), err))?;
+	}
+	std::fs::write(&path, text).map_err(|err| ConfigError::Io(path, err))
+}
+
+impl Config {
+	pub fn parse(path: PathBuf, text: &str) -> Result<Config, ConfigError> {
+		toml::from_str(text).map_err(|err| ConfigError::Parse(path, err))
+	}
+	pub fn load() -> Result<Config, ConfigError> {
+		load_toml(get_config_path().ok_or(ConfigError::NoConfigDir)?)
+	}
+	pub fn save(&self) -> Result<(), ConfigError> {
+		save_toml(get_config_path().ok_or(ConfigError::NoConfigDir)?, self)
+	}
+}
+
+// Settings given on the command line, which win over the config file for the whole run
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+	pub theme: Option<String>,
+	pub ascii: bool,
+	pub difficulty: Option<Difficulty>,
+	pub player_name: Option<String>,
+	pub leaderboard_server: Option<String>
+}
+
+impl Overrides {
+	pub fn apply(&self, config: &mut Config) {
+		if let Some(theme) = &self.theme {
+			config.theme = theme.to_owned();
+		}
+		config.ascii = config.ascii || self.ascii;
+		if let Some(difficulty) = self.difficulty {
+			config.difficulty = difficulty;
+		}
+		if let Some(player_name) = &self.player_name {
+			config.player_name = player_name.to_owned();
+		}
+		if let Some(leaderboard_server) = &self.leaderboard_server {
+			config.leaderboard_server = leaderboard_server.to_owned();
+		}
+	}
+}
+
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+static OVERRIDES: RwLock<Option<Overrides>> = RwLock::new(None);
+
+pub fn current_config() -> Config {
+	CONFIG.read().unwrap().clone().unwrap_or_default()
+}
+
+pub fn set_config(config: Config) {
+	*CONFIG.write().unwrap() = Some(config);
+}
+
+pub fn current_overrides() -> Overrides {
+	OVERRIDES.read().unwrap().clone().unwrap_or_default()
+}
+
+pub fn set_overrides(overrides: Overrides) {
+	*OVERRIDES.write().unwrap() = Some(overrides);
+}
+
+// Makes the config the one in use, including the look of everything drawn from now on
+pub fn apply_config(config: Config) {
+	set_theme(Theme::by_name(&config.theme).unwrap_or_else(Theme::classic));
+	set_ascii_only(config.ascii || !locale_supports_unicode());
+	set_config(config);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn overrides_win_over_the_file() {
+		let mut config = Config { theme: "ocean".to_owned(), ascii: true, ..Config::default() };
+		Overrides::default().apply(&mut config);
+		assert_eq!(config.theme, "ocean");
+		let overrides = Overrides {
+			theme: Some("classic".to_owned()),
+			difficulty: Some(Difficulty::Hard),
+			player_name: Some("Ada".to_owned()),
+			..Overrides::default()
+		};
+		overrides.apply(&mut config);
+		assert_eq!((config.theme.as_str(), config.difficulty, config.player_name.as_str()), ("classic", Difficulty::Hard, "Ada"));
+		// Turning ASCII on from the command line can't turn it off in the file
+		assert!(config.ascii);
+	}
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::config::*;
+use super::game::GameSetup;
+use super::registry::GameEntry;
+
+// Days of results kept in the daily leaderboard, locally and on the shared server
+pub static DAYS_KEPT: usize = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+	pub year: i64,
+	pub month: u8,
+	pub day: u8
+}
+
+impl Date {
+	// Civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+	pub fn from_days(days: u64) -> Date {
+		let z = days as i64 + 719468;
+		let era = z.div_euclid(146097);
+		let day_of_era = z.rem_euclid(146097);
+		let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+		let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+		let mp = (5 * day_of_year + 2) / 153;
+		let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+		let month = if mp < 10 {mp + 3} else {mp - 9} as u8;
+		let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+		Date { year, month, day }
+	}
+	// UTC, so everyone gets the same challenge at the same time
+	pub fn today() -> Date {
+		let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+		Date::from_days(seconds / 86400)
+	}
+	pub fn days_since_epoch(&self) -> u64 {
+		let year = if self.month <= 2 {self.year - 1} else {self.year};
+		let era = year.div_euclid(400);
+		let year_of_era = year.rem_euclid(400);
+		let month = self.month as i64;
+		let day_of_year = (153 * (if month > 2 {month - 3} else {month + 9}) + 2) / 5 + self.day as i64 - 1;
+		let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+		(era * 146097 + day_of_era - 719468) as u64
+	}
+}
+
+impl fmt::Display for Date {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+	}
+}
+
+// FNV-1a, unlike std's hasher it gives the same value on every build and platform
+pub fn stable_hash(text: &str) -> u64 {
+	text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn daily_seed(date: Date, game_id: &str) -> u64 {
+	stable_hash(&format!("{}:{}", date, game_id))
+}
+
+// The same settings and seed for everyone playing the game on that date
+pub fn daily_setup(entry: &GameEntry, date: Date) -> Option<GameSetup> {
+	let fix_settings = entry.daily?;
+	let mut config = current_config();
+	fix_settings(&mut config, date.days_since_epoch());
+	Some(GameSetup {
+		config,
+		seed: daily_seed(date, entry.id)
+	})
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DailyScore {
+	pub player: String,
+	pub score: f32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DailyResults {
+	// Date -> game id -> scores
+	pub days: BTreeMap<String, BTreeMap<String, Vec<DailyScore>>>
+}
+
+pub fn get_daily_path() -> Option<PathBuf> {
+	get_data_dir().map(|dir| dir.join("daily.toml"))
+}
+
+impl DailyResults {
+	pub fn load() -> Result<DailyResults, ConfigError> {
+		load_toml(get_daily_path().ok_or(ConfigError::NoDataDir)?)
+	}
+	pub fn save(&self) -> Result<(), ConfigError> {
+		save_toml(get_daily_path().ok_or(ConfigError::NoDataDir)?, self)
+	}
+	pub fn has_played(&self, date: Date, game_id: &str, player: &str) -> bool {
+		self.scores(date, game_id).iter().any(|score| score.player == player)
+	}
+	pub fn scores(&self, date: Date, game_id: &str) -> &[DailyScore] {
+		self.days.get(&date.to_string()).and_then(|games| games.get(game_id)).map_or(&[], |scores| scores.as_slice())
+	}
+	// Only each player's first attempt each day counts, returns whether this one did
+	pub fn add(&mut self, date: Date, game_id: &str, player: &str, score: f32) -> bool {
+		if self.has_played(date, game_id, player) {return false};
+		let scores = self.days.entry(date.to_string()).or_default().entry(game_id.to_owned()).or_default();
+		scores.push(DailyScore {
+			player: player.to_owned(),
+			score
+		});
+		scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+		// Dates sort oldest first
+		while self.days.len() > DAYS_KEPT {
+			self.days.pop_first();
+		}
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_dates() {
+		assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+		assert_eq!(Date::from_days(19782).to_string(), "2024-02-29");
+		assert_eq!(Date::from_days(20745).to_string(), "2026-10-19");
+		for days in [0, 59, 19782, 20745, 30000] {
+			assert_eq!(Date::from_days(days).days_since_epoch(), days);
+		}
+	}
+
+	#[test]
+	fn seeds_are_stable_per_date_and_game() {
+		let date = Date::from_days(20745);
+		assert_eq!(daily_seed(date, "snake"), daily_seed(date, "snake"));
+		assert_ne!(daily_seed(date, "snake"), daily_seed(date, "pong"));
+		assert_ne!(daily_seed(date, "snake"), daily_seed(Date::from_days(20746), "snake"));
+	}
+
+	#[test]
+	fn one_attempt_per_player_per_day() {
+		let date = Date::from_days(20745);
+		let mut results = DailyResults::default();
+		assert!(results.add(date, "snake", "ada", 4.0));
+		assert!(!results.add(date, "snake", "ada", 12.0));
+		assert!(results.add(date, "snake", "grace", 9.0));
+		assert!(results.add(date, "pong", "ada", 3.0));
+		assert!(results.add(Date::from_days(20746), "snake", "ada", 1.0));
+		assert!(results.has_played(date, "snake", "grace"));
+		assert!(!results.has_played(date, "pong", "grace"));
+		let players = results.scores(date, "snake").iter().map(|score| score.player.as_str()).collect::<Vec<&str>>();
+		assert_eq!(players, vec!["grace", "ada"]);
+	}
+
+	#[test]
+	fn keeps_recent_days() {
+		let mut results = DailyResults::default();
+		for days in 0..40 {
+			results.add(Date::from_days(20700 + days), "pong", "ada", 1.0);
+		}
+		assert_eq!(results.days.len(), DAYS_KEPT);
+		assert!(!results.days.contains_key(&Date::from_days(20700).to_string()));
+	}
+}
@@ -1,48 +1,110 @@
-use std::fmt::format;
+use super::config::*;
 
+// Non-exhaustive like GameReturn, so new kinds of result don't break games outside this crate
+#[non_exhaustive]
 pub enum Score {
 	SinglePlayer(f32),
 	TwoPlayer(f32, f32),
+	// Two player Tron and tournament matches, neither score is the player's own
+	Versus(f32, f32),
 	None
 }
 
+impl Score {
+	// The local player's score, the first one in two player games
+	pub fn player_score(&self) -> Option<f32> {
+		match self {
+			Score::SinglePlayer(score) => Some(*score),
+			Score::TwoPlayer(score, _) => Some(*score),
+			Score::Versus(..) | Score::None => None
+		}
+	}
+}
+
+#[non_exhaustive]
 pub enum WinState {
 	Win,
 	Lose,
 	Draw,
+	// The other player left a networked game before it finished
+	Disconnected,
 	None
 }
 
+// Built with GameReturn::new, so fields can be added without breaking games outside this crate
+#[non_exhaustive]
 pub struct GameReturn {
 	pub score: Score,
-	pub win_state: WinState
+	pub win_state: WinState,
+	// Game specific bests for the stats screen, like the longest snake
+	pub records: Vec<(&'static str, u32)>,
+	// Seconds taken for the stats screen's best times, where the lowest is kept
+	pub times: Vec<(&'static str, f32)>
 }
+impl Default for GameReturn {
+	fn default() -> GameReturn {
+		GameReturn::new(Score::SinglePlayer(0.0), WinState::Lose)
+	}
+}
+
 impl GameReturn {
-	pub fn default() -> GameReturn {
+	pub fn new(score: Score, win_state: WinState) -> GameReturn {
 		GameReturn {
-			score: Score::SinglePlayer(0.0),
-			win_state: WinState::Lose
+			score,
+			win_state,
+			records: vec![],
+			times: vec![]
 		}
 	}
-	pub fn get_end_text(self: &Self) -> String {
+	pub fn add_record(&mut self, label: &'static str, value: u32) -> &mut Self {
+		self.records.push((label, value));
+		self
+	}
+	pub fn add_time(&mut self, label: &'static str, seconds: f32) -> &mut Self {
+		self.times.push((label, seconds));
+		self
+	}
+	pub fn get_end_text(&self) -> String {
 		let game_over_text = match self.win_state {
 			WinState::None => "Game over.",
 			WinState::Draw => "Game over, draw.",
 			WinState::Lose => "Game over, you lose.",
-			WinState::Win => "Game over, you win."
+			WinState::Win => "Game over, you win.",
+			WinState::Disconnected => "Game over, opponent disconnected."
 		};
 		let score_text: String = match self.score {
 			Score::None => "".to_owned(),
 			Score::SinglePlayer(score) => format!("Score: {}", score),
-			Score::TwoPlayer(you, cpu) => format!("Score: {} - {}", you, cpu)
+			Score::TwoPlayer(you, cpu) => format!("Score: {} - {}", you, cpu),
+			Score::Versus(left, right) => format!("Score: {} - {}", left, right)
 		};
-		let title = format!("{} {}", game_over_text, score_text);
-		title
+		format!("{} {}", game_over_text, score_text)
 	}
 }
 
-pub trait Game<'a> {
-	fn run(&self) -> GameReturn;
+// Everything a game is started with, so a run can be repeated exactly
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSetup {
+	pub config: Config,
+	pub seed: u64
+}
+
+impl GameSetup {
+	// The current settings with a fresh seed
+	pub fn new() -> GameSetup {
+		GameSetup {
+			config: current_config(),
+			seed: rand::random()
+		}
+	}
 }
 
-pub type GameInstance<'a> = (Box<dyn Game<'a>>, &'a str);
\ No newline at end of file
+impl Default for GameSetup {
+	fn default() -> GameSetup {
+		GameSetup::new()
+	}
+}
+
+pub trait Game {
+	fn run(&self) -> GameReturn;
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+use std::{sync::{Arc, Mutex}, thread::sleep, time::Duration};
+
+use super::headless::Simulation;
+use super::input::*;
+use super::screen::util::batch_output;
+use super::terminal::input_closed;
+
+pub enum Tick {
+	// Wait this long before the next step
+	Continue(Duration),
+	Stop
+}
+
+// Runs a step of the game with the state locked, sleeping between steps with the lock released
+// so input handlers can update the state. Each step's drawing goes out in one write. Stops early if the player's connection closes
+pub fn run_game_loop<S, F>(locked_state: &Arc<Mutex<S>>, mut step: F) where F: FnMut(&mut S) -> Tick {
+	while !input_closed() {
+		let tick = {
+			let mut state = locked_state.lock().unwrap();
+			batch_output(|| step(&mut state))
+		};
+		match tick {
+			Tick::Continue(delay) => sleep(delay),
+			Tick::Stop => break
+		}
+	}
+}
+
+// Plays a simulation until it finishes, stepping and drawing it every frame while keys are handled
+// on another thread. Waits for the key that dismisses the end screen, then gives back the state
+pub fn run_simulation<S, F>(state: S, frame_time: Duration, mut handle_key: F) -> S
+where S: Simulation + Send + 'static, F: FnMut(&mut S, InputKey) + Send + 'static {
+	let locked_state = Arc::new(Mutex::new(state));
+	let state_clone = locked_state.clone();
+	let input_handler = spawn_input_handler(move |key| {
+		let mut state = state_clone.lock().unwrap();
+		if state.is_finished() {return false;}
+		handle_key(&mut state, key);
+		true
+	});
+	run_game_loop(&locked_state, |state| {
+		state.tick();
+		state.draw();
+		if state.is_finished() {Tick::Stop} else {Tick::Continue(frame_time)}
+	});
+	let _ = input_handler.join();
+	match Arc::try_unwrap(locked_state) {
+		Ok(state) => state.into_inner().unwrap(),
+		Err(_) => unreachable!("the input handler has finished with the state")
+	}
+}
+
+// Key handling for games that only need their bindings
+pub fn apply_bindings<S: Simulation>(bindings: Bindings) -> impl FnMut(&mut S, InputKey) + Send + 'static {
+	move |state, key| if let Some(action) = bindings.action_for(key) {
+		state.apply(action);
+	}
+}
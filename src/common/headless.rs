@@ -0,0 +1,77 @@
+use super::input::Action;
+use super::screen::{frame::*, layout::Rect};
+
+// The parts of a game that can be run without a terminal or real time passing
+pub trait Simulation {
+	fn apply(&mut self, action: Action);
+	fn tick(&mut self);
+	fn draw(&self);
+	fn is_finished(&self) -> bool;
+}
+
+// Steps a simulation with scripted input, so game logic can be tested
+pub struct Harness<S: Simulation> {
+	pub state: S,
+	pub size: (u16, u16),
+	pub ticks: u32
+}
+
+// A board of the given size inside a border at the top left, and the status line under it, the
+// way tests lay out most games
+pub fn board_rects(size: (u16, u16)) -> (Rect, Rect) {
+	(Rect::new((2, 2), size), Rect::new((1, size.1 + 3), (size.0 + 2, 1)))
+}
+
+impl<S: Simulation> Harness<S> {
+	pub fn new(state: S, size: (u16, u16)) -> Harness<S> {
+		Harness {
+			state,
+			size,
+			ticks: 0
+		}
+	}
+	// On a screen just big enough for the board_rects of the given size
+	pub fn for_board(state: S, size: (u16, u16)) -> Harness<S> {
+		Harness::new(state, (size.0 + 2, size.1 + 3))
+	}
+	pub fn press(&mut self, action: Action) -> &mut Self {
+		self.state.apply(action);
+		self
+	}
+	// Does nothing once the game has finished
+	pub fn step(&mut self) -> &mut Self {
+		if !self.state.is_finished() {
+			self.state.tick();
+			self.ticks += 1;
+		}
+		self
+	}
+	pub fn run(&mut self, ticks: u32) -> &mut Self {
+		for _ in 0..ticks {
+			self.step();
+		}
+		self
+	}
+	// Each action is pressed just before the tick with the same number, counting from 0
+	pub fn run_script(&mut self, script: &[(u32, Action)]) -> &mut Self {
+		let last_tick = script.iter().map(|(tick, _)| *tick).max().unwrap_or(0);
+		for tick in 0..=last_tick {
+			for (_, action) in script.iter().filter(|(at, _)| *at == tick) {
+				self.press(*action);
+			}
+			self.step();
+		}
+		self
+	}
+	// Whether the game finished within the given number of ticks
+	pub fn run_until_finished(&mut self, max_ticks: u32) -> bool {
+		for _ in 0..max_ticks {
+			if self.state.is_finished() {break};
+			self.step();
+		}
+		self.state.is_finished()
+	}
+	pub fn frame(&self) -> Frame {
+		capture(self.size, || self.state.draw())
+	}
+}
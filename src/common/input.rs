@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread::JoinHandle;
+
+use console::Key;
+use termion::event::{parse_event, Event, MouseButton, MouseEvent};
+
+use super::config::current_config;
+use super::terminal::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+	MoveUp,
+	MoveDown,
+	MoveLeft,
+	MoveRight,
+	RotateLeft,
+	RotateRight,
+	// Straight to the bottom
+	Drop,
+	Hold,
+	Undo,
+	// Marks a hidden square, like a suspected mine
+	Flag,
+	Pause,
+	Confirm,
+	Back
+}
+
+impl Action {
+	pub fn all() -> [Action; 13] {
+		[
+			Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight,
+			Action::RotateLeft, Action::RotateRight, Action::Drop, Action::Hold, Action::Undo, Action::Flag,
+			Action::Pause, Action::Confirm, Action::Back
+		]
+	}
+	// Name used in the config file
+	pub fn name(&self) -> &'static str {
+		match self {
+			Action::MoveUp => "move_up",
+			Action::MoveDown => "move_down",
+			Action::MoveLeft => "move_left",
+			Action::MoveRight => "move_right",
+			Action::RotateLeft => "rotate_left",
+			Action::RotateRight => "rotate_right",
+			Action::Drop => "drop",
+			Action::Hold => "hold",
+			Action::Undo => "undo",
+			Action::Flag => "flag",
+			Action::Pause => "pause",
+			Action::Confirm => "confirm",
+			Action::Back => "back"
+		}
+	}
+	pub fn label(&self) -> &'static str {
+		match self {
+			Action::MoveUp => "Move up",
+			Action::MoveDown => "Move down",
+			Action::MoveLeft => "Move left",
+			Action::MoveRight => "Move right",
+			Action::RotateLeft => "Rotate left",
+			Action::RotateRight => "Rotate right",
+			Action::Drop => "Drop",
+			Action::Hold => "Hold",
+			Action::Undo => "Undo",
+			Action::Flag => "Flag",
+			Action::Pause => "Pause",
+			Action::Confirm => "Confirm",
+			Action::Back => "Back"
+		}
+	}
+	pub fn from_name(name: &str) -> Option<Action> {
+		Action::all().into_iter().find(|action| action.name() == name)
+	}
+}
+
+// Mouse reports are short, anything longer is something else
+static MAX_MOUSE_REPORT: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputKey {
+	Char(char),
+	Up,
+	Down,
+	Left,
+	Right,
+	Enter,
+	Escape,
+	Space,
+	Tab,
+	Backspace
+}
+
+static NAMED_KEYS: [(&str, InputKey); 9] = [
+	("up", InputKey::Up),
+	("down", InputKey::Down),
+	("left", InputKey::Left),
+	("right", InputKey::Right),
+	("enter", InputKey::Enter),
+	("escape", InputKey::Escape),
+	("space", InputKey::Space),
+	("tab", InputKey::Tab),
+	("backspace", InputKey::Backspace)
+];
+
+impl InputKey {
+	// Letters are case insensitive, so they're always stored lowercase
+	pub fn parse(name: &str) -> Option<InputKey> {
+		let lower = name.to_lowercase();
+		if let Some((_, key)) = NAMED_KEYS.iter().find(|(key_name, _)| *key_name == lower) {
+			return Some(*key);
+		}
+		if lower == "esc" {
+			return Some(InputKey::Escape);
+		}
+		let mut chars = lower.chars();
+		match (chars.next(), chars.next()) {
+			(Some(' '), None) => Some(InputKey::Space),
+			(Some(c), None) if !c.is_control() => Some(InputKey::Char(c)),
+			_ => None
+		}
+	}
+	pub fn from_console_key(key: Key) -> Option<InputKey> {
+		match key {
+			Key::ArrowUp => Some(InputKey::Up),
+			Key::ArrowDown => Some(InputKey::Down),
+			Key::ArrowLeft => Some(InputKey::Left),
+			Key::ArrowRight => Some(InputKey::Right),
+			Key::Enter => Some(InputKey::Enter),
+			Key::Escape => Some(InputKey::Escape),
+			Key::Tab => Some(InputKey::Tab),
+			Key::Backspace => Some(InputKey::Backspace),
+			Key::Char(' ') => Some(InputKey::Space),
+			Key::Char(c) if !c.is_control() => c.to_lowercase().next().map(InputKey::Char),
+			_ => None
+		}
+	}
+}
+
+impl fmt::Display for InputKey {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			InputKey::Char(c) => write!(f, "{}", c),
+			key => {
+				let name = NAMED_KEYS.iter().find(|(_, named)| named == key).map(|(name, _)| *name).unwrap_or("?");
+				write!(f, "{}", name)
+			}
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BindingError {
+	UnknownGame(String),
+	UnknownAction(String, String),
+	UnknownKey(String, String),
+	Conflict(String, InputKey, Vec<Action>)
+}
+
+impl fmt::Display for BindingError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BindingError::UnknownGame(game) => write!(f, "unknown game \"{}\" in key bindings", game),
+			BindingError::UnknownAction(game, action) => write!(f, "unknown action \"{}\" in {} key bindings", action, game),
+			BindingError::UnknownKey(game, key) => write!(f, "unknown key \"{}\" in {} key bindings", key, game),
+			BindingError::Conflict(game, key, actions) => {
+				let names = actions.iter().map(|action| action.name()).collect::<Vec<&str>>();
+				write!(f, "key \"{}\" is bound to {} in {} key bindings", key, names.join(" and "), game)
+			}
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bindings {
+	pub game: String,
+	pub actions: Vec<(Action, Vec<InputKey>)>
+}
+
+impl Bindings {
+	// Default key names are written by hand, so a typo in one is a bug
+	pub fn new(game: &str, defaults: &[(Action, &[&str])]) -> Bindings {
+		Bindings {
+			game: game.to_owned(),
+			actions: defaults.iter().map(|(action, keys)| {
+				(*action, keys.iter().map(|key| InputKey::parse(key).expect("invalid default key")).collect())
+			}).collect()
+		}
+	}
+	// Each overridden action has its keys replaced, the rest keep their defaults
+	pub fn with_overrides(&self, overrides: &BTreeMap<String, Vec<String>>) -> Result<Bindings, BindingError> {
+		let mut bindings = self.clone();
+		for (action_name, key_names) in overrides {
+			let action = Action::from_name(action_name)
+				.ok_or_else(|| BindingError::UnknownAction(self.game.clone(), action_name.clone()))?;
+			let keys = key_names.iter().map(|name| {
+				InputKey::parse(name).ok_or_else(|| BindingError::UnknownKey(self.game.clone(), name.clone()))
+			}).collect::<Result<Vec<InputKey>, BindingError>>()?;
+			match bindings.actions.iter_mut().find(|(bound_action, _)| *bound_action == action) {
+				Some((_, bound_keys)) => *bound_keys = keys,
+				None => bindings.actions.push((action, keys))
+			}
+		}
+		match bindings.conflicts().into_iter().next() {
+			Some((key, actions)) => Err(BindingError::Conflict(self.game.clone(), key, actions)),
+			None => Ok(bindings)
+		}
+	}
+	pub fn conflicts(&self) -> Vec<(InputKey, Vec<Action>)> {
+		let mut conflicts: Vec<(InputKey, Vec<Action>)> = vec![];
+		for (action, keys) in &self.actions {
+			for key in keys {
+				if conflicts.iter().any(|(conflict_key, _)| conflict_key == key) {continue};
+				let actions = self.actions.iter()
+					.filter(|(_, other_keys)| other_keys.contains(key))
+					.map(|(other_action, _)| *other_action)
+					.collect::<Vec<Action>>();
+				if actions.len() > 1 && actions[0] == *action {
+					conflicts.push((*key, actions));
+				}
+			}
+		}
+		conflicts
+	}
+	pub fn action_for(&self, key: InputKey) -> Option<Action> {
+		self.actions.iter().find(|(_, keys)| keys.contains(&key)).map(|(action, _)| *action)
+	}
+	pub fn keys_for(&self, action: Action) -> &[InputKey] {
+		self.actions.iter().find(|(bound_action, _)| *bound_action == action).map(|(_, keys)| keys.as_slice()).unwrap_or(&[])
+	}
+	pub fn describe_keys(&self, action: Action) -> String {
+		self.keys_for(action).iter().map(|key| key.to_string()).collect::<Vec<String>>().join(", ")
+	}
+	// The game's defaults with the user's overrides from the config applied
+	pub fn load(&self) -> Result<Bindings, BindingError> {
+		match current_config().key_bindings.get(&self.game) {
+			Some(overrides) => self.with_overrides(overrides),
+			None => Ok(self.clone())
+		}
+	}
+	// Bad overrides are reported at startup, so by now just fall back to the defaults
+	pub fn load_or_default(&self) -> Bindings {
+		self.load().unwrap_or_else(|_| self.clone())
+	}
+}
+
+pub fn menu_bindings() -> Bindings {
+	Bindings::new("menu", &[
+		(Action::MoveUp, &["up"]),
+		(Action::MoveDown, &["down"]),
+		(Action::Confirm, &["enter"])
+	])
+}
+
+// Any key at all, including ones that can't be bound, from the session's terminal if there is one
+pub fn read_console_key() -> Option<Key> {
+	match current_terminal() {
+		Some(terminal) => terminal.read_key(),
+		None => console::Term::stdout().read_key().ok()
+	}
+}
+
+// A key, or a mouse button pressed at one based screen coordinates like the ones drawn at.
+// Clicks only arrive while mouse reporting is turned on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+	Key(InputKey),
+	Click(MouseButton, (u16, u16))
+}
+
+// The sequence after the escape, from terminals reporting in the SGR format. None for releases, drags and the wheel.
+// Termion panics on reports that aren't complete, so they're checked first
+pub fn parse_mouse_report(sequence: &[char]) -> Option<Input> {
+	let text = sequence.iter().collect::<String>();
+	let fields = text.strip_prefix("[<")?.strip_suffix(['M', 'm'])?;
+	let numbers = fields.split(';').map(|field| field.parse::<u16>().ok().filter(|_| field.bytes().all(|b| b.is_ascii_digit())));
+	if numbers.collect::<Option<Vec<u16>>>()?.len() != 3 {return None};
+	let mut bytes = text.bytes().map(Ok);
+	match parse_event(0x1b, &mut bytes) {
+		Ok(Event::Mouse(MouseEvent::Press(button @ (MouseButton::Left | MouseButton::Middle | MouseButton::Right), x, y))) => {
+			Some(Input::Click(button, (x, y)))
+		},
+		_ => None
+	}
+}
+
+// Console only reads the first few characters of a sequence it doesn't know, the rest come as characters
+fn read_mouse_report(mut sequence: Vec<char>) -> Option<Vec<char>> {
+	while !matches!(sequence.last(), Some('M' | 'm')) && sequence.len() < MAX_MOUSE_REPORT {
+		match read_console_key()? {
+			Key::Char(c) => sequence.push(c),
+			_ => break
+		}
+	}
+	Some(sequence)
+}
+
+// Blocks until a key is pressed or the mouse is clicked, None once input has closed
+pub fn read_input() -> Option<Input> {
+	loop {
+		match read_console_key()? {
+			Key::UnknownEscSeq(sequence) if sequence.starts_with(&['[', '<']) => {
+				if let Some(click) = parse_mouse_report(&read_mouse_report(sequence)?) {return Some(click)};
+			},
+			key => if let Some(input_key) = InputKey::from_console_key(key) {return Some(Input::Key(input_key))}
+		}
+	}
+}
+
+// Blocks until a key is pressed, None once input has closed
+pub fn read_key() -> Option<InputKey> {
+	loop {
+		if let Input::Key(key) = read_input()? {return Some(key)};
+	}
+}
+
+// Reads keys and clicks on another thread until the handler returns false or input closes
+pub fn spawn_event_handler<F>(mut handler: F) -> JoinHandle<()> where F: FnMut(Input) -> bool + Send + 'static {
+	spawn_in_session(move || {
+		while let Some(input) = read_input() {
+			if !handler(input) {break;}
+		}
+	})
+}
+
+// Reads keys on another thread until the handler returns false or input closes
+pub fn spawn_input_handler<F>(mut handler: F) -> JoinHandle<()> where F: FnMut(InputKey) -> bool + Send + 'static {
+	spawn_event_handler(move |input| match input {
+		Input::Key(key) => handler(key),
+		Input::Click(..) => true
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_mouse_presses() {
+		let report = |text: &str| parse_mouse_report(&text.chars().collect::<Vec<char>>());
+		assert_eq!(report("[<0;12;5M"), Some(Input::Click(MouseButton::Left, (12, 5))));
+		assert_eq!(report("[<2;1;1M"), Some(Input::Click(MouseButton::Right, (1, 1))));
+		assert_eq!(report("[<1;30;2M"), Some(Input::Click(MouseButton::Middle, (30, 2))));
+		// Release and wheel
+		assert_eq!(report("[<0;12;5m"), None);
+		assert_eq!(report("[<64;12;5M"), None);
+		assert_eq!(report("[<0;12"), None);
+		assert_eq!(report("[<0;12;M"), None);
+		assert_eq!(report("[<0;+1;99999M"), None);
+	}
+}
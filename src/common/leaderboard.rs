@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::*;
+use super::daily::{stable_hash, Date, DAYS_KEPT};
+use super::game::{GameReturn, GameSetup};
+use super::net::*;
+use super::registry::{GameEntry, Registry};
+
+// Scores kept for each game
+pub static LOCAL_LIMIT: usize = 10;
+pub static SERVER_LIMIT: usize = 100;
+static TIMEOUT: Duration = Duration::from_secs(2);
+// How long the server waits for a client's next request before hanging up
+static CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScoreEntry {
+	pub player: String,
+	pub score: f32,
+	// Hex, toml integers can't hold every seed
+	pub seed: String,
+	pub settings: String,
+	// Identifies the run, so the same result sent twice is only kept once
+	pub replay_hash: String
+}
+
+impl ScoreEntry {
+	// None for games that ended without a score for the player
+	pub fn new(entry: &GameEntry, setup: &GameSetup, game_return: &GameReturn) -> Option<ScoreEntry> {
+		let score = game_return.score.player_score()?;
+		let player = clean_field(&setup.config.player_name);
+		let seed = format!("{:016x}", setup.seed);
+		let settings = entry.settings_text(&setup.config);
+		let replay_hash = format!("{:016x}", stable_hash(&format!("{}|{}|{}|{}|{}", entry.id, player, score, seed, settings)));
+		Some(ScoreEntry { player, score, seed, settings, replay_hash })
+	}
+}
+
+// Fields are sent tab separated, one message per line
+fn clean_field(field: &str) -> String {
+	field.replace(['\t', '\r', '\n'], " ")
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct HighScores {
+	// Table, the game id or daily_table for the daily challenge -> scores, highest first
+	pub games: BTreeMap<String, Vec<ScoreEntry>>
+}
+
+// Each day's daily challenge results for a game go in a table of their own
+pub fn daily_table(game_id: &str, date: Date) -> String {
+	format!("{}@{}", game_id, date)
+}
+
+// The game a table is for, and the date if it's a daily table
+fn split_table(table: &str) -> (&str, Option<&str>) {
+	match table.split_once('@') {
+		Some((game_id, date)) => (game_id, Some(date)),
+		None => (table, None)
+	}
+}
+
+// Daily results are only taken for today, give or take a day for games played over midnight
+fn is_current_date(date: &str) -> bool {
+	let today = Date::today().days_since_epoch();
+	(today.saturating_sub(1)..=today + 1).any(|days| Date::from_days(days).to_string() == date)
+}
+
+pub fn get_high_scores_path() -> Option<PathBuf> {
+	get_data_dir().map(|dir| dir.join("highscores.toml"))
+}
+
+// Where `leaderboard-server` keeps everyone's scores unless told otherwise
+pub fn get_leaderboard_server_path() -> Option<PathBuf> {
+	get_data_dir().map(|dir| dir.join("leaderboard-server.toml"))
+}
+
+impl HighScores {
+	pub fn load() -> Result<HighScores, ConfigError> {
+		load_toml(get_high_scores_path().ok_or(ConfigError::NoDataDir)?)
+	}
+	pub fn save(&self) -> Result<(), ConfigError> {
+		save_toml(get_high_scores_path().ok_or(ConfigError::NoDataDir)?, self)
+	}
+	pub fn top(&self, game_id: &str, count: usize) -> Vec<ScoreEntry> {
+		self.games.get(game_id).map(|scores| scores.iter().take(count).cloned().collect()).unwrap_or_default()
+	}
+	// Place in the table counting from 1, None if the score didn't make it or was already there
+	pub fn add(&mut self, game_id: &str, score: ScoreEntry, limit: usize) -> Option<usize> {
+		let scores = self.games.entry(game_id.to_owned()).or_default();
+		if scores.iter().any(|kept| kept.replay_hash == score.replay_hash) {return None};
+		// Ties go to whoever got there first
+		let place = scores.iter().position(|kept| kept.score < score.score).unwrap_or(scores.len());
+		if place >= limit {return None};
+		scores.insert(place, score);
+		scores.truncate(limit);
+		Some(place + 1)
+	}
+	pub fn has_player(&self, table: &str, player: &str) -> bool {
+		self.games.get(table).is_some_and(|scores| scores.iter().any(|kept| kept.player == player))
+	}
+	// Drops daily tables from before the days kept, dates sort the same as text
+	fn remove_old_days(&mut self) {
+		let oldest = Date::from_days(Date::today().days_since_epoch().saturating_sub(DAYS_KEPT as u64)).to_string();
+		self.games.retain(|table, _| split_table(table).1.is_none_or(|date| date >= oldest.as_str()));
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+	Submit(String, ScoreEntry),
+	Top(String, usize),
+	Placed(usize),
+	Score(ScoreEntry),
+	End,
+	Error(String)
+}
+
+impl Message {
+	fn encode(&self) -> String {
+		let score_fields = |score: &ScoreEntry| format!(
+			"{}\t{}\t{}\t{}\t{}",
+			clean_field(&score.player), score.score, score.seed, clean_field(&score.settings), score.replay_hash
+		);
+		match self {
+			Message::Submit(game_id, score) => format!("SUBMIT\t{}\t{}", game_id, score_fields(score)),
+			Message::Top(game_id, count) => format!("TOP\t{}\t{}", game_id, count),
+			// 0 when the score didn't make the table
+			Message::Placed(place) => format!("PLACED\t{}", place),
+			Message::Score(score) => format!("SCORE\t{}", score_fields(score)),
+			Message::End => "END".to_owned(),
+			Message::Error(message) => format!("ERROR\t{}", clean_field(message))
+		}
+	}
+	fn decode(line: &str) -> Result<Message, NetError> {
+		let fields = line.split('\t').collect::<Vec<&str>>();
+		let bad = || NetError::Protocol(line.to_owned());
+		let score = |fields: &[&str]| match fields {
+			[player, score, seed, settings, replay_hash] => Ok(ScoreEntry {
+				player: player.to_string(),
+				// Infinity and NaN would sort above every real score
+				score: score.parse().ok().filter(|score: &f32| score.is_finite()).ok_or_else(bad)?,
+				seed: seed.to_string(),
+				settings: settings.to_string(),
+				replay_hash: replay_hash.to_string()
+			}),
+			_ => Err(bad())
+		};
+		match fields[..] {
+			["SUBMIT", game_id, ref rest @ ..] => Ok(Message::Submit(game_id.to_owned(), score(rest)?)),
+			["TOP", game_id, count] => Ok(Message::Top(game_id.to_owned(), count.parse().map_err(|_| bad())?)),
+			["PLACED", place] => Ok(Message::Placed(place.parse().map_err(|_| bad())?)),
+			["SCORE", ref rest @ ..] => Ok(Message::Score(score(rest)?)),
+			["END"] => Ok(Message::End),
+			["ERROR", message] => Ok(Message::Error(message.to_owned())),
+			_ => Err(bad())
+		}
+	}
+}
+
+fn open(addr: &str) -> Result<(LineReader, LineWriter), NetError> {
+	let (reader, writer) = split_lines(connect(addr, TIMEOUT)?)?;
+	reader.set_timeout(Some(TIMEOUT))?;
+	Ok((reader, writer))
+}
+
+fn recv(reader: &mut LineReader) -> Result<Message, NetError> {
+	match Message::decode(&reader.recv()?)? {
+		Message::Error(message) => Err(NetError::Protocol(message)),
+		message => Ok(message)
+	}
+}
+
+// Sends the score to the server, returning its place on the server's table
+pub fn submit_score(addr: &str, table: &str, score: &ScoreEntry) -> Result<Option<usize>, NetError> {
+	let (mut reader, mut writer) = open(addr)?;
+	writer.send(&Message::Submit(table.to_owned(), score.clone()).encode())?;
+	match recv(&mut reader)? {
+		Message::Placed(place) => Ok(if place > 0 {Some(place)} else {None}),
+		message => Err(NetError::Protocol(message.encode()))
+	}
+}
+
+pub fn fetch_top(addr: &str, table: &str, count: usize) -> Result<Vec<ScoreEntry>, NetError> {
+	let (mut reader, mut writer) = open(addr)?;
+	writer.send(&Message::Top(table.to_owned(), count).encode())?;
+	let mut scores = vec![];
+	loop {
+		match recv(&mut reader)? {
+			Message::Score(score) => scores.push(score),
+			Message::End => return Ok(scores),
+			message => return Err(NetError::Protocol(message.encode()))
+		}
+	}
+}
+
+// Answers one client's requests until it disconnects, saving the table after each new score.
+// Only games in the registry have tables, and each player gets one result in a daily table
+pub fn handle_client(stream: TcpStream, registry: &Registry, scores: &Mutex<HighScores>, path: &Path) -> Result<(), NetError> {
+	let (mut reader, mut writer) = split_lines(stream)?;
+	reader.set_timeout(Some(CLIENT_TIMEOUT))?;
+	loop {
+		let line = match reader.recv() {
+			Err(NetError::Closed) => return Ok(()),
+			result => result?
+		};
+		match Message::decode(&line) {
+			Ok(Message::Submit(ref table, _) | Message::Top(ref table, _)) if registry.find(split_table(table).0).is_none() => {
+				writer.send(&Message::Error(format!("unknown game \"{}\"", split_table(table).0)).encode())?
+			},
+			Ok(Message::Submit(table, _)) if split_table(&table).1.is_some_and(|date| !is_current_date(date)) => {
+				writer.send(&Message::Error(format!("\"{}\" isn't today's challenge", table)).encode())?
+			},
+			Ok(Message::Submit(table, score)) if split_table(&table).1.is_some() && scores.lock().unwrap().has_player(&table, &score.player) => {
+				writer.send(&Message::Error(format!("{} has already played today's challenge", score.player)).encode())?
+			},
+			Ok(Message::Submit(table, score)) => {
+				let mut scores = scores.lock().unwrap();
+				let place = scores.add(&table, score, SERVER_LIMIT);
+				if split_table(&table).1.is_some() {
+					scores.remove_old_days();
+				}
+				if place.is_some() {
+					if let Err(err) = save_toml(path.to_path_buf(), &*scores) {
+						writer.send(&Message::Error(err.to_string()).encode())?;
+						continue;
+					}
+				}
+				writer.send(&Message::Placed(place.unwrap_or(0)).encode())?;
+			},
+			Ok(Message::Top(table, count)) => {
+				let top = scores.lock().unwrap().top(&table, count);
+				for score in top {
+					writer.send(&Message::Score(score).encode())?;
+				}
+				writer.send(&Message::End.encode())?;
+			},
+			_ => writer.send(&Message::Error(format!("unexpected message \"{}\"", line)).encode())?
+		}
+	}
+}
+
+// For clients that arrive while the server is full
+pub fn turn_away(stream: TcpStream) {
+	if let Ok((_, mut writer)) = split_lines(stream) {
+		let _ = writer.send(&Message::Error("too many clients, try again later".to_owned()).encode());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::TcpListener;
+	use std::sync::Arc;
+	use std::thread;
+
+	use super::*;
+	use crate::common::game::{Score, WinState};
+	use crate::games::snake;
+
+	fn score(player: &str, score: f32) -> ScoreEntry {
+		ScoreEntry {
+			player: player.to_owned(),
+			score,
+			seed: "00000000000000ff".to_owned(),
+			settings: "30x10, 50ms".to_owned(),
+			replay_hash: format!("{}-{}", player, score)
+		}
+	}
+
+	#[test]
+	fn keeps_the_highest_scores() {
+		let mut scores = HighScores::default();
+		assert_eq!(scores.add("snake", score("Ada", 5.0), 3), Some(1));
+		assert_eq!(scores.add("snake", score("Grace", 9.0), 3), Some(1));
+		assert_eq!(scores.add("snake", score("Alan", 5.0), 3), Some(3));
+		assert_eq!(scores.add("snake", score("Ada", 5.0), 3), None);
+		assert_eq!(scores.add("snake", score("Edsger", 1.0), 3), None);
+		assert_eq!(scores.add("snake", score("Barbara", 7.0), 3), Some(2));
+		let players = scores.top("snake", 10).into_iter().map(|score| score.player).collect::<Vec<String>>();
+		assert_eq!(players, ["Grace", "Barbara", "Ada"]);
+		assert!(scores.top("pong", 10).is_empty());
+	}
+
+	#[test]
+	fn skips_games_between_two_people() {
+		let entry = snake::get_game_entry();
+		let setup = GameSetup { config: Config::default(), seed: 1 };
+		let against_computer = GameReturn::new(Score::TwoPlayer(3.0, 1.0), WinState::Win);
+		assert_eq!(ScoreEntry::new(&entry, &setup, &against_computer).map(|entry| entry.score), Some(3.0));
+		let hot_seat = GameReturn::new(Score::Versus(3.0, 1.0), WinState::Win);
+		assert_eq!(ScoreEntry::new(&entry, &setup, &hot_seat), None);
+	}
+
+	#[test]
+	fn messages_survive_encoding() {
+		let messages = [
+			Message::Submit("snake".to_owned(), score("Ada Lovelace", 12.5)),
+			Message::Top("pong".to_owned(), 10),
+			Message::Placed(3),
+			Message::Score(score("Grace", 4.0)),
+			Message::End,
+			Message::Error("couldn't save".to_owned())
+		];
+		for message in messages {
+			assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+		}
+		assert!(Message::decode("TOP\tsnake").is_err());
+		assert!(Message::decode("SCORE\tAda\tlots\t0\t\t0").is_err());
+		assert!(Message::decode("SUBMIT\tsnake\tAda\tinf\t0\t\t0").is_err());
+		assert!(Message::decode("SUBMIT\tsnake\tAda\tNaN\t0\t\t0").is_err());
+	}
+
+	// A server for snake on a free port, saving to a file named after the test
+	fn start_server(name: &str) -> (String, Arc<Mutex<HighScores>>, PathBuf) {
+		let path = std::env::temp_dir().join(format!("terminal-games-{}-{}.toml", name, std::process::id()));
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		let scores = Arc::new(Mutex::new(HighScores::default()));
+		let serving = scores.clone();
+		let server_path = path.clone();
+		thread::spawn(move || {
+			let mut registry = Registry::new();
+			registry.register(snake::get_game_entry()).unwrap();
+			for stream in listener.incoming().flatten() {
+				let _ = handle_client(stream, &registry, &serving, &server_path);
+			}
+		});
+		(addr, scores, path)
+	}
+
+	#[test]
+	fn submits_and_fetches_from_a_local_server() {
+		let (addr, scores, path) = start_server("leaderboard");
+		assert_eq!(submit_score(&addr, "snake", &score("Ada", 5.0)).unwrap(), Some(1));
+		assert_eq!(submit_score(&addr, "snake", &score("Grace", 8.0)).unwrap(), Some(1));
+		assert_eq!(submit_score(&addr, "snake", &score("Grace", 8.0)).unwrap(), None);
+		let top = fetch_top(&addr, "snake", 10).unwrap();
+		assert_eq!(top, [score("Grace", 8.0), score("Ada", 5.0)]);
+		assert_eq!(fetch_top(&addr, "snake", 1).unwrap().len(), 1);
+		assert!(submit_score(&addr, "made-up", &score("Ada", 5.0)).is_err());
+		assert!(fetch_top(&addr, "made-up", 10).is_err());
+		let saved: HighScores = load_toml(path.clone()).unwrap();
+		assert_eq!(saved, *scores.lock().unwrap());
+		let _ = std::fs::remove_file(path);
+	}
+
+	#[test]
+	fn daily_tables_take_one_result_per_player_for_today() {
+		let (addr, scores, path) = start_server("daily-leaderboard");
+		let today = Date::today();
+		let table = daily_table("snake", today);
+		assert_eq!(submit_score(&addr, &table, &score("Ada", 5.0)).unwrap(), Some(1));
+		assert!(submit_score(&addr, &table, &score("Ada", 9.0)).is_err());
+		assert_eq!(submit_score(&addr, &table, &score("Grace", 8.0)).unwrap(), Some(1));
+		assert_eq!(fetch_top(&addr, &table, 10).unwrap(), [score("Grace", 8.0), score("Ada", 5.0)]);
+		let last_week = Date::from_days(today.days_since_epoch() - 7);
+		assert!(submit_score(&addr, &daily_table("snake", last_week), &score("Ada", 5.0)).is_err());
+		assert!(submit_score(&addr, &daily_table("made-up", today), &score("Ada", 5.0)).is_err());
+		assert!(fetch_top(&addr, "snake", 10).unwrap().is_empty());
+
+		let long_ago = daily_table("snake", Date::from_days(today.days_since_epoch() - DAYS_KEPT as u64 - 1));
+		scores.lock().unwrap().add(&long_ago, score("Ada", 1.0), SERVER_LIMIT);
+		submit_score(&addr, &table, &score("Alan", 1.0)).unwrap();
+		assert!(!scores.lock().unwrap().games.contains_key(&long_ago));
+		let _ = std::fs::remove_file(path);
+	}
+
+	#[test]
+	fn unreachable_server() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		drop(listener);
+		assert!(fetch_top(&addr, "snake", 10).is_err());
+	}
+}
@@ -1,2 +1,18 @@
+pub mod achievements;
+pub(crate) mod ball;
+pub mod config;
+pub(crate) mod daily;
 pub mod game;
-pub mod screen;
\ No newline at end of file
+pub mod game_loop;
+pub mod headless;
+pub mod input;
+pub mod leaderboard;
+pub(crate) mod net;
+pub mod registry;
+pub mod screen;
+pub mod snapshot;
+pub mod spectate;
+pub(crate) mod stats;
+pub(crate) mod telnet;
+pub(crate) mod terminal;
+pub(crate) mod tournament;
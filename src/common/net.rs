@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// Longest line either end will read, so a peer that never sends a newline can't use up memory
+static MAX_LINE_LENGTH: usize = 4096;
+
+#[derive(Debug)]
+pub enum NetError {
+	Io(io::Error),
+	// The other end sent something that doesn't follow the protocol
+	Protocol(String),
+	NoAddress(String),
+	TooLong,
+	Closed
+}
+
+impl fmt::Display for NetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			NetError::Io(err) => write!(f, "{}", err),
+			NetError::Protocol(message) => write!(f, "unexpected message \"{}\"", message),
+			NetError::NoAddress(addr) => write!(f, "couldn't find an address for {}", addr),
+			NetError::TooLong => write!(f, "line longer than {} bytes", MAX_LINE_LENGTH),
+			NetError::Closed => write!(f, "connection closed")
+		}
+	}
+}
+
+impl From<io::Error> for NetError {
+	fn from(err: io::Error) -> NetError {
+		NetError::Io(err)
+	}
+}
+
+pub struct LineReader {
+	reader: BufReader<TcpStream>
+}
+
+impl LineReader {
+	// Without the line ending, Closed once the other end has gone
+	pub fn recv(&mut self) -> Result<String, NetError> {
+		let mut line = String::new();
+		match (&mut self.reader).take(MAX_LINE_LENGTH as u64 + 1).read_line(&mut line)? {
+			0 => Err(NetError::Closed),
+			length if length > MAX_LINE_LENGTH && !line.ends_with('\n') => Err(NetError::TooLong),
+			_ => Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+		}
+	}
+	pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+		self.reader.get_ref().set_read_timeout(timeout)
+	}
+}
+
+pub struct LineWriter {
+	stream: TcpStream
+}
+
+impl LineWriter {
+	pub fn send(&mut self, line: &str) -> Result<(), NetError> {
+		self.stream.write_all(format!("{}\n", line).as_bytes())?;
+		Ok(())
+	}
+	// A write that can't finish in time fails rather than blocking its thread forever
+	pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+		self.stream.set_write_timeout(timeout)
+	}
+	// Also wakes up the reader on this end, which then sees the connection as closed
+	pub fn close(&self) {
+		let _ = self.stream.shutdown(Shutdown::Both);
+	}
+}
+
+// Newline separated text messages, read and written from separate threads
+pub fn split_lines(stream: TcpStream) -> io::Result<(LineReader, LineWriter)> {
+	stream.set_nodelay(true)?;
+	let writer = LineWriter {
+		stream: stream.try_clone()?
+	};
+	Ok((LineReader { reader: BufReader::new(stream) }, writer))
+}
+
+pub fn connect(addr: &str, timeout: Duration) -> Result<TcpStream, NetError> {
+	let mut last_err = NetError::NoAddress(addr.to_owned());
+	for socket_addr in addr.to_socket_addrs()? {
+		match TcpStream::connect_timeout(&socket_addr, timeout) {
+			Ok(stream) => return Ok(stream),
+			Err(err) => last_err = NetError::Io(err)
+		}
+	}
+	Err(last_err)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::TcpListener;
+
+	use super::*;
+
+	#[test]
+	fn rejects_overlong_lines() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (mut reader, _) = split_lines(listener.accept().unwrap().0).unwrap();
+		let fits = "a".repeat(MAX_LINE_LENGTH - 1);
+		client.write_all(format!("{}\n{}\n", fits, "b".repeat(MAX_LINE_LENGTH + 1)).as_bytes()).unwrap();
+		assert_eq!(reader.recv().unwrap(), fits);
+		assert!(matches!(reader.recv(), Err(NetError::TooLong)));
+	}
+}
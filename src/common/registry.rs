@@ -0,0 +1,174 @@
+use std::fmt;
+
+use super::achievements::Achievement;
+use super::config::Config;
+use super::game::{Game, GameSetup};
+use super::input::Bindings;
+
+// Settings shown alongside a game's scores. Apart from the shared difficulty each reads one
+// game's part of the config, and is named for that game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GameOption {
+	Difficulty,
+	SnakeBoardSize,
+	SnakeSpeed,
+	PongPointsToWin,
+	TilesGridSize,
+	Minefield,
+	TronRounds,
+	TronOpponent
+}
+
+impl GameOption {
+	pub fn name(&self) -> &'static str {
+		match self {
+			GameOption::Difficulty => "difficulty",
+			GameOption::SnakeBoardSize => "board size",
+			GameOption::SnakeSpeed => "speed",
+			GameOption::PongPointsToWin => "points to win",
+			GameOption::TilesGridSize => "grid size",
+			GameOption::Minefield => "minefield",
+			GameOption::TronRounds => "rounds",
+			GameOption::TronOpponent => "opponent"
+		}
+	}
+	// The value the option has in the config
+	pub fn describe(&self, config: &Config) -> String {
+		match self {
+			GameOption::Difficulty => config.difficulty.name().to_owned(),
+			GameOption::SnakeBoardSize => format!("{}x{}", config.snake.width, config.snake.height),
+			GameOption::SnakeSpeed => format!("{}ms", config.snake.speed),
+			GameOption::PongPointsToWin => format!("{} points", config.pong.points_to_win),
+			GameOption::TilesGridSize => format!("{0}x{0}", config.tiles.board_size()),
+			GameOption::Minefield => config.minesweeper.describe(),
+			GameOption::TronRounds => format!("best of {}", config.tron.rounds),
+			GameOption::TronOpponent => if config.tron.two_players {"second player"} else {"computer"}.to_owned()
+		}
+	}
+}
+
+// Built with GameEntry::new and the setters, so fields can be added without breaking games
+// outside this crate
+#[non_exhaustive]
+pub struct GameEntry {
+	pub id: &'static str,
+	pub name: &'static str,
+	pub description: &'static str,
+	pub min_players: u8,
+	pub max_players: u8,
+	pub options: &'static [GameOption],
+	pub version: &'static str,
+	pub create: fn(GameSetup) -> Box<dyn Game>,
+	pub default_bindings: fn() -> Bindings,
+	pub achievements: &'static [Achievement],
+	// Fixes the settings for a daily challenge, given the day number. None if there's no daily challenge
+	pub daily: Option<fn(&mut Config, u64)>
+}
+
+impl GameEntry {
+	// A one player game without options, achievements or a daily challenge until they're set
+	pub fn new(id: &'static str, name: &'static str, description: &'static str, create: fn(GameSetup) -> Box<dyn Game>, default_bindings: fn() -> Bindings) -> GameEntry {
+		GameEntry {
+			id,
+			name,
+			description,
+			min_players: 1,
+			max_players: 1,
+			options: &[],
+			version: "1.0.0",
+			create,
+			default_bindings,
+			achievements: &[],
+			daily: None
+		}
+	}
+	pub fn set_players(&mut self, min_players: u8, max_players: u8) -> &mut Self {
+		self.min_players = min_players;
+		self.max_players = std::cmp::max(min_players, max_players);
+		self
+	}
+	pub fn set_options(&mut self, options: &'static [GameOption]) -> &mut Self {
+		self.options = options;
+		self
+	}
+	pub fn set_version(&mut self, version: &'static str) -> &mut Self {
+		self.version = version;
+		self
+	}
+	pub fn set_achievements(&mut self, achievements: &'static [Achievement]) -> &mut Self {
+		self.achievements = achievements;
+		self
+	}
+	pub fn set_daily(&mut self, daily: fn(&mut Config, u64)) -> &mut Self {
+		self.daily = Some(daily);
+		self
+	}
+	pub fn players_text(&self) -> String {
+		if self.min_players == self.max_players {
+			format!("{}", self.min_players)
+		} else {
+			format!("{}-{}", self.min_players, self.max_players)
+		}
+	}
+	// What the game was played with, scores from different settings aren't really comparable
+	pub fn settings_text(&self, config: &Config) -> String {
+		self.options.iter().map(|option| option.describe(config)).collect::<Vec<String>>().join(", ")
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegistryError {
+	DuplicateId(&'static str)
+}
+
+impl fmt::Display for RegistryError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RegistryError::DuplicateId(id) => write!(f, "a game with the id \"{}\" is already registered", id)
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct Registry {
+	entries: Vec<GameEntry>
+}
+
+impl Registry {
+	pub fn new() -> Registry {
+		Registry::default()
+	}
+	pub fn register(&mut self, entry: GameEntry) -> Result<(), RegistryError> {
+		if self.find(entry.id).is_some() {
+			return Err(RegistryError::DuplicateId(entry.id));
+		}
+		self.entries.push(entry);
+		Ok(())
+	}
+	pub fn games(&self) -> &[GameEntry] {
+		&self.entries
+	}
+	// Games can be picked by id or display name, ignoring case
+	pub fn find(&self, name: &str) -> Option<&GameEntry> {
+		self.entries.iter().find(|entry| {
+			entry.id.eq_ignore_ascii_case(name) || entry.name.eq_ignore_ascii_case(name)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn options_describe_their_own_game() {
+		let mut config = Config::default();
+		config.snake.width = 30;
+		config.tron.rounds = 5;
+		config.tron.two_players = true;
+		let describe = |options: &[GameOption]| options.iter().map(|option| option.describe(&config)).collect::<Vec<String>>();
+		assert_eq!(describe(&[GameOption::TronRounds, GameOption::TronOpponent]), ["best of 5", "second player"]);
+		assert!(describe(&[GameOption::SnakeBoardSize])[0].starts_with("30x"));
+	}
+}
@@ -0,0 +1,178 @@
+use termion::color::*;
+use super::boxes::*;
+use super::layout::*;
+use super::text::*;
+use super::theme::current_theme;
+use super::util::*;
+
+pub static BIG_TEXT_HEIGHT: u16 = 5;
+static LETTER_SPACING: u16 = 1;
+
+// '#' marks a filled cell, drawn with the theme's block glyph
+static FONT: &[(char, [&str; 5])] = &[
+	('A', [" # ", "# #", "###", "# #", "# #"]),
+	('B', ["## ", "# #", "## ", "# #", "## "]),
+	('C', [" ##", "#  ", "#  ", "#  ", " ##"]),
+	('D', ["## ", "# #", "# #", "# #", "## "]),
+	('E', ["###", "#  ", "## ", "#  ", "###"]),
+	('F', ["###", "#  ", "## ", "#  ", "#  "]),
+	('G', [" ##", "#  ", "# #", "# #", " ##"]),
+	('H', ["# #", "# #", "###", "# #", "# #"]),
+	('I', ["###", " # ", " # ", " # ", "###"]),
+	('J', ["  #", "  #", "  #", "# #", " # "]),
+	('K', ["# #", "# #", "## ", "# #", "# #"]),
+	('L', ["#  ", "#  ", "#  ", "#  ", "###"]),
+	('M', ["#   #", "## ##", "# # #", "#   #", "#   #"]),
+	('N', ["#   #", "##  #", "# # #", "#  ##", "#   #"]),
+	('O', [" # ", "# #", "# #", "# #", " # "]),
+	('P', ["## ", "# #", "## ", "#  ", "#  "]),
+	('Q', [" # ", "# #", "# #", "## ", " ##"]),
+	('R', ["## ", "# #", "## ", "# #", "# #"]),
+	('S', [" ##", "#  ", " # ", "  #", "## "]),
+	('T', ["###", " # ", " # ", " # ", " # "]),
+	('U', ["# #", "# #", "# #", "# #", "###"]),
+	('V', ["# #", "# #", "# #", "# #", " # "]),
+	('W', ["#   #", "#   #", "# # #", "## ##", "#   #"]),
+	('X', ["# #", "# #", " # ", "# #", "# #"]),
+	('Y', ["# #", "# #", " # ", " # ", " # "]),
+	('Z', ["###", "  #", " # ", "#  ", "###"]),
+	('0', ["###", "# #", "# #", "# #", "###"]),
+	('1', [" # ", "## ", " # ", " # ", "###"]),
+	('2', ["## ", "  #", " # ", "#  ", "###"]),
+	('3', ["## ", "  #", " # ", "  #", "## "]),
+	('4', ["# #", "# #", "###", "  #", "  #"]),
+	('5', ["###", "#  ", "## ", "  #", "## "]),
+	('6', [" ##", "#  ", "###", "# #", "###"]),
+	('7', ["###", "  #", " # ", " # ", " # "]),
+	('8', ["###", "# #", "###", "# #", "###"]),
+	('9', ["###", "# #", "###", "  #", "## "]),
+	(' ', ["  ", "  ", "  ", "  ", "  "]),
+	('!', ["#", "#", "#", " ", "#"]),
+	('?', ["## ", "  #", " # ", "   ", " # "]),
+	('.', [" ", " ", " ", " ", "#"]),
+	(',', ["  ", "  ", "  ", " #", "# "]),
+	(':', [" ", "#", " ", "#", " "]),
+	('\'', ["#", "#", " ", " ", " "]),
+	('-', ["   ", "   ", "###", "   ", "   "]),
+	('+', ["   ", " # ", "###", " # ", "   "]),
+	('=', ["   ", "###", "   ", "###", "   "]),
+	('*', ["   ", "# #", " # ", "# #", "   "]),
+	('/', ["  #", "  #", " # ", "#  ", "#  "]),
+	('(', [" #", "# ", "# ", "# ", " #"]),
+	(')', ["# ", " #", " #", " #", "# "]),
+	('_', ["   ", "   ", "   ", "   ", "###"])
+];
+
+fn get_glyph(c: char) -> &'static [&'static str; 5] {
+	let c = c.to_ascii_uppercase();
+	FONT.iter()
+		.find(|(font_char, _)| *font_char == c)
+		.or_else(|| FONT.iter().find(|(font_char, _)| *font_char == '?'))
+		.map(|(_, rows)| rows)
+		.unwrap()
+}
+
+// The rows of the text in the block font, with '#' for filled cells
+pub fn render_big_text(text: &str) -> Vec<String> {
+	(0..BIG_TEXT_HEIGHT as usize).map(|row| {
+		text.chars()
+			.map(|c| get_glyph(c)[row])
+			.collect::<Vec<&str>>()
+			.join(&repeat_str(" ", LETTER_SPACING))
+	}).collect()
+}
+
+pub fn big_text_width(text: &str) -> u16 {
+	render_big_text(text).first().map(|row| str_width(row)).unwrap_or(0)
+}
+
+pub struct BigText<'a> {
+	pub text: &'a str,
+	pub coords: (u16, u16),
+	pub fg_col: Option<&'a dyn Color>,
+	pub bg_col: Option<&'a dyn Color>,
+	pub clip: Option<Rect>,
+	pub align: Align
+}
+
+impl<'a> BigText<'a> {
+	pub fn new(text: &'a str, coords: (u16, u16)) -> BigText<'a> {
+		BigText {
+			text,
+			coords,
+			fg_col: None,
+			bg_col: None,
+			clip: None,
+			align: Align::Start
+		}
+	}
+	// Positions the text from the top of the rect and clips it to the rect
+	pub fn in_rect(text: &'a str, rect: Rect, align: Align) -> BigText<'a> {
+		let mut big_text = BigText::new(text, rect.coords());
+		big_text.clip = Some(rect);
+		big_text.align = align;
+		big_text
+	}
+	pub fn color_fg(&mut self, col: &'a dyn Color) -> &mut Self {
+		self.fg_col = Some(col);
+		self
+	}
+	pub fn color_bg(&mut self, col: &'a dyn Color) -> &mut Self {
+		self.bg_col = Some(col);
+		self
+	}
+	pub fn clip_to(&mut self, rect: Rect) -> &mut Self {
+		self.clip = Some(rect);
+		self
+	}
+	pub fn size(&self) -> (u16, u16) {
+		(big_text_width(self.text), BIG_TEXT_HEIGHT)
+	}
+	pub fn fits(&self, rect: Rect) -> bool {
+		let (w, h) = self.size();
+		w <= rect.width && h <= rect.height
+	}
+	pub fn print(&self) {
+		let theme = current_theme();
+		let fg_col = Some(self.fg_col.unwrap_or(&theme.accent));
+		let bg_col = Some(self.bg_col.unwrap_or(&theme.fill));
+		let x = match self.clip {
+			Some(rect) => rect.x + self.align.offset(self.size().0, rect.width),
+			None => self.coords.0
+		};
+		for (i, row) in render_big_text(self.text).iter().enumerate() {
+			let line = row.replace('#', theme.glyphs.block);
+			print_clipped(&line, (x, self.coords.1 + i as u16), self.clip, bg_col, fg_col);
+		}
+	}
+}
+
+// A boxed banner in the middle of the screen, falling back to plain text when it won't fit
+pub fn print_splash(title: &str, subtitle: &str) {
+	let screen = Rect::screen();
+	let big_title = BigText::new(title, (1, 1));
+	let use_big = big_title.size().0 + 4 <= screen.width && BIG_TEXT_HEIGHT + 5 <= screen.height;
+	let title_size = if use_big {big_title.size()} else {(str_width(title), 1)};
+	let size = (
+		std::cmp::max(title_size.0, str_width(subtitle)) + 4,
+		title_size.1 + 4
+	);
+	let box_rect = screen.centered(size);
+	BoxPrint::in_rect(box_rect).print();
+
+	let [title_rect, _, subtitle_rect] = Layout::vertical(&[Constraint::Fixed(title_size.1), Constraint::Fixed(1), Constraint::Fixed(1)])
+		.padding(Padding::symmetric(2, 1))
+		.split(box_rect)[..] else {return};
+	let theme = current_theme();
+	if use_big {
+		BigText::in_rect(title, title_rect, Align::Center).print();
+	} else {
+		TextPrint::in_rect(title, title_rect, Align::Center)
+			.color_fg(&theme.accent)
+			.add_prefix(&termion::style::Bold)
+			.print();
+	}
+	TextPrint::in_rect(subtitle, subtitle_rect, Align::Center)
+		.set_max_width(subtitle_rect.width)
+		.print();
+}
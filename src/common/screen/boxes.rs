@@ -1,5 +1,7 @@
-use termion::*;
 use termion::color::*;
+use super::layout::*;
+use super::text::fill_width;
+use super::theme::current_theme;
 use super::util::*;
 
 pub struct BoxPrint<'a> {
@@ -7,66 +9,97 @@ pub struct BoxPrint<'a> {
 	pub size: (u16, u16),
 	pub has_border: bool,
 	pub fill_col: Option<&'a dyn Color>,
-	pub border_col: Option<&'a dyn Color>
+	pub border_col: Option<&'a dyn Color>,
+	pub clip: Option<Rect>
 }
 
 impl<'a> BoxPrint<'a> {
 	pub fn new(size: (u16, u16)) -> BoxPrint<'a> {
-		let term_size = terminal_size().unwrap();
-		let new_size = (
-			std::cmp::min(size.0, term_size.0),
-			std::cmp::min(size.1, term_size.1),
-		);
-		let coords = get_centered_coords(size);
+		let rect = Rect::screen().centered(size);
 		BoxPrint {
-			coords,
-			size: new_size,
+			coords: rect.coords(),
+			size: rect.size(),
 			has_border: true,
 			fill_col: None,
-			border_col: None
+			border_col: None,
+			clip: None
 		}
 	}
-	pub fn at_coords(self: &mut Self, coords: (u16, u16)) -> &mut Self {
+	pub fn in_rect(rect: Rect) -> BoxPrint<'a> {
+		BoxPrint {
+			coords: rect.coords(),
+			size: rect.size(),
+			has_border: true,
+			fill_col: None,
+			border_col: None,
+			clip: Some(rect)
+		}
+	}
+	pub fn at_coords(&mut self, coords: (u16, u16)) -> &mut Self {
 		self.coords = coords;
 		self
 	}
-	pub fn set_border(self: &mut Self, has_border: bool) -> &mut Self {
+	pub fn set_border(&mut self, has_border: bool) -> &mut Self {
 		self.has_border = has_border;
 		self
 	}
-	pub fn set_fill(self: &mut Self, fill: &'a dyn Color) -> &mut Self {
+	pub fn set_fill(&mut self, fill: &'a dyn Color) -> &mut Self {
 		self.fill_col = Some(fill);
 		self
 	}
-	pub fn set_border_col(self: &mut Self, col: &'a dyn Color) -> &mut Self {
+	pub fn set_border_col(&mut self, col: &'a dyn Color) -> &mut Self {
 		self.border_col = Some(col);
 		self
 	}
-	pub fn remove_fill(self: &mut Self) -> &mut Self {
+	pub fn remove_fill(&mut self) -> &mut Self {
 		self.fill_col = None;
 		self
 	}
-	pub fn draw_border(self: &Self) {
+	pub fn clip_to(&mut self, rect: Rect) -> &mut Self {
+		self.clip = Some(rect);
+		self
+	}
+	pub fn rect(&self) -> Rect {
+		Rect::new(self.coords, self.size)
+	}
+	// The area inside the border that content can be laid out in
+	pub fn inner(&self) -> Rect {
+		let padding = if self.has_border {1} else {0};
+		self.rect().inner(Padding::uniform(padding))
+	}
+	fn print_part(&self, text: &str, coords: (u16, u16), fg: Option<&dyn Color>) {
+		let clip = match self.clip {
+			Some(clip) => clip.intersection(self.rect()),
+			None => self.rect()
+		};
+		let theme = current_theme();
+		print_clipped(text, coords, Some(clip), Some(self.fill_col.unwrap_or(&theme.fill)), fg);
+	}
+	pub fn draw_border(&self) {
 		let (w, h) = self.size;
 		let (x, y) = self.coords;
+		if w < 2 || h < 2 {return};
+		let theme = current_theme();
+		let glyphs = theme.glyphs;
+		let border_col = Some(self.border_col.unwrap_or(&theme.border));
 		// Draw corners
-		print_at_with_cols("┌", (x, y), self.fill_col, self.border_col);
-		print_at_with_cols("┐", (x+w-1, y), self.fill_col, self.border_col);
-		print_at_with_cols("└", (x, y+h-1), self.fill_col, self.border_col);
-		print_at_with_cols("┘", (x+w-1, y+h-1), self.fill_col, self.border_col);
-	
+		self.print_part(glyphs.top_left, (x, y), border_col);
+		self.print_part(glyphs.top_right, (x+w-1, y), border_col);
+		self.print_part(glyphs.bottom_left, (x, y+h-1), border_col);
+		self.print_part(glyphs.bottom_right, (x+w-1, y+h-1), border_col);
+
 		// Draw lines
-		print_at_with_cols(&repeat_str("─", w-2), (x+1, y), self.fill_col, self.border_col);
-		print_at_with_cols(&repeat_str("─", w-2), (x+1, y+h-1), self.fill_col, self.border_col);
-		for i in 1..=(h-2) {
-			print_at_with_cols("│", (x, y+i), self.fill_col, self.border_col);
-			print_at_with_cols("│", (x+w-1, y+i), self.fill_col, self.border_col);
+		self.print_part(&fill_width(glyphs.horizontal, w-2), (x+1, y), border_col);
+		self.print_part(&fill_width(glyphs.horizontal, w-2), (x+1, y+h-1), border_col);
+		for i in 1..(h-1) {
+			self.print_part(glyphs.vertical, (x, y+i), border_col);
+			self.print_part(glyphs.vertical, (x+w-1, y+i), border_col);
 		}
 	}
-	pub fn print(self: &Self) {
-		for i in 0..=(self.size.1-1) {
-			print_at_with_cols(&repeat_str(" ", self.size.0-1), (self.coords.0, self.coords.1 + i), self.fill_col, None);
+	pub fn print(&self) {
+		for i in 0..self.size.1 {
+			self.print_part(&fill_width(" ", self.size.0), (self.coords.0, self.coords.1 + i), None);
 		}
 		if self.has_border {self.draw_border()};
 	}
-}
\ No newline at end of file
+}
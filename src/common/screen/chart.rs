@@ -0,0 +1,47 @@
+use super::theme::current_theme;
+use super::util::repeat_str;
+
+// Bar scaled so that max fills the width, at least one cell for anything above zero
+pub fn bar(value: f32, max: f32, width: u16) -> String {
+	if value <= 0.0 || max <= 0.0 {return String::new()};
+	let cells = ((value / max).min(1.0) * width as f32).round() as u16;
+	repeat_str(current_theme().glyphs.block, std::cmp::max(cells, 1))
+}
+
+// One cell per value, the most recent values when there are more than fit
+pub fn sparkline(values: &[f32], width: u16) -> String {
+	let levels = current_theme().glyphs.sparkline;
+	let values = &values[values.len().saturating_sub(width as usize)..];
+	let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+	let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+	values.iter().map(|value| {
+		let level = if max > min {
+			((value - min) / (max - min) * (levels.len() - 1) as f32).round() as usize
+		} else {
+			levels.len() / 2
+		};
+		levels[level]
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bars_scale_to_width() {
+		let block = current_theme().glyphs.block;
+		assert_eq!(bar(5.0, 10.0, 10), block.repeat(5));
+		assert_eq!(bar(20.0, 10.0, 10), block.repeat(10));
+		assert_eq!(bar(0.1, 10.0, 10), block);
+		assert_eq!(bar(0.0, 10.0, 10), "");
+	}
+
+	#[test]
+	fn sparkline_spans_lowest_to_highest() {
+		let levels = current_theme().glyphs.sparkline;
+		let line = sparkline(&[1.0, 5.0, 3.0, 9.0], 3);
+		assert_eq!(line, format!("{}{}{}", levels[2], levels[0], levels[7]));
+		assert_eq!(sparkline(&[], 10), "");
+	}
+}
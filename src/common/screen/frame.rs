@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use termion::color::{Color, Fg};
+
+use super::text::char_width;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cell {
+	// Empty for the right half of a wide character
+	pub symbol: String,
+	pub fg: Option<String>,
+	pub bg: Option<String>
+}
+
+impl Cell {
+	fn blank() -> Cell {
+		Cell {
+			symbol: " ".to_owned(),
+			fg: None,
+			bg: None
+		}
+	}
+}
+
+// Readable name for a colour, None for the terminal default
+pub fn describe_color(col: &dyn Color) -> Option<String> {
+	let escape = format!("{}", Fg(col));
+	let params = escape.trim_start_matches("\x1b[").trim_end_matches('m');
+	let parts = params.split(';').filter_map(|part| part.parse::<u8>().ok()).collect::<Vec<u8>>();
+	match parts[..] {
+		[38, 2, r, g, b] => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+		[38, 5, n] => Some(format!("ansi{}", n)),
+		[39] => None,
+		[n @ 30..=37] => Some(format!("ansi{}", n - 30)),
+		[n @ 90..=97] => Some(format!("ansi{}", n - 90 + 8)),
+		_ => Some(params.to_owned())
+	}
+}
+
+// An in memory screen, drawn to instead of the terminal while capturing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+	pub size: (u16, u16),
+	cells: Vec<Cell>
+}
+
+impl Frame {
+	pub fn new(size: (u16, u16)) -> Frame {
+		Frame {
+			size,
+			cells: vec![Cell::blank(); size.0 as usize * size.1 as usize]
+		}
+	}
+	fn index(&self, coords: (u16, u16)) -> Option<usize> {
+		let (x, y) = coords;
+		if x == 0 || y == 0 || x > self.size.0 || y > self.size.1 {return None};
+		Some((y - 1) as usize * self.size.0 as usize + (x - 1) as usize)
+	}
+	// Coordinates start at 1, like the terminal's
+	pub fn get(&self, coords: (u16, u16)) -> Option<&Cell> {
+		self.index(coords).map(|i| &self.cells[i])
+	}
+	pub fn clear(&mut self) {
+		self.cells.fill(Cell::blank());
+	}
+	// Anything past the right edge is dropped rather than wrapped, style escapes are skipped
+	pub fn write(&mut self, text: &str, coords: (u16, u16), bg: Option<String>, fg: Option<String>) {
+		let (mut x, y) = coords;
+		let mut chars = text.chars();
+		while let Some(c) = chars.next() {
+			if c == '\x1b' {
+				// Control sequences end with a character in the @ to ~ range
+				if chars.next() == Some('[') {
+					for c in chars.by_ref() {
+						if ('@'..='~').contains(&c) {break};
+					}
+				}
+				continue;
+			}
+			let width = char_width(c);
+			if width == 0 {continue};
+			if x.saturating_add(width - 1) > self.size.0 {break};
+			if let Some(i) = self.index((x, y)) {
+				self.cells[i] = Cell {
+					symbol: c.to_string(),
+					fg: fg.clone(),
+					bg: bg.clone()
+				};
+			}
+			for offset in 1..width {
+				if let Some(i) = self.index((x + offset, y)) {
+					self.cells[i] = Cell {
+						symbol: String::new(),
+						fg: fg.clone(),
+						bg: bg.clone()
+					};
+				}
+			}
+			x += width;
+		}
+	}
+	pub fn row(&self, y: u16) -> &[Cell] {
+		match self.index((1, y)) {
+			Some(start) => &self.cells[start..start + self.size.0 as usize],
+			None => &[]
+		}
+	}
+	pub fn line(&self, y: u16) -> String {
+		self.row(y).iter().map(|cell| cell.symbol.as_str()).collect()
+	}
+	pub fn lines(&self) -> Vec<String> {
+		(1..=self.size.1).map(|y| self.line(y).trim_end().to_owned()).collect()
+	}
+	// Position of the first match, searching row by row
+	pub fn find(&self, text: &str) -> Option<(u16, u16)> {
+		(1..=self.size.1).find_map(|y| {
+			let line = self.line(y);
+			let byte_index = line.find(text)?;
+			let x = super::text::str_width(&line[..byte_index]) + 1;
+			Some((x, y))
+		})
+	}
+	pub fn contains(&self, text: &str) -> bool {
+		self.find(text).is_some()
+	}
+	// The text of the frame followed by its colours, listed as runs of cells on each row
+	pub fn annotated(&self) -> String {
+		let mut out = self.lines().join("\n");
+		out.push_str("\n-- colours --");
+		for y in 1..=self.size.1 {
+			let row = self.row(y);
+			let mut start = 0;
+			while start < row.len() {
+				let (fg, bg) = (&row[start].fg, &row[start].bg);
+				let end = row[start..].iter()
+					.position(|cell| cell.fg != *fg || cell.bg != *bg)
+					.map_or(row.len(), |length| start + length);
+				if fg.is_some() || bg.is_some() {
+					let describe = |col: &Option<String>| col.clone().unwrap_or_else(|| "default".to_owned());
+					out.push_str(&format!("\n{}:{}-{} fg={} bg={}", y, start + 1, end, describe(fg), describe(bg)));
+				}
+				start = end;
+			}
+		}
+		out.push('\n');
+		out
+	}
+}
+
+// Turns a colour back into the escape that sets it, layer being 38 for the foreground or 48 for the background
+fn color_escape(col: &Option<String>, layer: u8) -> String {
+	let Some(col) = col else {return format!("\x1b[{}m", layer + 1)};
+	if let Some(hex) = col.strip_prefix('#') {
+		let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+		return format!("\x1b[{};2;{};{};{}m", layer, channel(0), channel(2), channel(4));
+	}
+	match col.strip_prefix("ansi").and_then(|n| n.parse::<u8>().ok()) {
+		Some(n) => format!("\x1b[{};5;{}m", layer, n),
+		None => String::new()
+	}
+}
+
+impl Frame {
+	// Escapes that draw the whole frame over whatever a terminal is showing
+	pub fn to_ansi(&self) -> String {
+		let mut out = String::new();
+		for y in 1..=self.size.1 {
+			out.push_str(&format!("{}", termion::cursor::Goto(1, y)));
+			let (mut fg, mut bg) = (&None, &None);
+			for cell in self.row(y) {
+				if cell.fg != *fg {
+					out.push_str(&color_escape(&cell.fg, 38));
+					fg = &cell.fg;
+				}
+				if cell.bg != *bg {
+					out.push_str(&color_escape(&cell.bg, 48));
+					bg = &cell.bg;
+				}
+				out.push_str(&cell.symbol);
+			}
+			out.push_str("\x1b[39m\x1b[49m");
+		}
+		out
+	}
+}
+
+impl fmt::Display for Frame {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.lines().join("\n"))
+	}
+}
+
+thread_local! {
+	static CAPTURE: RefCell<Option<Frame>> = const { RefCell::new(None) };
+}
+
+// Runs the drawing function against an in memory frame of the given size instead of the terminal.
+// Capturing is per thread, so tests can run in parallel
+pub fn capture<F: FnOnce()>(size: (u16, u16), draw: F) -> Frame {
+	let previous = CAPTURE.with(|capture| capture.replace(Some(Frame::new(size))));
+	draw();
+	let frame = CAPTURE.with(|capture| capture.replace(previous));
+	frame.expect("capture frame went missing")
+}
+
+pub fn is_capturing() -> bool {
+	CAPTURE.with(|capture| capture.borrow().is_some())
+}
+
+// Applies the change to the frame being captured, None when drawing to the terminal
+pub fn with_capture<R, F: FnOnce(&mut Frame) -> R>(change: F) -> Option<R> {
+	CAPTURE.with(|capture| capture.borrow_mut().as_mut().map(change))
+}
@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyphs {
+	pub top_left: &'static str,
+	pub top_right: &'static str,
+	pub bottom_left: &'static str,
+	pub bottom_right: &'static str,
+	pub horizontal: &'static str,
+	pub vertical: &'static str,
+	pub block: &'static str,
+	pub food: &'static str,
+	// Fainter than a block, for outlines like where a piece will land
+	pub shade: &'static str,
+	pub ball: &'static str,
+	pub selector: &'static str,
+	pub ellipsis: &'static str,
+	// Lowest to highest
+	pub sparkline: [&'static str; 8]
+}
+
+impl Glyphs {
+	pub fn unicode() -> Glyphs {
+		Glyphs {
+			top_left: "┌",
+			top_right: "┐",
+			bottom_left: "└",
+			bottom_right: "┘",
+			horizontal: "─",
+			vertical: "│",
+			block: "█",
+			food: "█",
+			shade: "░",
+			ball: "o",
+			selector: ">",
+			ellipsis: "…",
+			sparkline: ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"]
+		}
+	}
+	pub fn heavy() -> Glyphs {
+		Glyphs {
+			top_left: "┏",
+			top_right: "┓",
+			bottom_left: "┗",
+			bottom_right: "┛",
+			horizontal: "━",
+			vertical: "┃",
+			..Glyphs::unicode()
+		}
+	}
+	pub fn rounded() -> Glyphs {
+		Glyphs {
+			top_left: "╭",
+			top_right: "╮",
+			bottom_left: "╰",
+			bottom_right: "╯",
+			..Glyphs::unicode()
+		}
+	}
+	// Safe on serial consoles and legacy code pages
+	pub fn ascii() -> Glyphs {
+		Glyphs {
+			top_left: "+",
+			top_right: "+",
+			bottom_left: "+",
+			bottom_right: "+",
+			horizontal: "-",
+			vertical: "|",
+			block: "#",
+			food: "O",
+			shade: ":",
+			ball: "o",
+			selector: ">",
+			ellipsis: "...",
+			sparkline: ["_", ".", "-", "~", "=", "+", "*", "#"]
+		}
+	}
+}
+
+static ASCII_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub fn ascii_only() -> bool {
+	ASCII_ONLY.load(Ordering::Relaxed)
+}
+
+pub fn set_ascii_only(ascii_only: bool) {
+	ASCII_ONLY.store(ascii_only, Ordering::Relaxed);
+}
+
+// Follows the usual precedence of the locale variables, an unset locale is plain "C"
+pub fn locale_supports_unicode() -> bool {
+	let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+		.filter_map(|name| std::env::var(name).ok())
+		.find(|value| !value.is_empty())
+		.unwrap_or_default()
+		.to_ascii_lowercase();
+	locale.contains("utf-8") || locale.contains("utf8")
+}
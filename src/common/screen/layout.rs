@@ -0,0 +1,274 @@
+use super::text::{skip_width, take_width};
+use super::util::{get_terminal_size, repeat_str};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+	pub x: u16,
+	pub y: u16,
+	pub width: u16,
+	pub height: u16
+}
+
+impl Rect {
+	pub fn new(coords: (u16, u16), size: (u16, u16)) -> Rect {
+		Rect {
+			x: coords.0,
+			y: coords.1,
+			width: size.0,
+			height: size.1
+		}
+	}
+	// Terminal coordinates are 1-based, the last row is kept free for the cursor
+	pub fn screen() -> Rect {
+		let (w, h) = get_terminal_size();
+		Rect::new((1, 1), (w, h.saturating_sub(1)))
+	}
+	pub fn coords(&self) -> (u16, u16) {
+		(self.x, self.y)
+	}
+	pub fn size(&self) -> (u16, u16) {
+		(self.width, self.height)
+	}
+	pub fn right(&self) -> u16 {
+		self.x.saturating_add(self.width)
+	}
+	pub fn bottom(&self) -> u16 {
+		self.y.saturating_add(self.height)
+	}
+	pub fn is_empty(&self) -> bool {
+		self.width == 0 || self.height == 0
+	}
+	pub fn contains(&self, coords: (u16, u16)) -> bool {
+		coords.0 >= self.x && coords.0 < self.right() && coords.1 >= self.y && coords.1 < self.bottom()
+	}
+	pub fn intersection(&self, other: Rect) -> Rect {
+		let x = std::cmp::max(self.x, other.x);
+		let y = std::cmp::max(self.y, other.y);
+		let right = std::cmp::min(self.right(), other.right());
+		let bottom = std::cmp::min(self.bottom(), other.bottom());
+		Rect::new((x, y), (right.saturating_sub(x), bottom.saturating_sub(y)))
+	}
+	pub fn inner(&self, padding: Padding) -> Rect {
+		let width = self.width.saturating_sub(padding.left + padding.right);
+		let height = self.height.saturating_sub(padding.top + padding.bottom);
+		Rect::new(
+			(
+				std::cmp::min(self.x + padding.left, self.right()),
+				std::cmp::min(self.y + padding.top, self.bottom())
+			),
+			(width, height)
+		)
+	}
+	pub fn outer(&self, padding: Padding) -> Rect {
+		Rect::new(
+			(self.x.saturating_sub(padding.left), self.y.saturating_sub(padding.top)),
+			(
+				self.width.saturating_add(padding.left + padding.right),
+				self.height.saturating_add(padding.top + padding.bottom)
+			)
+		)
+	}
+	// Places a child of the given size inside this rect, shrinking it if it doesn't fit
+	pub fn align(&self, size: (u16, u16), horizontal: Align, vertical: Align) -> Rect {
+		let width = std::cmp::min(size.0, self.width);
+		let height = std::cmp::min(size.1, self.height);
+		Rect::new(
+			(
+				self.x + horizontal.offset(width, self.width),
+				self.y + vertical.offset(height, self.height)
+			),
+			(width, height)
+		)
+	}
+	pub fn centered(&self, size: (u16, u16)) -> Rect {
+		self.align(size, Align::Center, Align::Center)
+	}
+	pub fn row(&self, i: u16) -> Rect {
+		if i >= self.height {
+			return Rect::new((self.x, self.bottom()), (self.width, 0));
+		}
+		Rect::new((self.x, self.y + i), (self.width, 1))
+	}
+	// Cuts a single line of text down to the columns that fall inside this rect
+	pub fn clip_line(&self, text: &str, coords: (u16, u16)) -> Option<(String, (u16, u16))> {
+		if coords.1 < self.y || coords.1 >= self.bottom() || coords.0 >= self.right() {
+			return None;
+		}
+		let (rest, padding) = skip_width(text, self.x.saturating_sub(coords.0));
+		let start_x = std::cmp::max(coords.0, self.x);
+		let max_width = self.right() - start_x;
+		let visible = take_width(rest, max_width.saturating_sub(padding));
+		if visible.is_empty() && padding == 0 {
+			return None;
+		}
+		Some((format!("{}{}", repeat_str(" ", std::cmp::min(padding, max_width)), visible), (start_x, coords.1)))
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+	Start,
+	Center,
+	End
+}
+
+impl Align {
+	pub fn offset(&self, size: u16, available: u16) -> u16 {
+		let free = available.saturating_sub(size);
+		match self {
+			Align::Start => 0,
+			Align::Center => free / 2,
+			Align::End => free
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Padding {
+	pub top: u16,
+	pub right: u16,
+	pub bottom: u16,
+	pub left: u16
+}
+
+impl Padding {
+	pub fn uniform(n: u16) -> Padding {
+		Padding { top: n, right: n, bottom: n, left: n }
+	}
+	pub fn symmetric(horizontal: u16, vertical: u16) -> Padding {
+		Padding { top: vertical, right: horizontal, bottom: vertical, left: horizontal }
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Constraint {
+	Fixed(u16),
+	Percent(u16),
+	Min(u16)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+	Horizontal,
+	Vertical
+}
+
+pub struct Layout {
+	pub direction: Direction,
+	pub constraints: Vec<Constraint>,
+	pub padding: Padding,
+	pub spacing: u16
+}
+
+impl Layout {
+	pub fn new(direction: Direction, constraints: &[Constraint]) -> Layout {
+		Layout {
+			direction,
+			constraints: constraints.to_vec(),
+			padding: Padding::default(),
+			spacing: 0
+		}
+	}
+	pub fn horizontal(constraints: &[Constraint]) -> Layout {
+		Layout::new(Direction::Horizontal, constraints)
+	}
+	pub fn vertical(constraints: &[Constraint]) -> Layout {
+		Layout::new(Direction::Vertical, constraints)
+	}
+	pub fn padding(&mut self, padding: Padding) -> &mut Self {
+		self.padding = padding;
+		self
+	}
+	pub fn spacing(&mut self, spacing: u16) -> &mut Self {
+		self.spacing = spacing;
+		self
+	}
+	pub fn split(&self, area: Rect) -> Vec<Rect> {
+		let area = area.inner(self.padding);
+		let total = match self.direction {
+			Direction::Horizontal => area.width,
+			Direction::Vertical => area.height
+		};
+		let gaps = self.spacing.saturating_mul(self.constraints.len().saturating_sub(1) as u16);
+		let available = total.saturating_sub(gaps);
+
+		// Fixed and percent sizes first, min constraints then share whatever is left over
+		let mut sizes: Vec<u16> = self.constraints.iter().map(|constraint| match constraint {
+			Constraint::Fixed(n) => *n,
+			Constraint::Percent(p) => (available as u32 * std::cmp::min(*p, 100) as u32 / 100) as u16,
+			Constraint::Min(n) => *n
+		}).collect();
+		let used: u16 = sizes.iter().fold(0u16, |acc, size| acc.saturating_add(*size));
+		let mins: Vec<usize> = self.constraints.iter().enumerate()
+			.filter(|(_, constraint)| matches!(constraint, Constraint::Min(_)))
+			.map(|(i, _)| i)
+			.collect();
+		if used < available && !mins.is_empty() {
+			let extra = available - used;
+			let share = extra / mins.len() as u16;
+			let remainder = extra % mins.len() as u16;
+			for (n, i) in mins.iter().enumerate() {
+				sizes[*i] += share + if (n as u16) < remainder {1} else {0};
+			}
+		}
+
+		// Anything that doesn't fit gets cut off from the end
+		let mut rects = Vec::with_capacity(sizes.len());
+		let mut position: u16 = 0;
+		for size in sizes {
+			let start = std::cmp::min(position, total);
+			let size = std::cmp::min(size, total - start);
+			rects.push(match self.direction {
+				Direction::Horizontal => Rect::new((area.x + start, area.y), (size, area.height)),
+				Direction::Vertical => Rect::new((area.x, area.y + start), (area.width, size))
+			});
+			position = position.saturating_add(size).saturating_add(self.spacing);
+		}
+		rects
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn min_constraints_share_the_leftover_space() {
+		let rects = Layout::horizontal(&[Constraint::Fixed(3), Constraint::Min(1), Constraint::Min(1)]).split(Rect::new((1, 1), (10, 2)));
+		assert_eq!(rects, [Rect::new((1, 1), (3, 2)), Rect::new((4, 1), (4, 2)), Rect::new((8, 1), (3, 2))]);
+		let rects = Layout::vertical(&[Constraint::Percent(50), Constraint::Min(0)]).spacing(1).split(Rect::new((1, 1), (4, 8)));
+		assert_eq!(rects, [Rect::new((1, 1), (4, 3)), Rect::new((1, 5), (4, 4))]);
+	}
+
+	#[test]
+	fn too_little_space_cuts_off_the_end() {
+		let rects = Layout::vertical(&[Constraint::Fixed(3), Constraint::Fixed(3), Constraint::Min(2)]).split(Rect::new((1, 1), (5, 4)));
+		assert_eq!(rects, [Rect::new((1, 1), (5, 3)), Rect::new((1, 4), (5, 1)), Rect::new((1, 5), (5, 0))]);
+		let rects = Layout::horizontal(&[Constraint::Min(1), Constraint::Percent(200)]).padding(Padding::uniform(2)).split(Rect::new((1, 1), (3, 3)));
+		assert!(rects.iter().all(|rect| rect.is_empty()));
+		assert_eq!(Layout::horizontal(&[]).split(Rect::new((1, 1), (3, 3))), []);
+	}
+
+	#[test]
+	fn rects_shrink_to_fit() {
+		let area = Rect::new((2, 2), (5, 3));
+		assert_eq!(area.centered((3, 2)), Rect::new((3, 2), (3, 2)));
+		assert_eq!(area.centered((9, 9)), area);
+		assert_eq!(area.inner(Padding::uniform(4)), Rect::new((6, 5), (0, 0)));
+		assert_eq!(area.intersection(Rect::new((10, 10), (2, 2))).size(), (0, 0));
+		assert_eq!(area.row(5), Rect::new((2, 5), (5, 0)));
+	}
+
+	#[test]
+	fn clipping_pads_cut_wide_glyphs() {
+		let area = Rect::new((3, 1), (4, 1));
+		assert_eq!(area.clip_line("abcdef", (1, 1)), Some(("cdef".to_owned(), (3, 1))));
+		// "日" covers columns 2 and 3, the half inside is drawn as a space
+		assert_eq!(area.clip_line("a日本語", (1, 1)), Some((" 本".to_owned(), (3, 1))));
+		// Hanging off the right edge, it's left out rather than split
+		assert_eq!(area.clip_line("a日", (6, 1)), Some(("a".to_owned(), (6, 1))));
+		assert_eq!(area.clip_line("日", (6, 1)), None);
+		assert_eq!(area.clip_line("本", (7, 1)), None);
+		assert_eq!(area.clip_line("abc", (1, 2)), None);
+	}
+}
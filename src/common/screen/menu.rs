@@ -2,13 +2,19 @@ use std::ops::Range;
 
 use console::Key;
 
+use crate::common::input::*;
+
 use super::util::*;
+use super::bigtext::*;
 use super::boxes::*;
+use super::layout::*;
 use super::text::*;
+use super::theme::*;
+use super::toast::draw_toast;
 
 pub struct MenuItem<'a> {
-	label: &'a str,
-	value: &'a str
+	pub label: &'a str,
+	pub value: &'a str
 }
 impl<'a> MenuItem<'a> {
 	pub fn new(label: &'a str, value: &'a str) -> MenuItem<'a> {
@@ -19,101 +25,165 @@ impl<'a> MenuItem<'a> {
 	}
 }
 
-pub fn get_window_scroll_items_range<T>(items: &Vec<T>, selected_index: usize, max_window_size: usize) -> Range<usize> {
-	let selected_offset = max_window_size / 2;
-	let windows = items.windows(max_window_size);
-	let window_len = windows.len();
-	let mut window_i = 0;
-	for _ in windows {
-		if selected_index < selected_offset as usize && window_i == 0 {
-			return window_i..(window_i + max_window_size);
-		} else if selected_index >= selected_offset as usize && window_i == selected_index - selected_offset as usize {
-			return window_i..(window_i + max_window_size);
-		} else if selected_index + selected_offset >= items.len() - 1 && window_i == window_len - 1 {
-			return window_i..(window_i + max_window_size);
-		};
-		window_i += 1;
-	};
-	0..items.len()
+pub fn get_window_scroll_items_range<T>(items: &[T], selected_index: usize, max_window_size: usize) -> Range<usize> {
+	if items.len() <= max_window_size {
+		return 0..items.len();
+	}
+	let start = std::cmp::min(
+		selected_index.saturating_sub(max_window_size / 2),
+		items.len() - max_window_size
+	);
+	start..(start + max_window_size)
 }
 
-pub fn draw_menu_render(items: &Vec<MenuItem>, title: &str, selected_index: usize) {
-	let term_size = termion::terminal_size().unwrap();
-	let max_length_item = items.iter().reduce(|item, acc| {
-		if item.label.len() > acc.label.len() {
-			return item;
-		}
-		acc
-	});
-	let mut max_length: u16 = match max_length_item {
-		Some(item) => item.label.len() as u16 + 4,
-		None => 5
-	};
-	if title.len() as u16 > max_length {max_length = title.len() as u16 + 2};
+pub fn draw_menu_render(items: &[MenuItem], title: &str, banner: Option<&str>, selected_index: usize) {
+	let max_length = items.iter()
+		.map(|item| str_width(item.label) + 4)
+		.chain([5, str_width(title) + 2])
+		.max()
+		.unwrap_or(5);
 	clear_screen();
 
 	let max_window_items = 7;
+	let area = Rect::screen().inner(Padding::symmetric(2, 0));
 	let size = (
-		std::cmp::min(max_length + 2, term_size.0 - 4),
-		std::cmp::min(std::cmp::min(max_window_items, items.len()) as u16 + 3, term_size.1)
+		max_length + 2,
+		std::cmp::min(max_window_items, items.len()) as u16 + 3
 	);
 
-	let items_window_range = get_window_scroll_items_range(items, selected_index, max_window_items);
-	let min = items_window_range.clone().min().unwrap();
-	let items_window = &items[items_window_range];
+	// The banner goes above the menu when there's room for both
+	let banner_height = BIG_TEXT_HEIGHT + 1;
+	let banner = banner.filter(|banner| {
+		big_text_width(banner) <= area.width && size.1 + banner_height <= area.height
+	});
+	let mut menu_area = area;
+	if let Some(banner) = banner {
+		let frame = area.centered((area.width, size.1 + banner_height));
+		if let [banner_rect, rest] = Layout::vertical(&[Constraint::Fixed(banner_height), Constraint::Min(0)]).split(frame)[..] {
+			BigText::in_rect(banner, banner_rect, Align::Center).print();
+			menu_area = rest;
+		}
+	}
 
-	let coords = get_centered_coords(size);
-	let mut box_print = BoxPrint::new(size);
-	box_print
-		.at_coords(coords)
-		.set_border(true);
+	let mut box_print = BoxPrint::in_rect(menu_area.centered(size));
+	box_print.set_border(true);
 	box_print.print();
 
-	TextPrint::new(title, (coords.0 + 2, coords.1 + 1))
+	let [title_rect, items_rect] = Layout::vertical(&[Constraint::Fixed(1), Constraint::Min(0)])
+		.padding(Padding { left: 1, ..Padding::default() })
+		.split(box_print.inner())[..] else {return};
+
+	let theme = current_theme();
+	TextPrint::in_rect(title, title_rect, Align::Start)
+		.set_max_width(title_rect.width)
+		.color_fg(&theme.accent)
 		.add_prefix(&termion::style::Bold)
 		.add_prefix(&termion::style::Underline)
 		.print();
-	let mut i = 0;
-	for item in items_window {
+
+	let window_size = std::cmp::min(max_window_items, items_rect.height as usize);
+	let items_window_range = get_window_scroll_items_range(items, selected_index, window_size);
+	let min = items_window_range.start;
+	for (i, item) in items[items_window_range].iter().enumerate() {
 		let item_index = i + min;
-		let label: &str;
-		let unselected_label = format!("  {}", item.label);
-		let selected_label = format!("{}{} {}{}", termion::style::Bold, ">", &item.label, termion::style::Reset);
-		if item_index == selected_index  {
-			label = &selected_label
+		let row = items_rect.row(i as u16);
+		if item_index == selected_index {
+			let selected_label = format!("{} {}", theme.glyphs.selector, item.label);
+			TextPrint::in_rect(&selected_label, row, Align::Start)
+				.set_max_width(row.width)
+				.color_fg(&theme.accent)
+				.add_prefix(&termion::style::Bold)
+				.print();
 		} else {
-			label = &unselected_label
+			let unselected_label = format!("  {}", item.label);
+			TextPrint::in_rect(&unselected_label, row, Align::Start)
+				.set_max_width(row.width)
+				.print();
 		}
-		print_at(label, (box_print.coords.0 + 2, box_print.coords.1 + 2 + i as u16));
-		i += 1;
 	}
+	// Such as a leaderboard reply that came in after the game ended
+	draw_toast();
 	cursor_to_end();
 }
 
-pub fn draw_menu(items: &Vec<MenuItem>, title: &str) -> String {
-	let mut selected_index = 0;
-	let term = console::Term::stdout();
+pub fn draw_menu(items: &[MenuItem], title: &str) -> String {
+	draw_menu_with_banner(items, title, None)
+}
+
+pub fn draw_menu_with_banner(items: &[MenuItem], title: &str, banner: Option<&str>) -> String {
+	run_menu(items, title, banner, 0)
+}
+
+// Starts with the given item selected, for menus that are redrawn after each choice
+pub fn draw_menu_at(items: &[MenuItem], title: &str, selected_index: usize) -> String {
+	run_menu(items, title, None, selected_index)
+}
+
+fn run_menu(items: &[MenuItem], title: &str, banner: Option<&str>, selected_index: usize) -> String {
+	let mut selected_index = std::cmp::min(selected_index, items.len().saturating_sub(1));
+	let bindings = menu_bindings().load_or_default();
 	loop {
-		draw_menu_render(items, title, selected_index);
-		let input = term.read_key();
+		draw_menu_render(items, title, banner, selected_index);
+		// Nothing is chosen once input has closed, callers treat that like backing out
+		let input = match read_key() {
+			Some(key) => bindings.action_for(key),
+			None => return String::new()
+		};
 		match input {
-			Ok(Key::ArrowUp) => {
-				let new_index: usize;
-				if selected_index == 0 {new_index = 0}
-				else {new_index = selected_index - 1}
-				selected_index = new_index
+			Some(Action::MoveUp) => {
+				selected_index = selected_index.saturating_sub(1);
 			},
-			Ok(Key::ArrowDown) => {
-				let new_index: usize;
-				if selected_index == items.len() - 1 {new_index = selected_index}
-				else {new_index = selected_index + 1}
-				selected_index = new_index
+			Some(Action::MoveDown) if selected_index + 1 < items.len() => {
+				selected_index += 1;
 			},
-			Ok(Key::Enter) => {
+			Some(Action::Confirm) => {
 				break;
 			}
 			_ => {}
 		}
 	};
 	items[selected_index].value.to_owned()
-}
\ No newline at end of file
+}
+
+pub fn draw_text_input_render(title: &str, value: &str, max_length: usize) {
+	clear_screen();
+	let width = std::cmp::max(str_width(title), max_length as u16 + 1) + 4;
+	let area = Rect::screen().inner(Padding::symmetric(2, 0));
+	let box_print = BoxPrint::in_rect(area.centered((width, 4)));
+	box_print.print();
+
+	let [title_rect, value_rect] = Layout::vertical(&[Constraint::Fixed(1), Constraint::Fixed(1)])
+		.padding(Padding { left: 1, ..Padding::default() })
+		.split(box_print.inner())[..] else {return};
+	let theme = current_theme();
+	TextPrint::in_rect(title, title_rect, Align::Start)
+		.set_max_width(title_rect.width)
+		.color_fg(&theme.accent)
+		.add_prefix(&termion::style::Bold)
+		.print();
+	// Keep the end of the value in view, that's where the typing happens
+	let value_with_cursor = format!("{}_", value);
+	let overflow = str_width(&value_with_cursor).saturating_sub(value_rect.width);
+	TextPrint::in_rect(skip_width(&value_with_cursor, overflow).0, value_rect, Align::Start).print();
+	cursor_to_end();
+}
+
+// Returns None if the input was cancelled with escape
+pub fn draw_text_input(title: &str, initial: &str, max_length: usize) -> Option<String> {
+	let mut value = initial.to_owned();
+	loop {
+		draw_text_input_render(title, &value, max_length);
+		match read_console_key() {
+			Some(Key::Enter) => return Some(value),
+			Some(Key::Escape) => return None,
+			Some(Key::Backspace) => {
+				value.pop();
+			},
+			Some(Key::Char(c)) if !c.is_control() && value.chars().count() < max_length => {
+				value.push(c);
+			},
+			None => return None,
+			_ => {}
+		}
+	}
+}
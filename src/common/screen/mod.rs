@@ -1,5 +1,12 @@
 pub mod text;
+pub mod bigtext;
 pub mod boxes;
-pub mod screen;
+pub mod chart;
+pub mod frame;
+pub mod glyphs;
+pub mod layout;
+pub mod theme;
+pub mod toast;
 pub mod util;
-pub mod menu;
\ No newline at end of file
+pub mod menu;
+pub mod sprite;
@@ -0,0 +1,68 @@
+use termion::color::Color;
+use super::layout::Rect;
+use super::text::*;
+use super::util::*;
+
+// A small picture made of text, with a list of frames for animating it. Spaces are see through,
+// so sprites can overlap the background without wiping it out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sprite {
+	pub frames: &'static [&'static [&'static str]]
+}
+
+impl Sprite {
+	pub const fn new(frames: &'static [&'static [&'static str]]) -> Sprite {
+		Sprite { frames }
+	}
+	pub fn width(&self) -> u16 {
+		self.frames.iter().flat_map(|rows| rows.iter()).map(|row| str_width(row)).max().unwrap_or(0)
+	}
+	pub fn height(&self) -> u16 {
+		self.frames.iter().map(|rows| rows.len() as u16).max().unwrap_or(0)
+	}
+	// Frames loop, so any count of animation steps can be passed in
+	pub fn frame(&self, frame: usize) -> &'static [&'static str] {
+		if self.frames.is_empty() {return &[]};
+		self.frames[frame % self.frames.len()]
+	}
+	// Whether the cell `offset` from the top left is drawn in the frame
+	pub fn is_solid(&self, frame: usize, offset: (u16, u16)) -> bool {
+		let Some(row) = self.frame(frame).get(offset.1 as usize) else {return false};
+		let (rest, _) = skip_width(row, offset.0);
+		rest.chars().next().is_some_and(|c| c != ' ')
+	}
+	pub fn draw(&self, frame: usize, coords: (u16, u16), clip: Option<Rect>, fg: Option<&dyn Color>) {
+		for (i, row) in self.frame(frame).iter().enumerate() {
+			let y = coords.1 + i as u16;
+			let mut x = coords.0;
+			for run in row.split(' ') {
+				if !run.is_empty() {
+					print_clipped(run, (x, y), clip, None, fg);
+				}
+				x += str_width(run) + 1;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	static SHIP: Sprite = Sprite::new(&[
+		&[" ^ ", "/ \\"],
+		&[" ^ ", "|_|"]
+	]);
+
+	#[test]
+	fn measures_and_hit_tests_frames() {
+		assert_eq!((SHIP.width(), SHIP.height()), (3, 2));
+		assert_eq!(SHIP.frame(3), SHIP.frames[1]);
+		assert!(SHIP.is_solid(0, (1, 0)));
+		assert!(!SHIP.is_solid(0, (0, 0)));
+		assert!(!SHIP.is_solid(0, (1, 1)));
+		assert!(SHIP.is_solid(1, (1, 1)));
+		assert!(!SHIP.is_solid(0, (3, 0)));
+		assert!(!SHIP.is_solid(0, (0, 2)));
+	}
+}
@@ -1,14 +1,112 @@
 use std::fmt::Display;
 
 use termion::color::*;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use super::layout::*;
+use super::theme::current_theme;
 use super::util::*;
 
+pub fn char_width(c: char) -> u16 {
+	c.width().unwrap_or(0) as u16
+}
+
+pub fn str_width(text: &str) -> u16 {
+	std::cmp::min(text.width(), u16::MAX as usize) as u16
+}
+
+// Longest start of the text that fits within the given number of columns
+pub fn take_width(text: &str, width: u16) -> &str {
+	let mut used: u16 = 0;
+	for (i, c) in text.char_indices() {
+		let w = char_width(c);
+		if used + w > width {
+			return &text[..i];
+		}
+		used += w;
+	}
+	text
+}
+
+// Drops the given number of columns from the start of the text. A wide glyph cut in half
+// can't be drawn, so the columns it leaves behind are returned to be padded with spaces.
+pub fn skip_width(text: &str, columns: u16) -> (&str, u16) {
+	let mut skipped: u16 = 0;
+	for (i, c) in text.char_indices() {
+		if skipped >= columns {
+			return (&text[i..], skipped - columns);
+		}
+		skipped += char_width(c);
+	}
+	("", skipped.saturating_sub(columns))
+}
+
+pub fn truncate(text: &str, width: u16) -> String {
+	if str_width(text) <= width {
+		return text.to_owned();
+	}
+	if width == 0 {
+		return "".to_owned();
+	}
+	let ellipsis = current_theme().glyphs.ellipsis;
+	if str_width(ellipsis) >= width {
+		return take_width(text, width).to_owned();
+	}
+	format!("{}{}", take_width(text, width - str_width(ellipsis)), ellipsis)
+}
+
+pub fn fill_width(glyph: &str, width: u16) -> String {
+	let glyph_width = std::cmp::max(str_width(glyph), 1);
+	let mut filled = repeat_str(glyph, width / glyph_width);
+	filled.push_str(&repeat_str(" ", width % glyph_width));
+	filled
+}
+
+pub fn wrap(text: &str, width: u16) -> Vec<String> {
+	let mut lines: Vec<String> = vec![];
+	if width == 0 {
+		return lines;
+	}
+	for paragraph in text.split('\n') {
+		let mut line = String::new();
+		for word in paragraph.split_whitespace() {
+			let mut word = word;
+			let line_width = str_width(&line);
+			if !line.is_empty() && line_width + 1 + str_width(word) <= width {
+				line.push(' ');
+				line.push_str(word);
+				continue;
+			}
+			if !line.is_empty() {
+				lines.push(std::mem::take(&mut line));
+			}
+			// Words longer than a whole line get broken up
+			while str_width(word) > width {
+				let mut part = take_width(word, width);
+				if part.is_empty() {
+					part = &word[..word.chars().next().unwrap().len_utf8()];
+				}
+				// A last glyph too wide for any line starts the next line rather than leaving an empty one
+				if part.len() == word.len() {break};
+				lines.push(part.to_owned());
+				word = &word[part.len()..];
+			}
+			line.push_str(word);
+		}
+		lines.push(line);
+	}
+	lines
+}
+
 pub struct TextPrint<'a> {
 	pub text: &'a str,
 	pub coords: (u16, u16),
 	pub prefixes: Vec<&'a dyn Display>,
 	pub fg_col: Option<&'a dyn Color>,
 	pub bg_col: Option<&'a dyn Color>,
+	pub clip: Option<Rect>,
+	pub align: Align,
+	pub wrap: bool,
+	pub max_width: Option<u16>
 }
 
 impl<'a> TextPrint<'a> {
@@ -18,26 +116,122 @@ impl<'a> TextPrint<'a> {
 			coords,
 			prefixes: vec![],
 			bg_col: None,
-			fg_col: None
+			fg_col: None,
+			clip: None,
+			align: Align::Start,
+			wrap: false,
+			max_width: None
 		}
 	}
-	pub fn color_fg(self: &mut Self, col: &'a dyn Color) -> &mut Self {
+	// Positions the text from the top of the rect and clips it to the rect
+	pub fn in_rect(text: &'a str, rect: Rect, align: Align) -> TextPrint<'a> {
+		let mut text_print = TextPrint::new(text, rect.coords());
+		text_print.clip = Some(rect);
+		text_print.align = align;
+		text_print
+	}
+	pub fn color_fg(&mut self, col: &'a dyn Color) -> &mut Self {
 		self.fg_col = Some(col);
 		self
 	}
-	pub fn color_bg(self: &mut Self, col: &'a dyn Color) -> &mut Self {
+	pub fn color_bg(&mut self, col: &'a dyn Color) -> &mut Self {
 		self.bg_col = Some(col);
 		self
 	}
-	pub fn add_prefix(self: &mut Self, prefix: &'a dyn Display) -> &mut Self {
+	pub fn add_prefix(&mut self, prefix: &'a dyn Display) -> &mut Self {
 		self.prefixes.push(prefix);
 		self
 	}
-	pub fn print(self: &Self) {
-		let mut prefix_str: String = "".to_owned();
-		self.prefixes.iter().for_each(|prefix| {
-			prefix_str = format!("{}{}", prefix_str, prefix);
-		});
-		print_at_with_cols(&format!("{}{}{}", prefix_str, self.text, termion::style::Reset), self.coords, self.bg_col, self.fg_col);
+	pub fn clip_to(&mut self, rect: Rect) -> &mut Self {
+		self.clip = Some(rect);
+		self
+	}
+	pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
+		self.wrap = wrap;
+		self
+	}
+	pub fn set_max_width(&mut self, max_width: u16) -> &mut Self {
+		self.max_width = Some(max_width);
+		self
+	}
+	fn available_width(&self) -> u16 {
+		let clip_width = match self.clip {
+			Some(rect) => rect.right().saturating_sub(std::cmp::max(self.coords.0, rect.x)),
+			None => u16::MAX
+		};
+		match self.max_width {
+			Some(max_width) => std::cmp::min(max_width, clip_width),
+			None => clip_width
+		}
+	}
+	// The lines that will be drawn, after wrapping and truncation
+	pub fn lines(&self) -> Vec<String> {
+		let width = self.available_width();
+		if self.wrap {
+			wrap(self.text, width)
+		} else if self.max_width.is_some() {
+			vec![truncate(self.text, width)]
+		} else {
+			vec![self.text.to_owned()]
+		}
+	}
+	pub fn print(&self) {
+		let clip = match self.clip {
+			Some(rect) => rect.intersection(Rect::screen()),
+			None => Rect::screen()
+		};
+		let theme = current_theme();
+		let fg_col = Some(self.fg_col.unwrap_or(&theme.text));
+		let bg_col = Some(self.bg_col.unwrap_or(&theme.fill));
+		let prefix_str: String = self.prefixes.iter().map(|prefix| prefix.to_string()).collect();
+		for (i, line) in self.lines().iter().enumerate() {
+			let x = match self.clip {
+				Some(rect) if self.align != Align::Start => rect.x + self.align.offset(str_width(line), rect.width),
+				_ => self.coords.0
+			};
+			let (text, coords) = match clip.clip_line(line, (x, self.coords.1.saturating_add(i as u16))) {
+				Some(clipped) => clipped,
+				None => continue
+			};
+			print_at_with_cols(&format!("{}{}{}", prefix_str, text, termion::style::Reset), coords, bg_col, fg_col);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn measures_wide_characters() {
+		assert_eq!(str_width("日本語"), 6);
+		assert_eq!(str_width("a\u{301}"), 1);
+		assert_eq!(take_width("日本語", 5), "日本");
+		assert_eq!(take_width("日本語", 1), "");
+		assert_eq!(take_width("abc", 0), "");
+		assert_eq!(skip_width("日本語", 3), ("語", 1));
+		assert_eq!(skip_width("日本語", 4), ("語", 0));
+		assert_eq!(skip_width("ab", 5), ("", 0));
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn truncates_with_an_ellipsis() {
+		assert_eq!(truncate("hello", 5), "hello");
+		assert_eq!(truncate("hello world", 6), "hello…");
+		assert_eq!(truncate("日本語", 4), "日…");
+		assert_eq!(truncate("hello", 1), "h");
+		assert_eq!(truncate("hello", 0), "");
+		assert_eq!(fill_width("日", 5), "日日 ");
+	}
+
+	#[test]
+	fn wraps_by_display_width() {
+		assert_eq!(wrap("the quick brown fox", 9), ["the quick", "brown fox"]);
+		assert_eq!(wrap("one\n\ntwo", 10), ["one", "", "two"]);
+		assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+		assert_eq!(wrap("日本語です", 4), ["日本", "語で", "す"]);
+		// Too narrow for even one wide glyph, each gets a line of its own
+		assert_eq!(wrap("日本", 1), ["日", "本"]);
+		assert!(wrap("anything", 0).is_empty());
+	}
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use termion::color::{AnsiValue, Color, Reset, Rgb};
+
+use super::glyphs::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorDepth {
+	None,
+	Ansi16,
+	Ansi256,
+	TrueColor
+}
+
+impl ColorDepth {
+	pub fn detect() -> ColorDepth {
+		if std::env::var_os("NO_COLOR").is_some() {
+			return ColorDepth::None;
+		}
+		let colorterm = std::env::var("COLORTERM").unwrap_or_default().to_ascii_lowercase();
+		if colorterm == "truecolor" || colorterm == "24bit" {
+			return ColorDepth::TrueColor;
+		}
+		let term = std::env::var("TERM").unwrap_or_default().to_ascii_lowercase();
+		if term.is_empty() || term == "dumb" {
+			ColorDepth::None
+		} else if term.contains("256color") || term.contains("truecolor") || term.contains("direct") {
+			ColorDepth::Ansi256
+		} else {
+			ColorDepth::Ansi16
+		}
+	}
+	fn from_u8(value: u8) -> ColorDepth {
+		match value {
+			0 => ColorDepth::None,
+			1 => ColorDepth::Ansi16,
+			2 => ColorDepth::Ansi256,
+			_ => ColorDepth::TrueColor
+		}
+	}
+}
+
+static COLOR_DEPTH: AtomicU8 = AtomicU8::new(3);
+
+pub fn color_depth() -> ColorDepth {
+	ColorDepth::from_u8(COLOR_DEPTH.load(Ordering::Relaxed))
+}
+
+pub fn set_color_depth(depth: ColorDepth) {
+	COLOR_DEPTH.store(depth as u8, Ordering::Relaxed);
+}
+
+// Standard xterm values for the 16 basic colours, used to find the closest match
+static ANSI_16: [(u8, u8, u8); 16] = [
+	(0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+	(0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+	(127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+	(92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255)
+];
+
+// A theme colour, written out at whatever depth the terminal supports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeColor {
+	Default,
+	Rgb(u8, u8, u8)
+}
+
+impl ThemeColor {
+	pub fn to_ansi_256(r: u8, g: u8, b: u8) -> u8 {
+		let scale = |v: u8| ((v as u16 * 5 + 127) / 255) as u8;
+		16 + 36 * scale(r) + 6 * scale(g) + scale(b)
+	}
+	pub fn to_ansi_16(r: u8, g: u8, b: u8) -> u8 {
+		let distance = |(cr, cg, cb): &(u8, u8, u8)| {
+			let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+			d(r, *cr) + d(g, *cg) + d(b, *cb)
+		};
+		ANSI_16.iter().enumerate().min_by_key(|(_, col)| distance(col)).map(|(i, _)| i as u8).unwrap_or(7)
+	}
+	fn write(&self, f: &mut fmt::Formatter, background: bool) -> fmt::Result {
+		let (r, g, b) = match self {
+			ThemeColor::Default => return if background {Reset.write_bg(f)} else {Reset.write_fg(f)},
+			ThemeColor::Rgb(r, g, b) => (*r, *g, *b)
+		};
+		match color_depth() {
+			ColorDepth::None => if background {Reset.write_bg(f)} else {Reset.write_fg(f)},
+			ColorDepth::Ansi16 => {
+				let n = ThemeColor::to_ansi_16(r, g, b);
+				let code = match (background, n < 8) {
+					(false, true) => 30 + n,
+					(false, false) => 90 + n - 8,
+					(true, true) => 40 + n,
+					(true, false) => 100 + n - 8
+				};
+				write!(f, "\x1b[{}m", code)
+			},
+			ColorDepth::Ansi256 => {
+				let col = AnsiValue(ThemeColor::to_ansi_256(r, g, b));
+				if background {col.write_bg(f)} else {col.write_fg(f)}
+			},
+			ColorDepth::TrueColor => if background {Rgb(r, g, b).write_bg(f)} else {Rgb(r, g, b).write_fg(f)}
+		}
+	}
+}
+
+impl Color for ThemeColor {
+	fn write_fg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.write(f, false)
+	}
+	fn write_bg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.write(f, true)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntityColors {
+	pub snake_body: ThemeColor,
+	pub snake_food: ThemeColor,
+	pub pong_paddle: ThemeColor,
+	pub pong_ball: ThemeColor,
+	// By piece, in the order I J L O S T Z
+	pub tetris_pieces: [ThemeColor; 7],
+	// Tile backgrounds, empty then 2, 4 and so on up to 2048, then anything bigger
+	pub tiles: [ThemeColor; 13],
+	// Numbers on the 2 and 4 tiles, then on the rest
+	pub tiles_text: [ThemeColor; 2],
+	// Minesweeper counts of neighbouring mines, from 1 to 8
+	pub mines_numbers: [ThemeColor; 8],
+	pub mines_mine: ThemeColor,
+	// Breakout bricks by row, repeating down the wall
+	pub breakout_rows: [ThemeColor; 6],
+	// Bricks with two hits left, then three or more
+	pub breakout_strong: [ThemeColor; 2],
+	// Invaders by row kind, from the top
+	pub invaders_aliens: [ThemeColor; 3],
+	// Also the aliens' bullets and the player being hit
+	pub invaders_ufo: ThemeColor,
+	pub invaders_bunker: ThemeColor,
+	// Tron trails and heads, for the first cycle then the second
+	pub tron_trails: [ThemeColor; 2],
+	pub tron_heads: [ThemeColor; 2],
+	pub tron_crash: ThemeColor
+}
+
+impl EntityColors {
+	// Everything in the terminal's own colours
+	pub fn plain() -> EntityColors {
+		EntityColors {
+			snake_body: ThemeColor::Default,
+			snake_food: ThemeColor::Default,
+			pong_paddle: ThemeColor::Default,
+			pong_ball: ThemeColor::Default,
+			tetris_pieces: [ThemeColor::Default; 7],
+			tiles: [ThemeColor::Default; 13],
+			tiles_text: [ThemeColor::Default; 2],
+			mines_numbers: [ThemeColor::Default; 8],
+			mines_mine: ThemeColor::Default,
+			breakout_rows: [ThemeColor::Default; 6],
+			breakout_strong: [ThemeColor::Default; 2],
+			invaders_aliens: [ThemeColor::Default; 3],
+			invaders_ufo: ThemeColor::Default,
+			invaders_bunker: ThemeColor::Default,
+			tron_trails: [ThemeColor::Default; 2],
+			tron_heads: [ThemeColor::Default; 2],
+			tron_crash: ThemeColor::Default
+		}
+	}
+	// The usual colours for each game's pieces, themes mostly differ in their snake and pong colours
+	pub fn colorful() -> EntityColors {
+		EntityColors {
+			tetris_pieces: [
+				ThemeColor::Rgb(0, 200, 220),
+				ThemeColor::Rgb(40, 90, 230),
+				ThemeColor::Rgb(240, 150, 30),
+				ThemeColor::Rgb(240, 220, 40),
+				ThemeColor::Rgb(60, 200, 60),
+				ThemeColor::Rgb(170, 60, 210),
+				ThemeColor::Rgb(220, 50, 50)
+			],
+			tiles: [
+				ThemeColor::Rgb(205, 193, 180),
+				ThemeColor::Rgb(238, 228, 218),
+				ThemeColor::Rgb(237, 224, 200),
+				ThemeColor::Rgb(242, 177, 121),
+				ThemeColor::Rgb(245, 149, 99),
+				ThemeColor::Rgb(246, 124, 95),
+				ThemeColor::Rgb(246, 94, 59),
+				ThemeColor::Rgb(237, 207, 114),
+				ThemeColor::Rgb(237, 204, 97),
+				ThemeColor::Rgb(237, 200, 80),
+				ThemeColor::Rgb(237, 197, 63),
+				ThemeColor::Rgb(237, 194, 46),
+				ThemeColor::Rgb(60, 58, 50)
+			],
+			tiles_text: [ThemeColor::Rgb(119, 110, 101), ThemeColor::Rgb(249, 246, 242)],
+			mines_numbers: [
+				ThemeColor::Rgb(80, 140, 255),
+				ThemeColor::Rgb(60, 180, 60),
+				ThemeColor::Rgb(230, 60, 60),
+				ThemeColor::Rgb(150, 90, 230),
+				ThemeColor::Rgb(190, 60, 40),
+				ThemeColor::Rgb(0, 170, 170),
+				ThemeColor::Rgb(200, 200, 200),
+				ThemeColor::Rgb(140, 140, 140)
+			],
+			mines_mine: ThemeColor::Rgb(220, 50, 50),
+			breakout_rows: [
+				ThemeColor::Rgb(220, 50, 50),
+				ThemeColor::Rgb(240, 150, 30),
+				ThemeColor::Rgb(240, 220, 40),
+				ThemeColor::Rgb(60, 200, 60),
+				ThemeColor::Rgb(40, 120, 230),
+				ThemeColor::Rgb(170, 60, 210)
+			],
+			breakout_strong: [ThemeColor::Rgb(160, 160, 170), ThemeColor::Rgb(235, 235, 240)],
+			invaders_aliens: [ThemeColor::Rgb(200, 80, 220), ThemeColor::Rgb(60, 190, 230), ThemeColor::Rgb(90, 210, 90)],
+			invaders_ufo: ThemeColor::Rgb(230, 60, 60),
+			invaders_bunker: ThemeColor::Rgb(60, 200, 60),
+			tron_trails: [ThemeColor::Rgb(60, 160, 255), ThemeColor::Rgb(255, 150, 40)],
+			tron_heads: [ThemeColor::Rgb(180, 220, 255), ThemeColor::Rgb(255, 215, 160)],
+			tron_crash: ThemeColor::Rgb(230, 50, 50),
+			..EntityColors::plain()
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+	pub name: &'static str,
+	pub border: ThemeColor,
+	pub fill: ThemeColor,
+	pub text: ThemeColor,
+	pub accent: ThemeColor,
+	pub entities: EntityColors,
+	pub glyphs: Glyphs
+}
+
+impl Theme {
+	pub fn classic() -> Theme {
+		Theme {
+			name: "classic",
+			border: ThemeColor::Default,
+			fill: ThemeColor::Default,
+			text: ThemeColor::Default,
+			accent: ThemeColor::Default,
+			entities: EntityColors {
+				snake_body: ThemeColor::Default,
+				snake_food: ThemeColor::Rgb(205, 0, 0),
+				pong_paddle: ThemeColor::Default,
+				pong_ball: ThemeColor::Default,
+				..EntityColors::colorful()
+			},
+			glyphs: Glyphs::unicode()
+		}
+	}
+	pub fn ocean() -> Theme {
+		Theme {
+			name: "ocean",
+			border: ThemeColor::Rgb(64, 160, 220),
+			fill: ThemeColor::Default,
+			text: ThemeColor::Rgb(200, 230, 255),
+			accent: ThemeColor::Rgb(0, 220, 200),
+			entities: EntityColors {
+				snake_body: ThemeColor::Rgb(0, 200, 160),
+				snake_food: ThemeColor::Rgb(255, 120, 80),
+				pong_paddle: ThemeColor::Rgb(120, 200, 255),
+				pong_ball: ThemeColor::Rgb(255, 255, 255),
+				..EntityColors::colorful()
+			},
+			glyphs: Glyphs::rounded()
+		}
+	}
+	pub fn forest() -> Theme {
+		Theme {
+			name: "forest",
+			border: ThemeColor::Rgb(110, 150, 60),
+			fill: ThemeColor::Default,
+			text: ThemeColor::Rgb(220, 210, 170),
+			accent: ThemeColor::Rgb(240, 190, 60),
+			entities: EntityColors {
+				snake_body: ThemeColor::Rgb(60, 180, 60),
+				snake_food: ThemeColor::Rgb(220, 40, 40),
+				pong_paddle: ThemeColor::Rgb(160, 110, 60),
+				pong_ball: ThemeColor::Rgb(240, 190, 60),
+				..EntityColors::colorful()
+			},
+			glyphs: Glyphs::unicode()
+		}
+	}
+	pub fn monochrome() -> Theme {
+		Theme {
+			name: "monochrome",
+			border: ThemeColor::Default,
+			fill: ThemeColor::Default,
+			text: ThemeColor::Default,
+			accent: ThemeColor::Default,
+			entities: EntityColors::plain(),
+			glyphs: Glyphs { ball: "●", ..Glyphs::unicode() }
+		}
+	}
+	pub fn high_contrast() -> Theme {
+		Theme {
+			name: "high-contrast",
+			border: ThemeColor::Rgb(255, 255, 255),
+			fill: ThemeColor::Rgb(0, 0, 0),
+			text: ThemeColor::Rgb(255, 255, 255),
+			accent: ThemeColor::Rgb(255, 255, 0),
+			entities: EntityColors {
+				snake_body: ThemeColor::Rgb(0, 255, 0),
+				snake_food: ThemeColor::Rgb(255, 0, 255),
+				pong_paddle: ThemeColor::Rgb(255, 255, 255),
+				pong_ball: ThemeColor::Rgb(255, 255, 0),
+				tetris_pieces: [
+					ThemeColor::Rgb(0, 255, 255),
+					ThemeColor::Rgb(80, 130, 255),
+					ThemeColor::Rgb(255, 160, 0),
+					ThemeColor::Rgb(255, 255, 0),
+					ThemeColor::Rgb(0, 255, 0),
+					ThemeColor::Rgb(255, 0, 255),
+					ThemeColor::Rgb(255, 0, 0)
+				],
+				tiles: [
+					ThemeColor::Rgb(0, 0, 0),
+					ThemeColor::Rgb(255, 255, 255),
+					ThemeColor::Rgb(255, 255, 255),
+					ThemeColor::Rgb(255, 255, 0),
+					ThemeColor::Rgb(255, 255, 0),
+					ThemeColor::Rgb(255, 160, 0),
+					ThemeColor::Rgb(255, 160, 0),
+					ThemeColor::Rgb(0, 255, 0),
+					ThemeColor::Rgb(0, 255, 0),
+					ThemeColor::Rgb(0, 255, 255),
+					ThemeColor::Rgb(0, 255, 255),
+					ThemeColor::Rgb(255, 0, 255),
+					ThemeColor::Rgb(255, 0, 255)
+				],
+				tiles_text: [ThemeColor::Rgb(0, 0, 0), ThemeColor::Rgb(0, 0, 0)],
+				mines_numbers: [
+					ThemeColor::Rgb(80, 160, 255),
+					ThemeColor::Rgb(0, 255, 0),
+					ThemeColor::Rgb(255, 60, 60),
+					ThemeColor::Rgb(255, 0, 255),
+					ThemeColor::Rgb(255, 160, 0),
+					ThemeColor::Rgb(0, 255, 255),
+					ThemeColor::Rgb(255, 255, 255),
+					ThemeColor::Rgb(200, 200, 200)
+				],
+				mines_mine: ThemeColor::Rgb(255, 0, 0),
+				breakout_rows: [
+					ThemeColor::Rgb(255, 0, 0),
+					ThemeColor::Rgb(255, 160, 0),
+					ThemeColor::Rgb(255, 255, 0),
+					ThemeColor::Rgb(0, 255, 0),
+					ThemeColor::Rgb(0, 255, 255),
+					ThemeColor::Rgb(255, 0, 255)
+				],
+				breakout_strong: [ThemeColor::Rgb(200, 200, 200), ThemeColor::Rgb(255, 255, 255)],
+				invaders_aliens: [ThemeColor::Rgb(255, 0, 255), ThemeColor::Rgb(0, 255, 255), ThemeColor::Rgb(0, 255, 0)],
+				invaders_ufo: ThemeColor::Rgb(255, 0, 0),
+				invaders_bunker: ThemeColor::Rgb(0, 255, 0),
+				tron_trails: [ThemeColor::Rgb(0, 255, 255), ThemeColor::Rgb(255, 255, 0)],
+				tron_heads: [ThemeColor::Rgb(255, 255, 255), ThemeColor::Rgb(255, 255, 255)],
+				tron_crash: ThemeColor::Rgb(255, 0, 0)
+			},
+			glyphs: Glyphs::heavy()
+		}
+	}
+	pub fn all() -> Vec<Theme> {
+		vec![
+			Theme::classic(),
+			Theme::ocean(),
+			Theme::forest(),
+			Theme::monochrome(),
+			Theme::high_contrast()
+		]
+	}
+	pub fn by_name(name: &str) -> Option<Theme> {
+		Theme::all().into_iter().find(|theme| theme.name.eq_ignore_ascii_case(name))
+	}
+}
+
+static THEME: RwLock<Option<Theme>> = RwLock::new(None);
+
+pub fn current_theme() -> Theme {
+	let mut theme = THEME.read().unwrap().unwrap_or_else(Theme::classic);
+	if ascii_only() {
+		theme.glyphs = Glyphs::ascii();
+	}
+	theme
+}
+
+pub fn set_theme(theme: Theme) {
+	*THEME.write().unwrap() = Some(theme);
+}
@@ -0,0 +1,77 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::boxes::BoxPrint;
+use super::layout::*;
+use super::text::*;
+use super::theme::current_theme;
+use crate::common::terminal::current_session;
+
+static TOAST_TIME: Duration = Duration::from_secs(3);
+
+pub(crate) struct Toast {
+	title: String,
+	text: String,
+	// Set the first time it's drawn
+	shown_at: Option<Instant>
+}
+
+// For the process's own terminal, each served session has its own
+static TOASTS: Mutex<Vec<Toast>> = Mutex::new(vec![]);
+
+fn with_toasts<R, F: FnOnce(&mut Vec<Toast>) -> R>(run: F) -> R {
+	match current_session() {
+		Some(session) => run(&mut session.toasts.lock().unwrap()),
+		None => run(&mut TOASTS.lock().unwrap())
+	}
+}
+
+// Queued to be drawn by the next few frames, one toast at a time
+pub fn show_toast(title: &str, text: &str) {
+	with_toasts(|toasts| toasts.push(Toast {
+		title: title.to_owned(),
+		text: text.to_owned(),
+		shown_at: None
+	}));
+}
+
+// Small box in the top right corner of the area
+pub fn draw_toast_box(title: &str, text: &str, area: Rect) {
+	let theme = current_theme();
+	let width = std::cmp::max(str_width(title), str_width(text)) + 4;
+	let rect = area.align((std::cmp::min(width, area.width), 4), Align::End, Align::Start);
+	let mut box_print = BoxPrint::in_rect(rect);
+	box_print.set_border_col(&theme.accent).print();
+	let inner = box_print.inner().inner(Padding::symmetric(1, 0));
+	TextPrint::in_rect(title, inner.row(0), Align::Start)
+		.set_max_width(inner.width)
+		.color_fg(&theme.accent)
+		.add_prefix(&termion::style::Bold)
+		.print();
+	TextPrint::in_rect(text, inner.row(1), Align::Start)
+		.set_max_width(inner.width)
+		.print();
+}
+
+// Games call this while drawing each frame and menus each time they're drawn, it does nothing when no toasts are waiting
+pub fn draw_toast() {
+	let Some((title, text)) = with_toasts(|toasts| {
+		toasts.retain(|toast| toast.shown_at.is_none_or(|shown_at| shown_at.elapsed() < TOAST_TIME));
+		let toast = toasts.first_mut()?;
+		toast.shown_at.get_or_insert_with(Instant::now);
+		Some((toast.title.clone(), toast.text.clone()))
+	}) else {return};
+	draw_toast_box(&title, &text, Rect::screen());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{screen::frame::capture, snapshot::assert_snapshot};
+
+	#[test]
+	fn snapshot_toast() {
+		let frame = capture((40, 8), || draw_toast_box("Achievement unlocked", "Long snake", Rect::new((1, 1), (40, 7))));
+		assert_snapshot("toast", &frame);
+	}
+}
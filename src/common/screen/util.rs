@@ -1,23 +1,84 @@
+use std::cell::RefCell;
+
 use termion::cursor::*;
 use termion::color::*;
 
+use crate::common::{spectate::current_spectators, terminal::current_terminal};
+
+use super::frame::*;
+use super::layout::Rect;
+use super::theme::current_theme;
+
 pub fn repeat_str(original_str: &str, num: u16) -> String {
-	let mut new_str = String::with_capacity((original_str.len() as u16 * num) as usize);
-	for _ in 0..num {
-		new_str.push_str(original_str);
+	original_str.repeat(num as usize)
+}
+
+thread_local! {
+	// Output held back while batching, see batch_output
+	static BATCH: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn write_text(text: &str) {
+	match current_terminal() {
+		Some(terminal) => terminal.write(text),
+		None => {
+			print!("{}", text);
+			let _ = std::io::Write::flush(&mut std::io::stdout());
+		}
 	}
-	return new_str
+}
+
+// Writes a line to the current session's terminal, or stdout without one
+fn write_line(text: &str) {
+	let batched = BATCH.with(|batch| match batch.borrow_mut().as_mut() {
+		Some(batch) => {
+			batch.push_str(text);
+			batch.push('\n');
+			true
+		},
+		None => false
+	});
+	if !batched {
+		write_text(&format!("{}\n", text));
+	}
+}
+
+// Sends everything drawn while the function runs in one write rather than a write for each
+// piece, so busy frames draw quickly and all at once
+pub fn batch_output<R, F: FnOnce() -> R>(draw: F) -> R {
+	if BATCH.with(|batch| batch.borrow().is_some()) {return draw()};
+	BATCH.with(|batch| batch.replace(Some(String::new())));
+	let result = draw();
+	let text = BATCH.with(|batch| batch.take()).unwrap_or_default();
+	if !text.is_empty() {
+		write_text(&text);
+	}
+	result
+}
+
+pub fn get_terminal_size() -> (u16, u16) {
+	if let Some(size) = with_capture(|frame| frame.size) {
+		return size;
+	}
+	if let Some(terminal) = current_terminal() {
+		return terminal.size();
+	}
+	termion::terminal_size().unwrap_or((80, 24))
 }
 
 pub fn get_centered_coords(size: (u16, u16)) -> (u16, u16) {
-	let (w, h) = termion::terminal_size().unwrap();
-	return (
-		w / 2 - size.0 / 2,
-		h / 2 - size.1 / 2,
+	let (w, h) = get_terminal_size();
+	(
+		std::cmp::max(1, (w / 2).saturating_sub(size.0 / 2)),
+		std::cmp::max(1, (h / 2).saturating_sub(size.1 / 2)),
 	)
 }
 
 pub fn print_at_with_cols(text: &str, coords: (u16, u16), bg: Option<&dyn Color>, fg: Option<&dyn Color>) {
+	if with_capture(|frame| frame.write(text, coords, bg.and_then(describe_color), fg.and_then(describe_color))).is_some() {return};
+	if let Some(spectators) = current_spectators() {
+		spectators.draw(|frame| frame.write(text, coords, bg.and_then(describe_color), fg.and_then(describe_color)));
+	}
 	let mut bg_col: &dyn termion::color::Color = &termion::color::Reset;
 	if let Some(col) = bg {
 		bg_col = col;
@@ -26,21 +87,54 @@ pub fn print_at_with_cols(text: &str, coords: (u16, u16), bg: Option<&dyn Color>
 	if let Some(col) = fg {
 		fg_col = col;
 	}
-	println!("{}{}{}{}{}{}", Goto(coords.0, coords.1), Bg(bg_col), Fg(fg_col), text, Bg(Reset), Fg(Reset));
+	write_line(&format!("{}{}{}{}{}{}", Goto(coords.0, coords.1), Bg(bg_col), Fg(fg_col), text, Bg(Reset), Fg(Reset)));
 }
 
 pub fn print_at(text: &str, coords: (u16, u16)) {
-	println!("{}{}", Goto(coords.0, coords.1), text);
+	if with_capture(|frame| frame.write(text, coords, None, None)).is_some() {return};
+	if let Some(spectators) = current_spectators() {
+		spectators.draw(|frame| frame.write(text, coords, None, None));
+	}
+	write_line(&format!("{}{}", Goto(coords.0, coords.1), text));
+}
+
+pub fn print_clipped(text: &str, coords: (u16, u16), clip: Option<Rect>, bg: Option<&dyn Color>, fg: Option<&dyn Color>) {
+	let clip = match clip {
+		Some(rect) => rect.intersection(Rect::screen()),
+		None => Rect::screen()
+	};
+	if let Some((clipped, coords)) = clip.clip_line(text, coords) {
+		print_at_with_cols(&clipped, coords, bg, fg);
+	}
 }
 
 pub fn clear_screen() {
-	let term_size = termion::terminal_size().unwrap();
-	for i in 1..=(term_size.1-1) {
-		println!("{}{}", termion::cursor::Goto(1, i), termion::clear::CurrentLine);
+	if with_capture(|frame| frame.clear()).is_some() {return};
+	let term_size = get_terminal_size();
+	if let Some(spectators) = current_spectators() {
+		spectators.clear(term_size);
+	}
+	for i in 1..term_size.1 {
+		write_line(&format!("{}{}", termion::cursor::Goto(1, i), termion::clear::CurrentLine));
 	}
 }
 
+// Has the terminal send clicks as input, in the SGR format `read_input` understands
+pub fn set_mouse_reporting(on: bool) {
+	if is_capturing() {return};
+	write_text(if on {"\x1b[?1000h\x1b[?1006h"} else {"\x1b[?1006l\x1b[?1000l"});
+}
+
+// Called once a screen has been drawn, spectators are sent it along with how many are watching
 pub fn cursor_to_end() {
-	let term_size = termion::terminal_size().unwrap();
-	println!("{}", termion::cursor::Goto(1, term_size.1 - 1))
-}
\ No newline at end of file
+	if is_capturing() {return};
+	let term_size = get_terminal_size();
+	if let Some(spectators) = current_spectators() {
+		let count = spectators.viewer_count();
+		if count > 0 {
+			print_at_with_cols(&format!(" {} watching ", count), (1, 1), None, Some(&current_theme().accent));
+		}
+		spectators.finish_frame();
+	}
+	write_line(&format!("{}", termion::cursor::Goto(1, std::cmp::max(1, term_size.1.saturating_sub(1)))));
+}
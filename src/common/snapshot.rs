@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use super::screen::frame::Frame;
+
+// Set to rewrite snapshots with what's drawn now, after checking the change is wanted
+pub static UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+// Snapshots live in tests/snapshots of the crate under test
+pub fn snapshot_path(name: &str) -> PathBuf {
+	let root = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+	root.join("tests").join("snapshots").join(format!("{}.snap", name))
+}
+
+fn describe_differences(expected: &str, actual: &str) -> String {
+	let expected_lines = expected.lines().collect::<Vec<&str>>();
+	let actual_lines = actual.lines().collect::<Vec<&str>>();
+	let mut out = String::new();
+	for i in 0..std::cmp::max(expected_lines.len(), actual_lines.len()) {
+		let (old, new) = (expected_lines.get(i), actual_lines.get(i));
+		if old == new {continue};
+		out.push_str(&format!("line {}:\n  - {}\n  + {}\n", i + 1, old.unwrap_or(&""), new.unwrap_or(&"")));
+	}
+	out
+}
+
+// Compares the frame, text and colours, with its stored snapshot
+pub fn assert_snapshot(name: &str, frame: &Frame) {
+	let path = snapshot_path(name);
+	let actual = frame.annotated();
+	if std::env::var_os(UPDATE_VAR).is_some() {
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir).expect("couldn't create snapshot directory");
+		}
+		std::fs::write(&path, actual).expect("couldn't write snapshot");
+		return;
+	}
+	let expected = match std::fs::read_to_string(&path) {
+		Ok(expected) => expected,
+		Err(_) => panic!("no snapshot at {}, run with {}=1 to create it\n{}", path.display(), UPDATE_VAR, actual)
+	};
+	if expected != actual {
+		panic!(
+			"frame doesn't match snapshot {}, run with {}=1 to update it\n{}",
+			path.display(), UPDATE_VAR, describe_differences(&expected, &actual)
+		);
+	}
+}
@@ -0,0 +1,206 @@
+use std::io::{self, ErrorKind, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::screen::frame::Frame;
+
+static SEND_TIME: Duration = Duration::from_millis(50);
+static WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+type Viewer = Box<dyn Write + Send>;
+
+// A socket left behind by an earlier run is removed. Anything else at the path, including a socket
+// that's still being listened on, is left alone and reported
+fn remove_stale_socket(path: &str) -> io::Result<()> {
+	match std::fs::symlink_metadata(path) {
+		Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+		Err(err) => Err(err),
+		Ok(metadata) if !metadata.file_type().is_socket() => {
+			Err(io::Error::new(ErrorKind::AlreadyExists, format!("{} already exists and isn't a socket", path)))
+		},
+		Ok(_) if UnixStream::connect(path).is_ok() => {
+			Err(io::Error::new(ErrorKind::AddrInUse, format!("{} is already in use", path)))
+		},
+		Ok(_) => std::fs::remove_file(path)
+	}
+}
+
+// Watchers of the screen, sent a copy of each frame once it's been fully drawn
+pub struct Spectators {
+	// The frame being drawn and the last one finished
+	drawing: Mutex<Frame>,
+	finished: Mutex<Frame>,
+	changed: AtomicBool,
+	viewers: Mutex<Vec<Viewer>>,
+	count: AtomicUsize
+}
+
+impl Spectators {
+	// Paths are unix sockets, anything else a TCP address.
+	// Viewers connect with `telnet`, `nc` or `nc -U` and see the screen read only
+	pub fn start(addr: &str) -> io::Result<Arc<Spectators>> {
+		let spectators = Arc::new(Spectators {
+			drawing: Mutex::new(Frame::new((0, 0))),
+			finished: Mutex::new(Frame::new((0, 0))),
+			changed: AtomicBool::new(false),
+			viewers: Mutex::new(vec![]),
+			count: AtomicUsize::new(0)
+		});
+		if addr.contains('/') {
+			remove_stale_socket(addr)?;
+			let listener = UnixListener::bind(addr)?;
+			let accepting = spectators.clone();
+			thread::spawn(move || for stream in listener.incoming().flatten() {
+				if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok() {
+					accepting.add_viewer(Box::new(stream));
+				}
+			});
+		} else {
+			let listener = TcpListener::bind(addr)?;
+			let accepting = spectators.clone();
+			thread::spawn(move || for stream in listener.incoming().flatten() {
+				if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok() && stream.set_nodelay(true).is_ok() {
+					accepting.add_viewer(Box::new(stream));
+				}
+			});
+		}
+		let sending = spectators.clone();
+		thread::spawn(move || loop {
+			thread::sleep(SEND_TIME);
+			sending.send_changes();
+		});
+		Ok(spectators)
+	}
+	// New viewers start with the whole of the current screen
+	fn add_viewer(&self, mut viewer: Viewer) {
+		let screen = format!("{}{}", termion::clear::All, self.finished.lock().unwrap().to_ansi());
+		if viewer.write_all(screen.as_bytes()).is_ok() {
+			self.viewers.lock().unwrap().push(viewer);
+			self.count.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+	fn send_changes(&self) {
+		if !self.changed.swap(false, Ordering::Relaxed) {return};
+		let screen = self.finished.lock().unwrap().to_ansi();
+		let mut viewers = self.viewers.lock().unwrap();
+		// Viewers that have gone, or stopped reading, are dropped
+		viewers.retain_mut(|viewer| viewer.write_all(screen.as_bytes()).is_ok());
+		self.count.store(viewers.len(), Ordering::Relaxed);
+	}
+	pub fn viewer_count(&self) -> usize {
+		self.count.load(Ordering::Relaxed)
+	}
+	pub fn draw<F: FnOnce(&mut Frame)>(&self, draw: F) {
+		draw(&mut self.drawing.lock().unwrap());
+	}
+	// Starts again on a blank frame, sized to match the screen
+	pub fn clear(&self, size: (u16, u16)) {
+		*self.drawing.lock().unwrap() = Frame::new(size);
+	}
+	pub fn finish_frame(&self) {
+		*self.finished.lock().unwrap() = self.drawing.lock().unwrap().clone();
+		self.changed.store(true, Ordering::Relaxed);
+	}
+}
+
+static SPECTATORS: Mutex<Option<Arc<Spectators>>> = Mutex::new(None);
+
+pub fn set_spectators(spectators: Arc<Spectators>) {
+	*SPECTATORS.lock().unwrap() = Some(spectators);
+}
+
+// Only set up when watching has been turned on
+pub fn current_spectators() -> Option<Arc<Spectators>> {
+	SPECTATORS.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Read;
+	use std::net::TcpStream;
+	use std::os::unix::net::UnixStream;
+
+	use super::*;
+
+	// Reads whatever arrives in the next half second
+	fn read_for_a_moment<R: Read>(reader: &mut R) -> String {
+		let mut buffer = [0; 4096];
+		let mut out = vec![];
+		while let Ok(length @ 1..) = reader.read(&mut buffer) {
+			out.extend_from_slice(&buffer[..length]);
+		}
+		String::from_utf8_lossy(&out).into_owned()
+	}
+
+	fn wait_for_viewers(spectators: &Spectators, count: usize) {
+		for _ in 0..100 {
+			if spectators.viewer_count() == count {return};
+			thread::sleep(Duration::from_millis(10));
+		}
+		panic!("expected {} viewers, found {}", count, spectators.viewer_count());
+	}
+
+	#[test]
+	fn viewers_get_finished_frames() {
+		let path = std::env::temp_dir().join(format!("terminal-games-spectate-{}.sock", std::process::id()));
+		let spectators = Spectators::start(path.to_str().unwrap()).unwrap();
+
+		spectators.clear((6, 2));
+		spectators.draw(|frame| frame.write("ready", (1, 1), None, None));
+		spectators.finish_frame();
+		let mut viewer = UnixStream::connect(&path).unwrap();
+		viewer.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+		wait_for_viewers(&spectators, 1);
+		assert!(read_for_a_moment(&mut viewer).contains("ready"));
+
+		// Half drawn frames aren't sent
+		spectators.clear((6, 2));
+		spectators.draw(|frame| frame.write("set", (1, 1), None, None));
+		assert!(!read_for_a_moment(&mut viewer).contains("set"));
+		spectators.draw(|frame| frame.write("go", (1, 2), None, None));
+		spectators.finish_frame();
+		let sent = read_for_a_moment(&mut viewer);
+		assert!(sent.contains("set") && sent.contains("go"));
+
+		// Noticed on the next frame that fails to send
+		drop(viewer);
+		for _ in 0..10 {
+			spectators.finish_frame();
+			thread::sleep(SEND_TIME * 2);
+		}
+		wait_for_viewers(&spectators, 0);
+		let _ = std::fs::remove_file(path);
+	}
+
+	#[test]
+	fn only_replaces_stale_sockets() {
+		let dir = std::env::temp_dir();
+		let file = dir.join(format!("terminal-games-spectate-{}.txt", std::process::id()));
+		std::fs::write(&file, "keep me").unwrap();
+		assert_eq!(Spectators::start(file.to_str().unwrap()).err().map(|err| err.kind()), Some(ErrorKind::AlreadyExists));
+		assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+		let _ = std::fs::remove_file(file);
+
+		let socket = dir.join(format!("terminal-games-stale-{}.sock", std::process::id()));
+		let listener = UnixListener::bind(&socket).unwrap();
+		assert_eq!(Spectators::start(socket.to_str().unwrap()).err().map(|err| err.kind()), Some(ErrorKind::AddrInUse));
+		drop(listener);
+		assert!(Spectators::start(socket.to_str().unwrap()).is_ok());
+		let _ = std::fs::remove_file(socket);
+	}
+
+	#[test]
+	fn tcp_viewers() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		drop(listener);
+		let spectators = Spectators::start(&addr).unwrap();
+		let _viewer = TcpStream::connect(&addr).unwrap();
+		wait_for_viewers(&spectators, 1);
+	}
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::config::*;
+use super::game::*;
+
+static RECENT_SCORES: usize = 20;
+static BEST_TIMES: usize = 3;
+
+// Older stats files kept just the single best time
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedTimes {
+	Best(f32),
+	Fastest(Vec<f32>)
+}
+
+fn load_times<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, Vec<f32>>, D::Error> {
+	let saved = BTreeMap::<String, SavedTimes>::deserialize(deserializer)?;
+	Ok(saved.into_iter().map(|(label, times)| match times {
+		SavedTimes::Best(time) => (label, vec![time]),
+		SavedTimes::Fastest(times) => (label, times)
+	}).collect())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct GameStats {
+	pub played: u32,
+	pub wins: u32,
+	pub losses: u32,
+	pub draws: u32,
+	// Games that returned a score, for the average
+	pub scored: u32,
+	pub total_score: f64,
+	pub best_score: Option<f32>,
+	// Seconds
+	pub play_time: u64,
+	// Oldest first
+	pub recent_scores: Vec<f32>,
+	// Best value of each game specific record, by label
+	pub records: BTreeMap<String, u32>,
+	// Fewest seconds for each game specific time, by label and fastest first
+	#[serde(deserialize_with = "load_times")]
+	pub best_times: BTreeMap<String, Vec<f32>>
+}
+
+impl GameStats {
+	pub fn record(&mut self, game_return: &GameReturn, play_time: Duration) {
+		self.played += 1;
+		match game_return.win_state {
+			WinState::Win => self.wins += 1,
+			WinState::Lose => self.losses += 1,
+			WinState::Draw => self.draws += 1,
+			WinState::Disconnected | WinState::None => {}
+		}
+		if let Some(score) = game_return.score.player_score() {
+			self.scored += 1;
+			self.total_score += score as f64;
+			self.best_score = Some(self.best_score.map_or(score, |best| best.max(score)));
+			self.recent_scores.push(score);
+			let excess = self.recent_scores.len().saturating_sub(RECENT_SCORES);
+			self.recent_scores.drain(..excess);
+		}
+		self.play_time += play_time.as_secs();
+		for (label, value) in &game_return.records {
+			let best = self.records.entry(label.to_string()).or_insert(0);
+			*best = std::cmp::max(*best, *value);
+		}
+		for (label, time) in &game_return.times {
+			let times = self.best_times.entry(label.to_string()).or_default();
+			times.push(*time);
+			times.sort_by(f32::total_cmp);
+			times.truncate(BEST_TIMES);
+		}
+	}
+	pub fn average_score(&self) -> Option<f32> {
+		if self.scored == 0 {return None};
+		Some((self.total_score / self.scored as f64) as f32)
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Stats {
+	// By game id
+	pub games: BTreeMap<String, GameStats>
+}
+
+pub fn get_stats_path() -> Option<PathBuf> {
+	get_data_dir().map(|dir| dir.join("stats.toml"))
+}
+
+impl Stats {
+	pub fn load() -> Result<Stats, ConfigError> {
+		load_toml(get_stats_path().ok_or(ConfigError::NoDataDir)?)
+	}
+	pub fn save(&self) -> Result<(), ConfigError> {
+		save_toml(get_stats_path().ok_or(ConfigError::NoDataDir)?, self)
+	}
+	pub fn game(&self, game_id: &str) -> GameStats {
+		self.games.get(game_id).cloned().unwrap_or_default()
+	}
+}
+
+// Adds a finished game to the saved stats
+pub fn record_game(game_id: &str, game_return: &GameReturn, play_time: Duration) -> Result<(), ConfigError> {
+	let mut stats = Stats::load()?;
+	stats.games.entry(game_id.to_owned()).or_default().record(game_return, play_time);
+	stats.save()
+}
+
+pub fn format_play_time(seconds: u64) -> String {
+	let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+	if hours > 0 {
+		format!("{}h {:02}m", hours, minutes)
+	} else {
+		format!("{}m {:02}s", minutes, seconds)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn game_return(score: f32, win_state: WinState) -> GameReturn {
+		GameReturn {
+			score: Score::SinglePlayer(score),
+			win_state,
+			records: vec![("Longest snake", score as u32 + 1)],
+			times: vec![]
+		}
+	}
+
+	#[test]
+	fn records_results_and_bests() {
+		let mut stats = GameStats::default();
+		stats.record(&game_return(4.0, WinState::Lose), Duration::from_secs(30));
+		stats.record(&game_return(10.0, WinState::Win), Duration::from_secs(45));
+		stats.record(&game_return(7.0, WinState::Draw), Duration::from_secs(5));
+		assert_eq!((stats.played, stats.wins, stats.losses, stats.draws), (3, 1, 1, 1));
+		assert_eq!(stats.best_score, Some(10.0));
+		assert_eq!(stats.average_score(), Some(7.0));
+		assert_eq!(stats.play_time, 80);
+		assert_eq!(stats.records.get("Longest snake"), Some(&11));
+		assert_eq!(stats.recent_scores, vec![4.0, 10.0, 7.0]);
+	}
+
+	#[test]
+	fn keeps_only_recent_scores() {
+		let mut stats = GameStats::default();
+		for score in 0..30 {
+			stats.record(&game_return(score as f32, WinState::Lose), Duration::ZERO);
+		}
+		assert_eq!(stats.recent_scores.len(), RECENT_SCORES);
+		assert_eq!(stats.recent_scores.first(), Some(&10.0));
+	}
+
+	#[test]
+	fn keeps_the_fastest_times() {
+		let mut stats = GameStats::default();
+		for time in [42.5, 30.1, 55.0, 12.0] {
+			stats.record(&GameReturn { times: vec![("Fastest win", time)], ..GameReturn::default() }, Duration::ZERO);
+		}
+		assert_eq!(stats.best_times.get("Fastest win"), Some(&vec![12.0, 30.1, 42.5]));
+	}
+
+	#[test]
+	fn loads_a_single_best_time() {
+		let stats: GameStats = toml::from_str("[best_times]\n\"Beginner time\" = 12.5\n\"Expert time\" = [80.0, 95.5]").unwrap();
+		assert_eq!(stats.best_times.get("Beginner time"), Some(&vec![12.5]));
+		assert_eq!(stats.best_times.get("Expert time"), Some(&vec![80.0, 95.5]));
+	}
+
+	#[test]
+	fn games_without_a_score_arent_averaged() {
+		let mut stats = GameStats::default();
+		stats.record(&GameReturn { score: Score::None, win_state: WinState::None, records: vec![], times: vec![] }, Duration::ZERO);
+		assert_eq!(stats.played, 1);
+		assert_eq!(stats.average_score(), None);
+	}
+
+	#[test]
+	fn formats_play_time() {
+		assert_eq!(format_play_time(65), "1m 05s");
+		assert_eq!(format_play_time(3720), "1h 02m");
+	}
+}
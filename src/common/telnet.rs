@@ -0,0 +1,366 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use console::Key;
+
+use super::terminal::Terminal;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+// The server echoes (by drawing), the client sends each key as it's pressed and reports its window size
+static NEGOTIATION: [u8; 12] = [
+	IAC, WILL, ECHO,
+	IAC, WILL, SUPPRESS_GO_AHEAD,
+	IAC, DO, SUPPRESS_GO_AHEAD,
+	IAC, DO, NAWS
+];
+
+static DEFAULT_SIZE: (u16, u16) = (80, 24);
+static SIZE_WAIT_TIME: Duration = Duration::from_millis(500);
+// No real sequence is this long, so anything longer is dropped rather than kept waiting for its end
+static MAX_SEQUENCE_LENGTH: usize = 64;
+// Connections that don't send anything for this long are closed
+static IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TelnetEvent {
+	Key(Key),
+	Resize(u16, u16),
+	// Bytes to send back to the client
+	Reply(Vec<u8>),
+	// The client pressed Ctrl-C
+	Interrupt
+}
+
+#[derive(Debug, Default)]
+pub struct TelnetDecoder {
+	pending: Vec<u8>,
+	after_cr: bool,
+	// Skipping what's left of a subnegotiation that was too long
+	discarding: bool
+}
+
+impl TelnetDecoder {
+	pub fn new() -> TelnetDecoder {
+		TelnetDecoder::default()
+	}
+	// Bytes of a sequence that's only partly arrived are kept until the rest does
+	pub fn feed(&mut self, bytes: &[u8]) -> Vec<TelnetEvent> {
+		self.pending.extend_from_slice(bytes);
+		let mut events = vec![];
+		let mut start = 0;
+		while start < self.pending.len() {
+			if self.discarding {
+				let (length, finished) = subnegotiation_end(&self.pending[start..]);
+				start += length;
+				self.discarding = !finished;
+				if finished {continue} else {break};
+			}
+			let Some((length, event)) = decode(&self.pending[start..], &mut self.after_cr) else {break};
+			start += length;
+			events.extend(event);
+		}
+		self.pending.drain(..start);
+		if self.pending.len() > MAX_SEQUENCE_LENGTH {
+			if self.pending.starts_with(&[IAC, SB]) {
+				let (length, _) = subnegotiation_end(&self.pending[2..]);
+				self.pending.drain(..length + 2);
+				self.discarding = true;
+			} else {
+				self.pending.clear();
+			}
+		}
+		events
+	}
+}
+
+// The event at the start of the bytes and how many bytes it used, None if more bytes are needed
+fn decode(bytes: &[u8], after_cr: &mut bool) -> Option<(usize, Option<TelnetEvent>)> {
+	let follows_cr = std::mem::replace(after_cr, false);
+	let key = |length, key| Some((length, Some(TelnetEvent::Key(key))));
+	match bytes {
+		[IAC] | [IAC, WILL..=DONT] => None,
+		[IAC, SB, ..] => decode_subnegotiation(bytes),
+		[IAC, command @ WILL..=DONT, option, ..] => Some((3, refuse(*command, *option))),
+		[IAC, _, ..] => Some((2, None)),
+		// Arrow keys, whether the client is in normal or application cursor mode.
+		// Anything else is passed on whole like console does, mouse reports included
+		[0x1b, b'[' | b'O', rest @ ..] => {
+			let end = rest.iter().position(|b| (0x40..=0x7e).contains(b))?;
+			let sequence = match rest[end] {
+				b'A' => Key::ArrowUp,
+				b'B' => Key::ArrowDown,
+				b'C' => Key::ArrowRight,
+				b'D' => Key::ArrowLeft,
+				_ => Key::UnknownEscSeq(bytes[1..end + 3].iter().map(|b| *b as char).collect())
+			};
+			key(end + 3, sequence)
+		},
+		// Escape on its own, sequences arrive all at once
+		[0x1b, ..] => key(1, Key::Escape),
+		// Clients end lines with CR LF or CR NUL, some with a bare LF
+		[b'\r', ..] => {
+			*after_cr = true;
+			key(1, Key::Enter)
+		},
+		[b'\n' | 0, ..] if follows_cr => Some((1, None)),
+		[b'\n', ..] => key(1, Key::Enter),
+		[0x03, ..] => Some((1, Some(TelnetEvent::Interrupt))),
+		[0x7f | 0x08, ..] => key(1, Key::Backspace),
+		[b'\t', ..] => key(1, Key::Tab),
+		[first, ..] if *first < 0x20 => Some((1, None)),
+		[first, ..] => {
+			let length = match first.leading_ones() {
+				0 => 1,
+				n @ 2..=4 => n as usize,
+				_ => return Some((1, None))
+			};
+			if bytes.len() < length {return None};
+			let c = std::str::from_utf8(&bytes[..length]).ok().and_then(|text| text.chars().next());
+			Some((length, c.map(|c| TelnetEvent::Key(Key::Char(c)))))
+		},
+		[] => None
+	}
+}
+
+// Options the client offers or asks for that the server hasn't mentioned are turned down
+fn refuse(command: u8, option: u8) -> Option<TelnetEvent> {
+	let reply = match (command, option) {
+		(DO, ECHO | SUPPRESS_GO_AHEAD) | (WILL, SUPPRESS_GO_AHEAD | NAWS) => return None,
+		(DO, _) => WONT,
+		(WILL, _) => DONT,
+		_ => return None
+	};
+	Some(TelnetEvent::Reply(vec![IAC, reply, option]))
+}
+
+// How many bytes of the subnegotiation data have been checked, and whether that includes the IAC SE
+// ending it. A last byte that could start an escaped IAC or the ending isn't counted yet
+fn subnegotiation_end(bytes: &[u8]) -> (usize, bool) {
+	let mut i = 0;
+	loop {
+		match bytes.get(i..i + 2) {
+			Some([IAC, SE]) => return (i + 2, true),
+			Some([IAC, IAC]) => i += 2,
+			Some(_) => i += 1,
+			None if bytes.get(i) == Some(&IAC) => return (i, false),
+			None => return (bytes.len(), false)
+		}
+	}
+}
+
+fn decode_subnegotiation(bytes: &[u8]) -> Option<(usize, Option<TelnetEvent>)> {
+	let mut data = vec![];
+	let mut i = 2;
+	loop {
+		match bytes.get(i..i + 2)? {
+			[IAC, IAC] => data.push(IAC),
+			[IAC, SE] => break,
+			[byte, _] => {
+				data.push(*byte);
+				i += 1;
+				continue;
+			},
+			_ => return None
+		}
+		i += 2;
+	}
+	let event = match data[..] {
+		[NAWS, w1, w0, h1, h0] => {
+			let size = (u16::from_be_bytes([w1, w0]), u16::from_be_bytes([h1, h0]));
+			if size.0 > 0 && size.1 > 0 {Some(TelnetEvent::Resize(size.0, size.1))} else {None}
+		},
+		_ => None
+	};
+	Some((i + 2, event))
+}
+
+// A telnet client on the other end of a connection, played on in character at a time mode
+pub struct TelnetTerminal {
+	stream: Mutex<TcpStream>,
+	size: Mutex<Option<(u16, u16)>>,
+	keys: Mutex<Receiver<Key>>,
+	closed: AtomicBool
+}
+
+impl TelnetTerminal {
+	pub fn start(stream: TcpStream) -> io::Result<Arc<TelnetTerminal>> {
+		stream.set_nodelay(true)?;
+		stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+		let mut reader = stream.try_clone()?;
+		(&stream).write_all(&NEGOTIATION)?;
+		let (sender, receiver) = mpsc::channel();
+		let terminal = Arc::new(TelnetTerminal {
+			stream: Mutex::new(stream),
+			size: Mutex::new(None),
+			keys: Mutex::new(receiver),
+			closed: AtomicBool::new(false)
+		});
+
+		let reading = terminal.clone();
+		thread::spawn(move || {
+			let mut decoder = TelnetDecoder::new();
+			let mut buffer = [0; 256];
+			'read: while let Ok(length @ 1..) = reader.read(&mut buffer) {
+				for event in decoder.feed(&buffer[..length]) {
+					match event {
+						TelnetEvent::Key(key) => if sender.send(key).is_err() {break 'read},
+						TelnetEvent::Resize(w, h) => *reading.size.lock().unwrap() = Some((w, h)),
+						TelnetEvent::Reply(bytes) => reading.write_bytes(&bytes),
+						TelnetEvent::Interrupt => break 'read
+					}
+				}
+			}
+			reading.close();
+		});
+
+		// Most clients report their size straight away, so give them a moment before the first screen is drawn
+		for _ in 0..SIZE_WAIT_TIME.as_millis() / 10 {
+			if terminal.size.lock().unwrap().is_some() || terminal.is_closed() {break};
+			thread::sleep(Duration::from_millis(10));
+		}
+		Ok(terminal)
+	}
+	fn write_bytes(&self, bytes: &[u8]) {
+		if self.stream.lock().unwrap().write_all(bytes).is_err() {
+			self.close();
+		}
+	}
+	// Also wakes up anything waiting for a key, which then sees the terminal has closed
+	pub fn close(&self) {
+		self.closed.store(true, Ordering::Relaxed);
+		let _ = self.stream.lock().unwrap().shutdown(Shutdown::Both);
+	}
+}
+
+impl Terminal for TelnetTerminal {
+	// The client's terminal expects a carriage return before each new line
+	fn write(&self, text: &str) {
+		self.write_bytes(text.replace('\n', "\r\n").as_bytes());
+	}
+	fn size(&self) -> (u16, u16) {
+		self.size.lock().unwrap().unwrap_or(DEFAULT_SIZE)
+	}
+	fn read_key(&self) -> Option<Key> {
+		self.keys.lock().unwrap().recv().ok()
+	}
+	fn is_closed(&self) -> bool {
+		self.closed.load(Ordering::Relaxed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::TcpListener;
+
+	use super::*;
+
+	fn keys(events: Vec<TelnetEvent>) -> Vec<Key> {
+		events.into_iter().filter_map(|event| match event {
+			TelnetEvent::Key(key) => Some(key),
+			_ => None
+		}).collect()
+	}
+
+	#[test]
+	fn decodes_keys() {
+		let mut decoder = TelnetDecoder::new();
+		let events = decoder.feed(b"aZ\x1b[A\x1bOB\r\0\r\nx\n\x7f\t");
+		assert_eq!(keys(events), [
+			Key::Char('a'), Key::Char('Z'), Key::ArrowUp, Key::ArrowDown,
+			Key::Enter, Key::Enter, Key::Char('x'), Key::Enter, Key::Backspace, Key::Tab
+		]);
+		assert_eq!(keys(decoder.feed(b"\x1b")), [Key::Escape]);
+		assert_eq!(decoder.feed(&[0x03]), [TelnetEvent::Interrupt]);
+	}
+
+	#[test]
+	fn passes_on_mouse_reports() {
+		let mut decoder = TelnetDecoder::new();
+		assert_eq!(decoder.feed(b"\x1b[<0;12"), []);
+		assert_eq!(keys(decoder.feed(b";5Mq")), [Key::UnknownEscSeq("[<0;12;5M".chars().collect()), Key::Char('q')]);
+	}
+
+	#[test]
+	fn waits_for_split_sequences() {
+		let mut decoder = TelnetDecoder::new();
+		assert_eq!(decoder.feed(b"\x1b["), []);
+		assert_eq!(keys(decoder.feed(b"C")), [Key::ArrowRight]);
+		let snowman = "☃".as_bytes();
+		assert_eq!(decoder.feed(&snowman[..1]), []);
+		assert_eq!(keys(decoder.feed(&snowman[1..])), [Key::Char('☃')]);
+		assert_eq!(decoder.feed(&[IAC, SB, NAWS, 0, 100]), []);
+		assert_eq!(decoder.feed(&[0, 30, IAC, SE]), [TelnetEvent::Resize(100, 30)]);
+	}
+
+	#[test]
+	fn drops_overlong_sequences() {
+		let mut decoder = TelnetDecoder::new();
+		assert_eq!(decoder.feed(&[IAC, SB, NAWS]), []);
+		for _ in 0..1000 {
+			assert_eq!(decoder.feed(&[1; 100]), []);
+			assert!(decoder.pending.len() <= MAX_SEQUENCE_LENGTH);
+		}
+		// An escaped IAC followed by SE is still data, the sequence only ends at an unescaped one
+		assert_eq!(decoder.feed(&[IAC]), []);
+		assert_eq!(decoder.feed(&[IAC, SE, IAC]), []);
+		assert_eq!(keys(decoder.feed(&[SE, b'q'])), [Key::Char('q')]);
+
+		let mut sequence = b"\x1b[".to_vec();
+		sequence.extend_from_slice(&[b';'; 100]);
+		assert_eq!(decoder.feed(&sequence), []);
+		assert!(decoder.pending.is_empty());
+		assert_eq!(keys(decoder.feed(b"x")), [Key::Char('x')]);
+	}
+
+	#[test]
+	fn handles_negotiation() {
+		let mut decoder = TelnetDecoder::new();
+		// An escaped 255 in the window size
+		let events = decoder.feed(&[IAC, WILL, NAWS, IAC, SB, NAWS, 0, IAC, IAC, 0, 40, IAC, SE, IAC, DO, ECHO, IAC, WILL, 24, b'q']);
+		assert_eq!(events, [
+			TelnetEvent::Resize(255, 40),
+			TelnetEvent::Reply(vec![IAC, DONT, 24]),
+			TelnetEvent::Key(Key::Char('q'))
+		]);
+	}
+
+	#[test]
+	fn terminal_over_localhost() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (stream, _) = listener.accept().unwrap();
+		client.write_all(&[IAC, SB, NAWS, 0, 120, 0, 40, IAC, SE]).unwrap();
+		let terminal = TelnetTerminal::start(stream).unwrap();
+		assert_eq!(terminal.size(), (120, 40));
+
+		let mut negotiation = [0; NEGOTIATION.len()];
+		client.read_exact(&mut negotiation).unwrap();
+		assert_eq!(negotiation, NEGOTIATION);
+		terminal.write("one\ntwo\n");
+		let mut text = [0; 10];
+		client.read_exact(&mut text).unwrap();
+		assert_eq!(&text, b"one\r\ntwo\r\n");
+
+		client.write_all(b"\x1b[D").unwrap();
+		assert_eq!(terminal.read_key(), Some(Key::ArrowLeft));
+		drop(client);
+		assert_eq!(terminal.read_key(), None);
+		assert!(terminal.is_closed());
+	}
+}
@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use console::Key;
+
+use super::achievements::Tracker;
+use super::screen::toast::Toast;
+
+// Somewhere other than the process's own terminal to play on, such as a network connection
+pub trait Terminal: Send + Sync {
+	fn write(&self, text: &str);
+	fn size(&self) -> (u16, u16);
+	// Blocks until a key is pressed, None once the terminal has closed
+	fn read_key(&self) -> Option<Key>;
+	fn is_closed(&self) -> bool;
+}
+
+// A player on a terminal other than the process's own, with what they've unlocked and the toasts
+// waiting to be shown to them. The process's own terminal keeps these in statics instead
+pub struct Session {
+	pub terminal: Arc<dyn Terminal>,
+	pub(crate) tracker: Mutex<Option<Tracker>>,
+	pub(crate) toasts: Mutex<Vec<Toast>>
+}
+
+thread_local! {
+	static SESSION: RefCell<Option<Arc<Session>>> = const { RefCell::new(None) };
+}
+
+// Runs the function with drawing and input going to the given terminal instead of stdout and stdin,
+// in a new session of its own
+pub fn with_terminal<R, F: FnOnce() -> R>(terminal: Arc<dyn Terminal>, run: F) -> R {
+	with_session(Arc::new(Session {
+		terminal,
+		tracker: Mutex::new(None),
+		toasts: Mutex::new(vec![])
+	}), run)
+}
+
+// Like capturing this is per thread, input handlers spawned meanwhile carry the session with them
+pub fn with_session<R, F: FnOnce() -> R>(session: Arc<Session>, run: F) -> R {
+	let previous = SESSION.with(|current| current.replace(Some(session)));
+	let result = run();
+	SESSION.with(|current| current.replace(previous));
+	result
+}
+
+pub fn current_session() -> Option<Arc<Session>> {
+	SESSION.with(|current| current.borrow().clone())
+}
+
+// A thread that draws, reads keys and unlocks achievements in the same session as this one
+pub fn spawn_in_session<R, F>(run: F) -> JoinHandle<R> where R: Send + 'static, F: FnOnce() -> R + Send + 'static {
+	let session = current_session();
+	thread::spawn(move || match session {
+		Some(session) => with_session(session, run),
+		None => run()
+	})
+}
+
+pub fn current_terminal() -> Option<Arc<dyn Terminal>> {
+	current_session().map(|session| session.terminal.clone())
+}
+
+// Players connected to a server share its owner's settings and controls, so they can't change them
+pub fn is_served() -> bool {
+	current_session().is_some()
+}
+
+// Only terminals other than the process's own can close while the games are running
+pub fn input_closed() -> bool {
+	current_terminal().is_some_and(|terminal| terminal.is_closed())
+}
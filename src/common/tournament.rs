@@ -0,0 +1,306 @@
+use super::config::Difficulty;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntrantKind {
+	Human,
+	Computer(Difficulty)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entrant {
+	pub name: String,
+	pub kind: EntrantKind
+}
+
+impl Entrant {
+	pub fn human(name: &str) -> Entrant {
+		Entrant { name: name.to_owned(), kind: EntrantKind::Human }
+	}
+	pub fn computer(name: &str, difficulty: Difficulty) -> Entrant {
+		Entrant { name: name.to_owned(), kind: EntrantKind::Computer(difficulty) }
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+	SingleElimination,
+	DoubleElimination,
+	RoundRobin
+}
+
+impl Format {
+	pub fn all() -> [Format; 3] {
+		[Format::SingleElimination, Format::DoubleElimination, Format::RoundRobin]
+	}
+	pub fn name(&self) -> &'static str {
+		match self {
+			Format::SingleElimination => "single elimination",
+			Format::DoubleElimination => "double elimination",
+			Format::RoundRobin => "round robin"
+		}
+	}
+}
+
+// Players are indexes into the tournament's entrants
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+	pub players: (usize, usize),
+	pub result: Option<(u16, u16)>
+}
+
+impl Match {
+	fn new(players: (usize, usize)) -> Match {
+		Match { players, result: None }
+	}
+	pub fn winner(&self) -> Option<usize> {
+		let (left, right) = self.result?;
+		if left > right {Some(self.players.0)} else {Some(self.players.1)}
+	}
+	pub fn loser(&self) -> Option<usize> {
+		let (left, right) = self.result?;
+		if left > right {Some(self.players.1)} else {Some(self.players.0)}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round {
+	pub label: String,
+	pub matches: Vec<Match>,
+	// Sits the round out, going through as if they'd won
+	pub bye: Option<usize>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+	pub player: usize,
+	pub wins: u16,
+	pub losses: u16,
+	pub points_for: u16,
+	pub points_against: u16
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tournament {
+	pub format: Format,
+	pub entrants: Vec<Entrant>,
+	pub rounds: Vec<Round>
+}
+
+impl Tournament {
+	pub fn new(format: Format, entrants: Vec<Entrant>) -> Tournament {
+		let mut tournament = Tournament { format, entrants, rounds: vec![] };
+		match format {
+			Format::RoundRobin => tournament.rounds = round_robin(tournament.entrants.len()),
+			_ => tournament.add_round()
+		}
+		tournament
+	}
+	// The round and match index of the next match to play, None once the tournament's over
+	pub fn next_match(&self) -> Option<(usize, usize)> {
+		self.rounds.iter().enumerate().find_map(|(round_index, round)| {
+			let match_index = round.matches.iter().position(|played| played.result.is_none())?;
+			Some((round_index, match_index))
+		})
+	}
+	// Draws don't settle anything, the match stays unplayed to be played again
+	pub fn record(&mut self, round: usize, index: usize, result: (u16, u16)) {
+		if result.0 == result.1 {return};
+		let Some(played) = self.rounds.get_mut(round).and_then(|round| round.matches.get_mut(index)) else {return};
+		played.result = Some(result);
+		if self.format != Format::RoundRobin && self.next_match().is_none() {
+			self.add_round();
+		}
+	}
+	fn matches(&self) -> impl Iterator<Item = &Match> {
+		self.rounds.iter().flat_map(|round| round.matches.iter())
+	}
+	pub fn losses(&self, player: usize) -> usize {
+		self.matches().filter(|played| played.loser() == Some(player)).count()
+	}
+	fn byes(&self, player: usize) -> usize {
+		self.rounds.iter().filter(|round| round.bye == Some(player)).count()
+	}
+	// Those still in an elimination tournament, everyone in a round robin
+	pub fn remaining(&self) -> Vec<usize> {
+		let lives = match self.format {
+			Format::SingleElimination => 1,
+			Format::DoubleElimination => 2,
+			Format::RoundRobin => usize::MAX
+		};
+		(0..self.entrants.len()).filter(|player| self.losses(*player) < lives).collect()
+	}
+	fn add_round(&mut self) {
+		let remaining = self.remaining();
+		if remaining.len() < 2 {return};
+		let label = if remaining.len() == 2 {"Final".to_owned()} else {format!("Round {}", self.rounds.len() + 1)};
+		let round = if self.format == Format::DoubleElimination && remaining.len() > 2 {
+			// Those yet to lose only play each other, as do those on their last life
+			let (unbeaten, last_life) = remaining.iter().partition::<Vec<usize>, _>(|player| self.losses(**player) == 0);
+			let (mut matches, unbeaten_bye) = self.pair(unbeaten);
+			let (last_life_matches, last_life_bye) = self.pair(last_life);
+			matches.extend(last_life_matches);
+			// With an odd number in both pools the two left over meet
+			let bye = match (unbeaten_bye, last_life_bye) {
+				(Some(first), Some(second)) => {
+					matches.push(Match::new((first, second)));
+					None
+				},
+				(first, second) => first.or(second)
+			};
+			Round { label, matches, bye }
+		} else {
+			let (matches, bye) = self.pair(remaining);
+			Round { label, matches, bye }
+		};
+		self.rounds.push(round);
+	}
+	// Pairs players in order, the one with the fewest byes so far, latest on a tie, sitting out if there's an odd number
+	fn pair(&self, mut players: Vec<usize>) -> (Vec<Match>, Option<usize>) {
+		let bye = if players.len() % 2 == 1 {
+			let index = players.iter().enumerate().rev().min_by_key(|(_, player)| self.byes(**player)).map(|(index, _)| index);
+			index.map(|index| players.remove(index))
+		} else {
+			None
+		};
+		let matches = players.chunks(2).map(|pair| Match::new((pair[0], pair[1]))).collect();
+		(matches, bye)
+	}
+	// Most wins first, then by points difference
+	pub fn standings(&self) -> Vec<Standing> {
+		let mut standings = (0..self.entrants.len()).map(|player| {
+			let mut standing = Standing { player, wins: 0, losses: 0, points_for: 0, points_against: 0 };
+			for played in self.matches() {
+				let Some((left, right)) = played.result else {continue};
+				let (scored, conceded) = match played.players {
+					(left_player, _) if left_player == player => (left, right),
+					(_, right_player) if right_player == player => (right, left),
+					_ => continue
+				};
+				standing.points_for += scored;
+				standing.points_against += conceded;
+				if scored > conceded {standing.wins += 1} else {standing.losses += 1};
+			}
+			standing
+		}).collect::<Vec<Standing>>();
+		standings.sort_by_key(|standing| (
+			std::cmp::Reverse(standing.wins),
+			std::cmp::Reverse(standing.points_for as i32 - standing.points_against as i32)
+		));
+		standings
+	}
+	pub fn champion(&self) -> Option<usize> {
+		if self.next_match().is_some() {return None};
+		match self.format {
+			Format::RoundRobin => self.standings().first().map(|standing| standing.player),
+			_ => match self.remaining()[..] {
+				[champion] => Some(champion),
+				_ => None
+			}
+		}
+	}
+}
+
+// Everyone plays everyone once, a round at a time by the circle method
+fn round_robin(count: usize) -> Vec<Round> {
+	// An odd player out meets the empty seat, which is a bye
+	let seats = count + count % 2;
+	let mut circle = (0..seats).map(|player| if player < count {Some(player)} else {None}).collect::<Vec<Option<usize>>>();
+	let mut rounds = vec![];
+	for round in 0..seats.saturating_sub(1) {
+		let mut matches = vec![];
+		let mut bye = None;
+		for i in 0..seats / 2 {
+			match (circle[i], circle[seats - 1 - i]) {
+				(Some(left), Some(right)) => matches.push(Match::new((left, right))),
+				(player, None) | (None, player) => bye = player
+			}
+		}
+		rounds.push(Round { label: format!("Round {}", round + 1), matches, bye });
+		// The first seat stays put while the rest turn
+		circle[1..].rotate_right(1);
+	}
+	rounds
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entrants(count: usize) -> Vec<Entrant> {
+		(0..count).map(|i| Entrant::human(&format!("Player {}", i + 1))).collect()
+	}
+
+	// Plays the tournament out with the lower numbered player always winning
+	fn play_out(tournament: &mut Tournament) -> usize {
+		let mut played = 0;
+		while let Some((round, index)) = tournament.next_match() {
+			let (left, right) = tournament.rounds[round].matches[index].players;
+			tournament.record(round, index, if left < right {(5, 2)} else {(2, 5)});
+			played += 1;
+			assert!(played < 100, "tournament never finished");
+		}
+		played
+	}
+
+	#[test]
+	fn single_elimination() {
+		let mut tournament = Tournament::new(Format::SingleElimination, entrants(5));
+		assert_eq!(tournament.rounds[0].matches.len(), 2);
+		assert_eq!(tournament.rounds[0].bye, Some(4));
+		assert_eq!(play_out(&mut tournament), 4);
+		assert_eq!(tournament.champion(), Some(0));
+		assert_eq!(tournament.rounds.last().unwrap().label, "Final");
+	}
+
+	#[test]
+	fn draws_are_replayed() {
+		let mut tournament = Tournament::new(Format::SingleElimination, entrants(2));
+		tournament.record(0, 0, (3, 3));
+		assert_eq!(tournament.next_match(), Some((0, 0)));
+		tournament.record(0, 0, (1, 5));
+		assert_eq!(tournament.champion(), Some(1));
+	}
+
+	#[test]
+	fn double_elimination() {
+		for count in 2..=8 {
+			let mut tournament = Tournament::new(Format::DoubleElimination, entrants(count));
+			play_out(&mut tournament);
+			assert_eq!(tournament.champion(), Some(0));
+			for player in 1..count {
+				assert_eq!(tournament.losses(player), 2);
+			}
+		}
+	}
+
+	#[test]
+	fn losing_the_final_once_isnt_the_end() {
+		let mut tournament = Tournament::new(Format::DoubleElimination, entrants(2));
+		tournament.record(0, 0, (5, 0));
+		assert_eq!(tournament.champion(), None);
+		tournament.record(1, 0, (0, 5));
+		tournament.record(2, 0, (0, 5));
+		assert_eq!(tournament.champion(), Some(1));
+	}
+
+	#[test]
+	fn round_robin_pairs_everyone_once() {
+		for count in 2..=8 {
+			let tournament = Tournament::new(Format::RoundRobin, entrants(count));
+			let mut pairs = tournament.matches().map(|played| {
+				let (left, right) = played.players;
+				(left.min(right), left.max(right))
+			}).collect::<Vec<(usize, usize)>>();
+			pairs.sort();
+			pairs.dedup();
+			assert_eq!(pairs.len(), count * (count - 1) / 2);
+			assert_eq!(tournament.matches().count(), pairs.len());
+		}
+		let mut tournament = Tournament::new(Format::RoundRobin, entrants(4));
+		assert_eq!(tournament.rounds.len(), 3);
+		play_out(&mut tournament);
+		let wins = tournament.standings().iter().map(|standing| standing.wins).collect::<Vec<u16>>();
+		assert_eq!(wins, [3, 2, 1, 0]);
+		assert_eq!(tournament.champion(), Some(0));
+	}
+}
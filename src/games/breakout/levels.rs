@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::common::config::get_config_dir;
+
+// Bricks across the board
+pub static COLUMNS: usize = 12;
+pub static MAX_ROWS: usize = 10;
+
+// Played in this order, before any of the player's own
+static BUILT_IN: [(&str, &str); 4] = [
+	("1-wall.txt", include_str!("levels/1-wall.txt")),
+	("2-pyramid.txt", include_str!("levels/2-pyramid.txt")),
+	("3-fortress.txt", include_str!("levels/3-fortress.txt")),
+	("4-checkers.txt", include_str!("levels/4-checkers.txt"))
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+	pub name: String,
+	// Hits each brick takes by row then column, 0 where there's a gap
+	pub bricks: Vec<Vec<u8>>
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+	Io(PathBuf, std::io::Error),
+	// Line numbers count from 1
+	TooWide(usize),
+	TooManyRows,
+	UnknownBrick(usize, char),
+	NoBricks
+}
+
+impl fmt::Display for LevelError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LevelError::Io(path, err) => write!(f, "couldn't read {}, {}", path.display(), err),
+			LevelError::TooWide(line) => write!(f, "line {} has more than {} bricks", line, COLUMNS),
+			LevelError::TooManyRows => write!(f, "there are more than {} rows of bricks", MAX_ROWS),
+			LevelError::UnknownBrick(line, c) => write!(f, "line {} has a '{}', bricks are digits and gaps are dots or spaces", line, c),
+			LevelError::NoBricks => write!(f, "there aren't any bricks")
+		}
+	}
+}
+
+// "2-pyramid.txt" is called Pyramid, the number only puts the files in order
+fn level_name(file_name: &str) -> String {
+	let stem = file_name.strip_suffix(".txt").unwrap_or(file_name);
+	let name = stem.split_once('-').filter(|(order, _)| order.chars().all(|c| c.is_ascii_digit())).map_or(stem, |(_, name)| name);
+	let mut chars = name.chars();
+	chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+// Lines starting with # are comments, trailing gaps can be left off
+pub fn parse_level(file_name: &str, text: &str) -> Result<Level, LevelError> {
+	let mut bricks = vec![];
+	for (i, line) in text.lines().enumerate() {
+		if line.starts_with('#') {continue};
+		let line = line.trim_end();
+		if line.chars().count() > COLUMNS {return Err(LevelError::TooWide(i + 1))};
+		let mut row = line.chars().map(|c| match c {
+			'.' | ' ' => Ok(0),
+			'1'..='9' => Ok(c as u8 - b'0'),
+			_ => Err(LevelError::UnknownBrick(i + 1, c))
+		}).collect::<Result<Vec<u8>, LevelError>>()?;
+		row.resize(COLUMNS, 0);
+		bricks.push(row);
+	}
+	// Blank lines at the end don't push the bricks down
+	while bricks.last().is_some_and(|row| row.iter().all(|hits| *hits == 0)) {
+		bricks.pop();
+	}
+	if bricks.len() > MAX_ROWS {return Err(LevelError::TooManyRows)};
+	if bricks.is_empty() {return Err(LevelError::NoBricks)};
+	Ok(Level { name: level_name(file_name), bricks })
+}
+
+pub fn get_levels_dir() -> Option<PathBuf> {
+	get_config_dir().map(|dir| dir.join("breakout"))
+}
+
+fn load_level(path: &Path) -> Result<Level, LevelError> {
+	let text = std::fs::read_to_string(path).map_err(|err| LevelError::Io(path.to_owned(), err))?;
+	let file_name = path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+	parse_level(&file_name, &text)
+}
+
+// The built in levels then any .txt files in the levels directory by name, along with the files
+// that couldn't be loaded
+pub fn load_levels() -> (Vec<Level>, Vec<(String, LevelError)>) {
+	let mut levels = BUILT_IN.iter().map(|(file_name, text)| parse_level(file_name, text).expect("built in levels are valid")).collect::<Vec<Level>>();
+	let mut errors = vec![];
+	let Some(Ok(entries)) = get_levels_dir().map(std::fs::read_dir) else {return (levels, errors)};
+	let mut paths = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| path.extension().is_some_and(|ext| ext == "txt")).collect::<Vec<PathBuf>>();
+	paths.sort();
+	for path in paths {
+		match load_level(&path) {
+			Ok(level) => levels.push(level),
+			Err(err) => errors.push((path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned()), err))
+		}
+	}
+	(levels, errors)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_levels() {
+		let level = parse_level("3-the-end.txt", "# A comment\n12\n\n.3 1\n\n").unwrap();
+		assert_eq!(level.name, "The-end");
+		assert_eq!(level.bricks.len(), 3);
+		assert_eq!(level.bricks[0][..3], [1, 2, 0]);
+		assert_eq!(level.bricks[1], vec![0; COLUMNS]);
+		assert_eq!(level.bricks[2][..4], [0, 3, 0, 1]);
+		for (_, text) in BUILT_IN {
+			assert!(parse_level("built-in", text).is_ok());
+		}
+	}
+
+	#[test]
+	fn rejects_bad_levels() {
+		assert!(matches!(parse_level("a", "1111111111111"), Err(LevelError::TooWide(1))));
+		assert!(matches!(parse_level("a", "# bricks\n11x1"), Err(LevelError::UnknownBrick(2, 'x'))));
+		assert!(matches!(parse_level("a", "# nothing\n...\n"), Err(LevelError::NoBricks)));
+		assert!(matches!(parse_level("a", &"1\n".repeat(MAX_ROWS + 1)), Err(LevelError::TooManyRows)));
+		assert_eq!(level_name("castle.txt"), "Castle");
+	}
+}
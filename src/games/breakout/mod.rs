@@ -0,0 +1,569 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use crate::common::{
+	achievements::*,
+	ball::*,
+	game::*,
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::*, toast::*, util::*}
+};
+
+use self::levels::*;
+
+pub mod levels;
+
+static BRICK_WIDTH: u16 = 4;
+// Empty rows between the top wall and the bricks
+static TOP_GAP: u16 = 2;
+static BOARD_HEIGHT: u16 = 20;
+static PADDLE_WIDTH: u16 = 7;
+static WIDE_PADDLE_WIDTH: u16 = 11;
+static PADDLE_STEP: u16 = 2;
+static BALL_SPEED: f32 = 0.5;
+static SLOW_SPEED: f32 = 0.3;
+static MAX_BALLS: usize = 9;
+static PI: f32 = std::f32::consts::PI;
+// Off the ends of the paddle
+static MAX_ANGLE: f32 = PI / 3.0;
+static LIVES: u32 = 3;
+static POWER_UP_CHANCE: f64 = 0.15;
+// How long the wide paddle and slow ball last
+static POWER_UP_TICKS: u32 = 300;
+static DROP_SPEED: f32 = 0.25;
+static FRAME_TIME: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PowerUp {
+	Wide,
+	Multi,
+	Slow
+}
+
+impl PowerUp {
+	fn letter(&self) -> &'static str {
+		match self {
+			PowerUp::Wide => "W",
+			PowerUp::Multi => "M",
+			PowerUp::Slow => "S"
+		}
+	}
+}
+
+// A power-up falling from a broken brick, caught with the paddle
+struct Drop {
+	power_up: PowerUp,
+	pos: (f32, f32)
+}
+
+struct GameState {
+	bounds: (u16, u16),
+	offset: (u16, u16),
+	status_rect: Rect,
+	levels: Vec<Level>,
+	level: usize,
+	// Hits left for each brick by row then column, 0 where there's none
+	bricks: Vec<Vec<u8>>,
+	// Left edge
+	paddle_x: u16,
+	balls: Vec<Ball>,
+	// The ball rides on the paddle until it's launched
+	launched: bool,
+	drops: Vec<Drop>,
+	wide_ticks: u32,
+	slow_ticks: u32,
+	lives: u32,
+	score: u32,
+	finished: bool,
+	won: bool,
+	paused: bool,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("breakout", &[
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Confirm, &["space", "w", "up"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+fn board_rect(state: &GameState) -> Rect {
+	Rect::new(state.offset, state.bounds)
+}
+
+impl GameState {
+	// The board is the area inside the border, power-ups are dropped using the seed
+	fn new(levels: Vec<Level>, board: Rect, status_rect: Rect, seed: u64) -> GameState {
+		let bounds = (COLUMNS as u16 * BRICK_WIDTH, std::cmp::max(board.height, TOP_GAP + MAX_ROWS as u16 + 4));
+		let mut state = GameState {
+			bounds,
+			offset: board.coords(),
+			status_rect,
+			levels,
+			level: 0,
+			bricks: vec![],
+			paddle_x: (bounds.0 - PADDLE_WIDTH) / 2,
+			balls: vec![],
+			launched: false,
+			drops: vec![],
+			wide_ticks: 0,
+			slow_ticks: 0,
+			lives: LIVES,
+			score: 0,
+			finished: false,
+			won: false,
+			paused: false,
+			rng: StdRng::seed_from_u64(seed)
+		};
+		state.start_level();
+		state
+	}
+	fn start_level(&mut self) {
+		self.bricks = self.levels.get(self.level).map_or(vec![], |level| level.bricks.clone());
+		self.drops.clear();
+		self.wide_ticks = 0;
+		self.slow_ticks = 0;
+		self.paddle_x = self.paddle_x.min(self.bounds.0 - self.paddle_width());
+		self.reset_ball();
+	}
+	fn paddle_row(&self) -> u16 {
+		self.bounds.1 - 1
+	}
+	fn paddle_width(&self) -> u16 {
+		if self.wide_ticks > 0 {WIDE_PADDLE_WIDTH} else {PADDLE_WIDTH}
+	}
+	fn reset_ball(&mut self) {
+		self.launched = false;
+		self.balls = vec![Ball::new((0.0, 0.0), Axis::Vertical, false, 0.0, BALL_SPEED)];
+		self.hold_ball();
+	}
+	// Keeps the waiting ball in the middle of the paddle, just above it
+	fn hold_ball(&mut self) {
+		let x = self.paddle_x as f32 + self.paddle_width() as f32 / 2.0;
+		let y = self.paddle_row() as f32 - 0.5;
+		if let Some(ball) = self.balls.first_mut() {
+			ball.pos = (x, y);
+		}
+	}
+	fn move_paddle(&mut self, right: bool) {
+		let furthest = self.bounds.0 - self.paddle_width();
+		self.paddle_x = if right {std::cmp::min(self.paddle_x + PADDLE_STEP, furthest)} else {self.paddle_x.saturating_sub(PADDLE_STEP)};
+		if !self.launched {
+			self.hold_ball();
+		}
+	}
+	fn launch(&mut self) {
+		if self.launched {return};
+		self.launched = true;
+		let angle = self.rng.gen_range(-(PI / 6.0)..=(PI / 6.0));
+		if let Some(ball) = self.balls.first_mut() {
+			ball.angle = angle;
+		}
+	}
+	fn bricks_left(&self) -> usize {
+		self.bricks.iter().flatten().filter(|hits| **hits > 0).count()
+	}
+	// The brick at a spot on the board, as its row and column
+	fn brick_at(&self, (x, y): (f32, f32)) -> Option<(usize, usize)> {
+		if x < 0.0 || y < TOP_GAP as f32 {return None};
+		let (row, column) = ((y as u16 - TOP_GAP) as usize, (x as u16 / BRICK_WIDTH) as usize);
+		let hits = *self.bricks.get(row)?.get(column)?;
+		if hits > 0 {Some((row, column))} else {None}
+	}
+	fn hit_brick(&mut self, (row, column): (usize, usize)) {
+		self.bricks[row][column] -= 1;
+		self.score += 10;
+		if self.bricks[row][column] > 0 || !self.rng.gen_bool(POWER_UP_CHANCE) {return};
+		let power_up = *[PowerUp::Wide, PowerUp::Multi, PowerUp::Slow].choose(&mut self.rng).unwrap();
+		let pos = ((column as u16 * BRICK_WIDTH + BRICK_WIDTH / 2) as f32, (row as u16 + TOP_GAP) as f32);
+		self.drops.push(Drop { power_up, pos });
+	}
+	fn catch(&mut self, power_up: PowerUp) {
+		self.score += 50;
+		match power_up {
+			PowerUp::Wide => {
+				self.wide_ticks = POWER_UP_TICKS;
+				self.paddle_x = self.paddle_x.min(self.bounds.0 - WIDE_PADDLE_WIDTH);
+			},
+			PowerUp::Slow => self.slow_ticks = POWER_UP_TICKS,
+			// Each ball splits in three, heading off either side of where it was going
+			PowerUp::Multi => {
+				let mut split = vec![];
+				for ball in &self.balls {
+					split.push(*ball);
+					for turn in [-0.5, 0.5] {
+						split.push(Ball { angle: (ball.angle + turn).clamp(-MAX_ANGLE, MAX_ANGLE), ..*ball });
+					}
+				}
+				split.truncate(MAX_BALLS);
+				self.balls = split;
+			}
+		}
+	}
+	// Bounces off the top wall, bricks and the paddle. False once it's gone past the paddle
+	fn move_ball(&mut self, index: usize) -> bool {
+		let mut ball = self.balls[index];
+		let before = ball.pos;
+		ball.speed = if self.slow_ticks > 0 {SLOW_SPEED} else {BALL_SPEED};
+		ball.step(self.bounds.0 as f32);
+		if ball.pos.1 < 0.0 {
+			ball.pos.1 = -ball.pos.1;
+			ball.forward = true;
+		}
+		let paddle_row = self.paddle_row() as f32;
+		if let Some(brick) = self.brick_at(ball.pos) {
+			self.hit_brick(brick);
+			// Coming in from above or below turns it around, from the side sends it back across
+			if ball.pos.1.floor() != before.1.floor() {
+				ball.forward = !ball.forward;
+			} else {
+				ball.angle *= -1.0;
+			}
+			ball.pos = before;
+		} else if ball.forward && ball.pos.1 >= paddle_row && before.1 < paddle_row {
+			let width = self.paddle_width() as f32;
+			if ball.is_over(self.paddle_x as f32, width) {
+				ball.bounce_off(self.paddle_x as f32, width, MAX_ANGLE);
+				ball.pos = before;
+			}
+		}
+		self.balls[index] = ball;
+		ball.pos.1 < self.bounds.1 as f32
+	}
+	fn move_drops(&mut self) {
+		let paddle = self.paddle_x as f32..(self.paddle_x + self.paddle_width()) as f32;
+		let paddle_row = self.paddle_row() as f32;
+		let mut caught = vec![];
+		self.drops.retain_mut(|drop| {
+			drop.pos.1 += DROP_SPEED;
+			if drop.pos.1.floor() == paddle_row && paddle.contains(&drop.pos.0) {
+				caught.push(drop.power_up);
+				return false;
+			}
+			drop.pos.1 < paddle_row + 1.0
+		});
+		for power_up in caught {
+			self.catch(power_up);
+		}
+	}
+	fn lose_ball(&mut self) {
+		self.lives -= 1;
+		if self.lives == 0 {
+			self.finished = true;
+		} else {
+			self.drops.clear();
+			self.reset_ball();
+		}
+	}
+	fn clear_level(&mut self) {
+		self.level += 1;
+		report_event("breakout", "level", self.level as u32);
+		if self.level == self.levels.len() {
+			self.won = true;
+			self.finished = true;
+		} else {
+			self.start_level();
+		}
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		match action {
+			Action::Pause => self.paused = !self.paused,
+			_ if self.paused || self.finished => {},
+			Action::MoveLeft => self.move_paddle(false),
+			Action::MoveRight => self.move_paddle(true),
+			Action::Confirm => self.launch(),
+			_ => {}
+		}
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished || !self.launched {return};
+		self.wide_ticks = self.wide_ticks.saturating_sub(1);
+		self.slow_ticks = self.slow_ticks.saturating_sub(1);
+		// The paddle shrinking back can leave it hanging off the edge
+		self.paddle_x = self.paddle_x.min(self.bounds.0 - self.paddle_width());
+		let mut index = 0;
+		while index < self.balls.len() {
+			if self.move_ball(index) {
+				index += 1;
+			} else {
+				self.balls.remove(index);
+			}
+		}
+		self.move_drops();
+		if self.bricks_left() == 0 {
+			self.clear_level();
+		} else if self.balls.is_empty() {
+			self.lose_ball();
+		}
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn brick_color(row: usize, hits: u8) -> ThemeColor {
+	let colors = current_theme().entities;
+	match hits {
+		1 => colors.breakout_rows[row % colors.breakout_rows.len()],
+		2 => colors.breakout_strong[0],
+		_ => colors.breakout_strong[1]
+	}
+}
+
+fn draw_bricks(state: &GameState) {
+	let theme = current_theme();
+	// A gap on the right of each brick keeps them apart
+	let brick = repeat_str(theme.glyphs.block, BRICK_WIDTH - 1);
+	for (row, bricks) in state.bricks.iter().enumerate() {
+		for (column, hits) in bricks.iter().enumerate() {
+			if *hits == 0 {continue};
+			let coords = (
+				state.offset.0 + column as u16 * BRICK_WIDTH,
+				state.offset.1 + TOP_GAP + row as u16
+			);
+			print_clipped(&brick, coords, Some(board_rect(state)), Some(&theme.fill), Some(&brick_color(row, *hits)));
+		}
+	}
+}
+
+fn draw_paddle_and_balls(state: &GameState) {
+	let theme = current_theme();
+	let paddle = repeat_str(theme.glyphs.block, state.paddle_width());
+	let paddle_coords = (state.offset.0 + state.paddle_x, state.offset.1 + state.paddle_row());
+	print_clipped(&paddle, paddle_coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.pong_paddle));
+	for ball in &state.balls {
+		let coords = (state.offset.0 + ball.pos.0.floor() as u16, state.offset.1 + ball.pos.1.floor() as u16);
+		print_clipped(theme.glyphs.ball, coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.pong_ball));
+	}
+	for drop in &state.drops {
+		let coords = (state.offset.0 + drop.pos.0.floor() as u16, state.offset.1 + drop.pos.1.floor() as u16);
+		print_clipped(drop.power_up.letter(), coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.accent));
+	}
+}
+
+fn write_game_text(state: &GameState) {
+	let level_name = state.levels.get(state.level).map_or("", |level| &level.name);
+	let progress = format!("Level {}: {}, lives {}, score {}", state.level + 1, level_name, state.lives, state.score);
+	let text = if state.won {
+		format!("Every level cleared, score: {}, press any key to continue", state.score)
+	} else if state.finished {
+		format!("Game over, score: {}, press any key to continue", state.score)
+	} else if state.paused {
+		format!("Paused, {}", progress)
+	} else if !state.launched {
+		format!("{}, space to launch", progress)
+	} else {
+		progress
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(board_rect(state).outer(Padding::uniform(1))).print();
+	draw_bricks(state);
+	draw_paddle_and_balls(state);
+	write_game_text(state);
+	if state.finished {
+		print_splash(if state.won {"You win"} else {"Game over"}, &format!("Score: {}", state.score));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+fn get_initial_state(setup: &GameSetup, levels: Vec<Level>) -> GameState {
+	let bounds = (COLUMNS as u16 * BRICK_WIDTH, BOARD_HEIGHT);
+	// Board with its border, plus a status line underneath
+	let frame = Rect::screen().centered((bounds.0 + 2, bounds.1 + 3));
+	let [board_rect, status_rect] = Layout::vertical(&[Constraint::Min(3), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	GameState::new(levels, board_rect.inner(Padding::uniform(1)), status_rect, setup.seed)
+}
+
+pub struct Breakout {
+	setup: GameSetup
+}
+
+impl Game for Breakout {
+	fn run(&self) -> GameReturn {
+		let (levels, errors) = load_levels();
+		for (file_name, err) in errors {
+			show_toast("Skipped a level", &format!("{}: {}", file_name, err));
+		}
+		let bindings = default_bindings().load_or_default();
+		let state = run_simulation(get_initial_state(&self.setup, levels), FRAME_TIME, apply_bindings(bindings));
+		GameReturn {
+			score: Score::SinglePlayer(state.score as f32),
+			win_state: if state.won {WinState::Win} else {WinState::Lose},
+			records: vec![("Most levels cleared", state.level as u32)],
+			times: vec![]
+		}
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "breakout",
+		name: "Breakout",
+		description: "Knock out every brick with the ball, catching power-ups on the way",
+		min_players: 1,
+		max_players: 1,
+		options: &[],
+		version: "1.0.0",
+		create: |setup| Box::new(Breakout { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "level_1",
+				name: "Bricklayer's nightmare",
+				description: "Clear a level",
+				event: "level",
+				goal: 1
+			},
+			Achievement {
+				id: "level_4",
+				name: "Demolition",
+				description: "Clear all four built in levels",
+				event: "level",
+				goal: 4
+			}
+		],
+		daily: Some(|_, _| {})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::*, snapshot::assert_snapshot};
+
+	fn level(text: &str) -> Level {
+		parse_level("test.txt", text).unwrap()
+	}
+
+	fn new_state(levels: &[&str]) -> GameState {
+		let (board, status_rect) = board_rects((COLUMNS as u16 * BRICK_WIDTH, BOARD_HEIGHT));
+		GameState::new(levels.iter().map(|text| level(text)).collect(), board, status_rect, 1)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		let size = state.bounds;
+		Harness::for_board(state, size)
+	}
+
+	// Straight up from the paddle's middle
+	fn launched_straight(state: &mut GameState) {
+		state.launched = true;
+		state.balls[0].angle = 0.0;
+	}
+
+	#[test]
+	fn waits_on_the_paddle_until_launched() {
+		let mut game = harness(new_state(&["1"]));
+		game.run(10).press(Action::MoveRight);
+		assert_eq!(game.state.balls[0].pos, (25.5, 18.5));
+		game.press(Action::Confirm).run(2);
+		assert!(game.state.balls[0].pos.1 < 18.5);
+	}
+
+	#[test]
+	fn bricks_take_their_hits() {
+		let mut state = new_state(&["...........1\n.....2"]);
+		state.balls[0].pos = (23.5, 5.0);
+		launched_straight(&mut state);
+		let mut game = harness(state);
+		game.run(5);
+		assert_eq!(game.state.bricks[1][5], 1);
+		assert!(game.state.balls[0].forward);
+		assert_eq!(game.state.score, 10);
+		// Back down to the paddle and up again
+		game.run(60);
+		assert_eq!(game.state.bricks[1][5], 0);
+		assert_eq!(game.state.lives, LIVES);
+	}
+
+	#[test]
+	fn angle_comes_from_where_the_paddle_is_hit() {
+		let mut state = new_state(&["1"]);
+		launched_straight(&mut state);
+		state.balls[0].pos = (state.paddle_x as f32 + 0.5, 17.0);
+		state.balls[0].forward = true;
+		let mut game = harness(state);
+		game.run(4);
+		assert!(!game.state.balls[0].forward);
+		assert!(game.state.balls[0].angle < -0.5);
+	}
+
+	#[test]
+	fn missing_costs_a_life() {
+		let mut state = new_state(&["1"]);
+		launched_straight(&mut state);
+		state.balls[0].pos = (2.0, 17.0);
+		state.balls[0].forward = true;
+		let mut game = harness(state);
+		game.run(10);
+		assert_eq!(game.state.lives, LIVES - 1);
+		assert!(!game.state.launched);
+		game.state.lives = 1;
+		launched_straight(&mut game.state);
+		game.state.balls[0].pos = (2.0, 17.0);
+		game.state.balls[0].forward = true;
+		assert!(game.run_until_finished(10));
+		assert!(!game.state.won);
+	}
+
+	#[test]
+	fn power_ups_are_caught_with_the_paddle() {
+		let mut state = new_state(&["1"]);
+		launched_straight(&mut state);
+		let x = state.paddle_x as f32 + 1.0;
+		state.drops.push(Drop { power_up: PowerUp::Multi, pos: (x, 17.5) });
+		state.drops.push(Drop { power_up: PowerUp::Wide, pos: (x, 16.5) });
+		state.drops.push(Drop { power_up: PowerUp::Slow, pos: (0.0, 17.5) });
+		let mut game = harness(state);
+		game.run(10);
+		assert_eq!(game.state.balls.len(), 3);
+		assert_eq!(game.state.paddle_width(), WIDE_PADDLE_WIDTH);
+		assert_eq!(game.state.slow_ticks, 0);
+		assert!(game.state.drops.is_empty());
+		assert_eq!(game.state.score, 100);
+	}
+
+	#[test]
+	fn clearing_the_last_level_wins() {
+		let mut state = new_state(&["1", ".....1"]);
+		state.bricks[0][0] = 0;
+		launched_straight(&mut state);
+		let mut game = harness(state);
+		game.step();
+		assert_eq!(game.state.level, 1);
+		assert!(!game.state.launched);
+		game.state.bricks[0][5] = 0;
+		launched_straight(&mut game.state);
+		assert!(game.run_until_finished(1));
+		assert!(game.state.won);
+	}
+
+	#[test]
+	fn snapshot_level() {
+		let mut game = harness(new_state(&[include_str!("levels/3-fortress.txt")]));
+		game.state.drops.push(Drop { power_up: PowerUp::Wide, pos: (10.0, 12.0) });
+		game.press(Action::MoveLeft).press(Action::MoveLeft);
+		assert_snapshot("breakout_fortress", &game.frame());
+	}
+}
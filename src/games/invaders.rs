@@ -0,0 +1,730 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use crate::common::{
+	achievements::*,
+	game::*,
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, sprite::Sprite, text::TextPrint, theme::*, toast::*, util::*}
+};
+
+static BOARD_SIZE: (u16, u16) = (64, 20);
+static ALIEN_COLUMNS: usize = 11;
+static ALIEN_ROWS: usize = 5;
+// From one alien to the next, sprites are 3 wide with a gap of 2
+static ALIEN_SPACING: (u16, u16) = (5, 2);
+// The UFO flies along the top row, the formation starts below it
+static FORMATION_TOP: u16 = 2;
+// Later waves start lower, down to this many rows further
+static MAX_WAVE_DROP: u16 = 3;
+// Ticks between the formation's steps when it's full, it steps every tick with one alien left
+static FULL_MARCH_TICKS: u32 = 16;
+static PLAYER_STEP: u16 = 2;
+static LIVES: u32 = 3;
+// Frozen for this long after the cannon is hit
+static RESPAWN_TICKS: u32 = 40;
+static SHOT_SPEED: f32 = 1.0;
+static BULLET_SPEED: f32 = 0.5;
+static MAX_BULLETS: usize = 3;
+static UFO_SPEED: f32 = 0.5;
+static UFO_POINTS: [u32; 4] = [50, 100, 150, 300];
+static FLASH_TICKS: u32 = 8;
+static BUNKERS: u16 = 4;
+// Half worn after one hit, gone after two
+static BUNKER_HEALTH: u8 = 2;
+static BUNKER_SHAPE: [&str; 3] = [" ##### ", "#######", "##   ##"];
+static FRAME_TIME: Duration = Duration::from_millis(40);
+
+// By row of the formation, top first
+static ALIEN_SPRITES: [Sprite; 3] = [
+	Sprite::new(&[&["{@}"], &["/@\\"]]),
+	Sprite::new(&[&["/W\\"], &["|W|"]]),
+	Sprite::new(&[&["<O>"], &["(O)"]])
+];
+static ALIEN_POINTS: [u32; 3] = [30, 20, 10];
+static PLAYER: Sprite = Sprite::new(&[&["  ^  ", "/===\\"]]);
+static PLAYER_HIT: Sprite = Sprite::new(&[&[" \\|/ ", "-*#*-"], &[" /|\\ ", "*-#-*"]]);
+static UFO: Sprite = Sprite::new(&[&["<=o=>"], &["<=O=>"]]);
+static ALIEN_HIT: &str = "\\*/";
+
+// Moving across the top row, shot down for a mystery score
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Ufo {
+	x: f32,
+	right: bool
+}
+
+// Something drawn briefly where it was hit
+struct Flash {
+	text: String,
+	coords: (u16, u16),
+	ticks: u32
+}
+
+struct GameState {
+	bounds: (u16, u16),
+	offset: (u16, u16),
+	status_rect: Rect,
+	// Which aliens are left by row then column
+	aliens: Vec<Vec<bool>>,
+	// Where the top left alien would be, it can be off the board once its column is gone
+	formation: (i32, i32),
+	marching_right: bool,
+	march_ticks: u32,
+	// Counts the steps to animate the aliens
+	steps: usize,
+	// Left edge
+	player_x: u16,
+	// The cannon only has one shot in the air at a time
+	shot: Option<(u16, f32)>,
+	bullets: Vec<(u16, f32)>,
+	fire_ticks: u32,
+	ufo: Option<Ufo>,
+	ufo_ticks: u32,
+	// Hits left for each cell by row then column from the top of the bunkers
+	bunkers: Vec<Vec<u8>>,
+	flashes: Vec<Flash>,
+	respawn_ticks: u32,
+	wave: u32,
+	lives: u32,
+	score: u32,
+	finished: bool,
+	paused: bool,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("invaders", &[
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Confirm, &["space", "w", "up"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+fn board_rect(state: &GameState) -> Rect {
+	Rect::new(state.offset, state.bounds)
+}
+
+fn alien_kind(row: usize) -> usize {
+	match row {
+		0 => 0,
+		1 | 2 => 1,
+		_ => 2
+	}
+}
+
+impl GameState {
+	// The board is the area inside the border, the aliens fire and the UFO flies using the seed
+	fn new(board: Rect, status_rect: Rect, seed: u64) -> GameState {
+		let bounds = BOARD_SIZE;
+		let mut state = GameState {
+			bounds,
+			offset: board.coords(),
+			status_rect,
+			aliens: vec![],
+			formation: (0, 0),
+			marching_right: true,
+			march_ticks: 0,
+			steps: 0,
+			player_x: (bounds.0 - PLAYER.width()) / 2,
+			shot: None,
+			bullets: vec![],
+			fire_ticks: 0,
+			ufo: None,
+			ufo_ticks: 0,
+			bunkers: vec![],
+			flashes: vec![],
+			respawn_ticks: 0,
+			wave: 1,
+			lives: LIVES,
+			score: 0,
+			finished: false,
+			paused: false,
+			rng: StdRng::seed_from_u64(seed)
+		};
+		state.start_wave();
+		state
+	}
+	fn start_wave(&mut self) {
+		self.aliens = vec![vec![true; ALIEN_COLUMNS]; ALIEN_ROWS];
+		let width = (ALIEN_COLUMNS as u16 - 1) * ALIEN_SPACING.0 + ALIEN_SPRITES[0].width();
+		let drop = std::cmp::min(self.wave - 1, MAX_WAVE_DROP as u32) as u16;
+		self.formation = (((self.bounds.0 - width) / 2) as i32, (FORMATION_TOP + drop) as i32);
+		self.marching_right = true;
+		self.march_ticks = self.march_interval();
+		self.shot = None;
+		self.bullets.clear();
+		self.ufo = None;
+		self.ufo_ticks = self.rng.gen_range(400..800);
+		self.fire_ticks = self.fire_interval();
+		self.build_bunkers();
+	}
+	fn build_bunkers(&mut self) {
+		let mut bunkers = vec![vec![0; self.bounds.0 as usize]; BUNKER_SHAPE.len()];
+		let width = BUNKER_SHAPE[0].len() as u16;
+		for i in 0..BUNKERS {
+			let left = self.bounds.0 * (2 * i + 1) / (2 * BUNKERS) - width / 2;
+			for (row, line) in BUNKER_SHAPE.iter().enumerate() {
+				for (column, c) in line.chars().enumerate() {
+					if c == '#' {
+						bunkers[row][left as usize + column] = BUNKER_HEALTH;
+					}
+				}
+			}
+		}
+		self.bunkers = bunkers;
+	}
+	fn bunker_top(&self) -> u16 {
+		self.player_top() - 1 - BUNKER_SHAPE.len() as u16
+	}
+	fn player_top(&self) -> u16 {
+		self.bounds.1 - PLAYER.height()
+	}
+	fn aliens_left(&self) -> usize {
+		self.aliens.iter().flatten().filter(|alive| **alive).count()
+	}
+	// Fewer aliens march faster, and so does every wave after the first
+	fn march_interval(&self) -> u32 {
+		let full = (ALIEN_ROWS * ALIEN_COLUMNS) as u32;
+		let interval = 1 + self.aliens_left() as u32 * FULL_MARCH_TICKS / full;
+		std::cmp::max(1, interval.saturating_sub(std::cmp::min(self.wave - 1, 4)))
+	}
+	fn fire_interval(&mut self) -> u32 {
+		let quickest = 20u32.saturating_sub(self.wave * 2).max(6);
+		self.rng.gen_range(quickest..quickest * 3)
+	}
+	fn alien_coords(&self, (row, column): (usize, usize)) -> (i32, i32) {
+		(
+			self.formation.0 + (column as u16 * ALIEN_SPACING.0) as i32,
+			self.formation.1 + (row as u16 * ALIEN_SPACING.1) as i32
+		)
+	}
+	fn alive_aliens(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+		self.aliens.iter().enumerate().flat_map(|(row, aliens)| {
+			aliens.iter().enumerate().filter(|(_, alive)| **alive).map(move |(column, _)| (row, column))
+		})
+	}
+	fn alien_at(&self, (x, y): (u16, u16)) -> Option<(usize, usize)> {
+		self.alive_aliens().find(|alien| {
+			let (left, top) = self.alien_coords(*alien);
+			let sprite = ALIEN_SPRITES[alien_kind(alien.0)];
+			let (dx, dy) = (x as i32 - left, y as i32 - top);
+			dx >= 0 && dy >= 0 && sprite.is_solid(self.steps, (dx as u16, dy as u16))
+		})
+	}
+	// Steps across, or down and back at the edges. Aliens crush the bunkers they walk into
+	fn march(&mut self) {
+		let width = ALIEN_SPRITES[0].width() as i32;
+		let xs = self.alive_aliens().map(|alien| self.alien_coords(alien).0).collect::<Vec<i32>>();
+		let (Some(left), Some(right)) = (xs.iter().min(), xs.iter().max().map(|x| x + width)) else {return};
+		let at_edge = if self.marching_right {right >= self.bounds.0 as i32} else {*left <= 0};
+		if at_edge {
+			self.formation.1 += 1;
+			self.marching_right = !self.marching_right;
+		} else {
+			self.formation.0 += if self.marching_right {1} else {-1};
+		}
+		self.steps += 1;
+		let bunker_top = self.bunker_top() as i32;
+		let aliens = self.alive_aliens().map(|alien| self.alien_coords(alien)).collect::<Vec<(i32, i32)>>();
+		for (x, y) in aliens {
+			if let Some(row) = usize::try_from(y - bunker_top).ok().and_then(|row| self.bunkers.get_mut(row)) {
+				for cell in row.iter_mut().skip(x.max(0) as usize).take(width as usize) {
+					*cell = 0;
+				}
+			}
+			if y + ALIEN_SPRITES[0].height() as i32 > self.player_top() as i32 {
+				self.finished = true;
+			}
+		}
+	}
+	// A random column's lowest alien fires, sometimes picking the one over the cannon
+	fn alien_fire(&mut self) {
+		let mut lowest = vec![None; ALIEN_COLUMNS];
+		for (row, column) in self.alive_aliens() {
+			lowest[column] = Some(row);
+		}
+		let shooters = lowest.iter().enumerate().filter_map(|(column, row)| row.map(|row| (row, column))).collect::<Vec<(usize, usize)>>();
+		let player_middle = (self.player_x + PLAYER.width() / 2) as i32;
+		let shooter = if self.rng.gen_bool(0.3) {
+			shooters.iter().min_by_key(|alien| (self.alien_coords(**alien).0 + 1 - player_middle).abs()).copied()
+		} else {
+			shooters.choose(&mut self.rng).copied()
+		};
+		if let Some(alien) = shooter {
+			let (x, y) = self.alien_coords(alien);
+			if x >= 0 && x + 1 < self.bounds.0 as i32 {
+				self.bullets.push(((x + 1) as u16, (y + 1) as f32));
+			}
+		}
+	}
+	fn move_player(&mut self, right: bool) {
+		let furthest = self.bounds.0 - PLAYER.width();
+		self.player_x = if right {std::cmp::min(self.player_x + PLAYER_STEP, furthest)} else {self.player_x.saturating_sub(PLAYER_STEP)};
+	}
+	fn fire(&mut self) {
+		if self.shot.is_some() {return};
+		self.shot = Some((self.player_x + PLAYER.width() / 2, self.player_top() as f32 - 1.0));
+	}
+	// Takes a hit off the bunker cell there, if there's anything left of it
+	fn hit_bunker(&mut self, (x, y): (u16, u16)) -> bool {
+		let Some(row) = y.checked_sub(self.bunker_top()) else {return false};
+		let Some(cell) = self.bunkers.get_mut(row as usize).and_then(|cells| cells.get_mut(x as usize)) else {return false};
+		if *cell == 0 {return false};
+		*cell -= 1;
+		true
+	}
+	fn flash(&mut self, text: &str, coords: (u16, u16)) {
+		self.flashes.push(Flash { text: text.to_owned(), coords, ticks: FLASH_TICKS });
+	}
+	// What the shot hit, if anything, in which case it's used up
+	fn shot_hits(&mut self, (x, y): (u16, u16)) -> bool {
+		let under_ufo = |ufo: &Ufo| y == 0 && x.checked_sub(ufo.x as u16).is_some_and(|dx| UFO.is_solid(0, (dx, 0)));
+		if let Some(ufo) = self.ufo.filter(under_ufo) {
+			let points = *UFO_POINTS.choose(&mut self.rng).unwrap();
+			self.score += points;
+			self.ufo = None;
+			self.flash(&points.to_string(), (ufo.x as u16, 0));
+			report_event("invaders", "ufo", 1);
+			return true;
+		}
+		if let Some((row, column)) = self.alien_at((x, y)) {
+			self.aliens[row][column] = false;
+			self.score += ALIEN_POINTS[alien_kind(row)];
+			let (left, top) = self.alien_coords((row, column));
+			self.flash(ALIEN_HIT, (left as u16, top as u16));
+			return true;
+		}
+		self.hit_bunker((x, y))
+	}
+	fn move_shot(&mut self) {
+		let Some((x, y)) = self.shot else {return};
+		let y = y - SHOT_SPEED;
+		if y < 0.0 {
+			self.shot = None;
+			return;
+		}
+		// Meeting a bullet head on takes out both
+		let before = self.bullets.len();
+		self.bullets.retain(|(bullet_x, bullet_y)| *bullet_x != x || (bullet_y - y).abs() >= 1.0);
+		if self.bullets.len() < before || self.shot_hits((x, y as u16)) {
+			self.shot = None;
+		} else {
+			self.shot = Some((x, y));
+		}
+	}
+	fn move_bullets(&mut self) {
+		let mut bullets = std::mem::take(&mut self.bullets);
+		let mut player_hit = false;
+		bullets.retain_mut(|(x, y)| {
+			*y += BULLET_SPEED;
+			let row = *y as u16;
+			if row >= self.bounds.1 || self.hit_bunker((*x, row)) {return false};
+			let top = self.player_top();
+			if row >= top && x.checked_sub(self.player_x).is_some_and(|dx| PLAYER.is_solid(0, (dx, row - top))) {
+				player_hit = true;
+				return false;
+			}
+			true
+		});
+		self.bullets = bullets;
+		if player_hit {
+			self.player_hit();
+		}
+	}
+	fn player_hit(&mut self) {
+		self.lives -= 1;
+		self.shot = None;
+		self.bullets.clear();
+		self.respawn_ticks = RESPAWN_TICKS;
+		if self.lives == 0 {
+			self.finished = true;
+		}
+	}
+	fn move_ufo(&mut self) {
+		match &mut self.ufo {
+			Some(ufo) => {
+				ufo.x += if ufo.right {UFO_SPEED} else {-UFO_SPEED};
+				if ufo.x < 0.0 || ufo.x > (self.bounds.0 - UFO.width()) as f32 {
+					self.ufo = None;
+				}
+			},
+			None => {
+				self.ufo_ticks = self.ufo_ticks.saturating_sub(1);
+				if self.ufo_ticks > 0 {return};
+				let right = self.rng.gen_bool(0.5);
+				let x = if right {0.0} else {(self.bounds.0 - UFO.width()) as f32};
+				self.ufo = Some(Ufo { x, right });
+				self.ufo_ticks = self.rng.gen_range(400..800);
+			}
+		}
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		match action {
+			Action::Pause => self.paused = !self.paused,
+			_ if self.paused || self.finished || self.respawn_ticks > 0 => {},
+			Action::MoveLeft => self.move_player(false),
+			Action::MoveRight => self.move_player(true),
+			Action::Confirm => self.fire(),
+			_ => {}
+		}
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished {return};
+		if self.respawn_ticks > 0 {
+			self.respawn_ticks -= 1;
+			return;
+		}
+		self.flashes.retain_mut(|flash| {
+			flash.ticks -= 1;
+			flash.ticks > 0
+		});
+		self.move_shot();
+		self.move_bullets();
+		if self.respawn_ticks > 0 {return};
+		self.move_ufo();
+		self.march_ticks = self.march_ticks.saturating_sub(1);
+		if self.march_ticks == 0 {
+			self.march();
+			self.march_ticks = self.march_interval();
+		}
+		self.fire_ticks = self.fire_ticks.saturating_sub(1);
+		if self.fire_ticks == 0 {
+			if self.bullets.len() < MAX_BULLETS {
+				self.alien_fire();
+			}
+			self.fire_ticks = self.fire_interval();
+		}
+		if self.aliens_left() == 0 {
+			report_event("invaders", "wave", self.wave);
+			self.wave += 1;
+			self.start_wave();
+		}
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn draw_bunkers(state: &GameState) {
+	let theme = current_theme();
+	let top = state.bunker_top();
+	for (row, cells) in state.bunkers.iter().enumerate() {
+		for (column, health) in cells.iter().enumerate() {
+			let glyph = match *health {
+				0 => continue,
+				1 => theme.glyphs.shade,
+				_ => theme.glyphs.block
+			};
+			let coords = (state.offset.0 + column as u16, state.offset.1 + top + row as u16);
+			print_clipped(glyph, coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.invaders_bunker));
+		}
+	}
+}
+
+fn draw_sprites(state: &GameState) {
+	let theme = current_theme();
+	let board = board_rect(state);
+	// Marching turns around at the edges, so the aliens left are always on the board
+	for alien in state.alive_aliens() {
+		let (x, y) = state.alien_coords(alien);
+		let kind = alien_kind(alien.0);
+		let coords = (state.offset.0 + x as u16, state.offset.1 + y as u16);
+		ALIEN_SPRITES[kind].draw(state.steps, coords, Some(board), Some(&theme.entities.invaders_aliens[kind]));
+	}
+	if let Some(ufo) = state.ufo {
+		UFO.draw(ufo.x as usize, (state.offset.0 + ufo.x as u16, state.offset.1), Some(board), Some(&theme.entities.invaders_ufo));
+	}
+	let player_coords = (state.offset.0 + state.player_x, state.offset.1 + state.player_top());
+	if state.respawn_ticks > 0 || state.lives == 0 {
+		PLAYER_HIT.draw(state.respawn_ticks as usize / 4, player_coords, Some(board), Some(&theme.entities.invaders_ufo));
+	} else {
+		PLAYER.draw(0, player_coords, Some(board), Some(&theme.accent));
+	}
+	if let Some((x, y)) = state.shot {
+		print_clipped("|", (state.offset.0 + x, state.offset.1 + y as u16), Some(board), None, Some(&theme.accent));
+	}
+	for (x, y) in &state.bullets {
+		let glyph = if y.fract() < 0.5 {"/"} else {"\\"};
+		print_clipped(glyph, (state.offset.0 + x, state.offset.1 + *y as u16), Some(board), None, Some(&theme.entities.invaders_ufo));
+	}
+	for flash in &state.flashes {
+		print_clipped(&flash.text, (state.offset.0 + flash.coords.0, state.offset.1 + flash.coords.1), Some(board), None, Some(&theme.accent));
+	}
+}
+
+fn write_game_text(state: &GameState) {
+	let progress = format!("Wave {}, lives {}, score {}", state.wave, state.lives, state.score);
+	let text = if state.finished {
+		format!("Game over, score: {}, press any key to continue", state.score)
+	} else if state.paused {
+		format!("Paused, {}", progress)
+	} else {
+		progress
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(board_rect(state).outer(Padding::uniform(1))).print();
+	draw_bunkers(state);
+	draw_sprites(state);
+	write_game_text(state);
+	if state.finished {
+		print_splash("Game over", &format!("Score: {}", state.score));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	// Board with its border, plus a status line underneath
+	let frame = Rect::screen().centered((BOARD_SIZE.0 + 2, BOARD_SIZE.1 + 3));
+	let [board_rect, status_rect] = Layout::vertical(&[Constraint::Min(3), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	GameState::new(board_rect.inner(Padding::uniform(1)), status_rect, setup.seed)
+}
+
+pub struct Invaders {
+	setup: GameSetup
+}
+
+impl Game for Invaders {
+	fn run(&self) -> GameReturn {
+		let bindings = default_bindings().load_or_default();
+		let state = run_simulation(get_initial_state(&self.setup), FRAME_TIME, apply_bindings(bindings));
+		GameReturn {
+			score: Score::SinglePlayer(state.score as f32),
+			win_state: WinState::Lose,
+			records: vec![("Most waves cleared", state.wave - 1)],
+			times: vec![]
+		}
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "invaders",
+		name: "Space Invaders",
+		description: "Hold off the marching alien waves from behind your bunkers",
+		min_players: 1,
+		max_players: 1,
+		options: &[],
+		version: "1.0.0",
+		create: |setup| Box::new(Invaders { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "wave_1",
+				name: "First contact",
+				description: "Clear a wave of aliens",
+				event: "wave",
+				goal: 1
+			},
+			Achievement {
+				id: "wave_5",
+				name: "Last line of defence",
+				description: "Clear five waves in one game",
+				event: "wave",
+				goal: 5
+			},
+			Achievement {
+				id: "ufo",
+				name: "Mystery solved",
+				description: "Shoot down the UFO",
+				event: "ufo",
+				goal: 1
+			}
+		],
+		daily: Some(|_, _| {})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::*, snapshot::assert_snapshot};
+
+	// The aliens hold their fire and the UFO stays away unless a test wants them
+	fn new_state() -> GameState {
+		let (board, status_rect) = board_rects(BOARD_SIZE);
+		let mut state = GameState::new(board, status_rect, 1);
+		state.fire_ticks = u32::MAX;
+		state.ufo_ticks = u32::MAX;
+		state
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		Harness::for_board(state, BOARD_SIZE)
+	}
+
+	fn clear_aliens(state: &mut GameState) {
+		state.aliens = vec![vec![false; ALIEN_COLUMNS]; ALIEN_ROWS];
+	}
+
+	#[test]
+	fn formation_speeds_up_as_it_shrinks() {
+		let mut state = new_state();
+		assert_eq!(state.march_interval(), FULL_MARCH_TICKS + 1);
+		clear_aliens(&mut state);
+		state.aliens[0][0] = true;
+		assert_eq!(state.march_interval(), 1);
+		state.wave = 3;
+		state.aliens = vec![vec![true; ALIEN_COLUMNS]; ALIEN_ROWS];
+		assert_eq!(state.march_interval(), FULL_MARCH_TICKS - 1);
+	}
+
+	#[test]
+	fn formation_drops_and_turns_at_the_edge() {
+		let mut state = new_state();
+		clear_aliens(&mut state);
+		state.aliens[0][5] = true;
+		state.march_ticks = 1;
+		let mut game = harness(state);
+		// The last alien starts 30 in from the left and steps right every tick
+		game.run(31);
+		let (x, _) = game.state.alien_coords((0, 5));
+		assert_eq!(x, (BOARD_SIZE.0 - 3) as i32);
+		game.step();
+		assert_eq!(game.state.formation.1, FORMATION_TOP as i32 + 1);
+		assert!(!game.state.marching_right);
+		game.step();
+		assert_eq!(game.state.alien_coords((0, 5)).0, x - 1);
+	}
+
+	#[test]
+	fn one_shot_at_a_time() {
+		let mut game = harness(new_state());
+		game.press(Action::Confirm);
+		let first = game.state.shot;
+		game.press(Action::MoveLeft).press(Action::Confirm);
+		assert_eq!(game.state.shot, first);
+		assert!(first.is_some());
+	}
+
+	#[test]
+	fn shots_knock_out_aliens() {
+		let mut state = new_state();
+		// Lined up under the bottom alien of the sixth column
+		let (x, _) = state.alien_coords((4, 5));
+		state.player_x = x as u16 + 1 - PLAYER.width() / 2;
+		state.march_ticks = u32::MAX;
+		let mut game = harness(state);
+		game.press(Action::Confirm).run(20);
+		assert!(!game.state.aliens[4][5]);
+		assert_eq!(game.state.score, ALIEN_POINTS[2]);
+		assert_eq!(game.state.shot, None);
+		// Next one up is worth more
+		game.press(Action::Confirm).run(20);
+		assert!(!game.state.aliens[3][5]);
+		assert_eq!(game.state.score, ALIEN_POINTS[2] * 2);
+	}
+
+	#[test]
+	fn bunkers_wear_away() {
+		let mut state = new_state();
+		let x = state.bunkers[0].iter().position(|health| *health > 0).unwrap() as u16;
+		let top = state.bunker_top() as f32;
+		state.bullets.push((x, top - 0.5));
+		state.march_ticks = u32::MAX;
+		let mut game = harness(state);
+		game.step();
+		assert!(game.state.bullets.is_empty());
+		assert_eq!(game.state.bunkers[0][x as usize], BUNKER_HEALTH - 1);
+		game.state.bullets.push((x, top - 0.5));
+		game.step();
+		assert_eq!(game.state.bunkers[0][x as usize], 0);
+		// Straight through the hole to the row under it
+		game.state.bullets.push((x, top - 0.5));
+		game.run(3);
+		assert!(game.state.bullets.is_empty());
+		assert_eq!(game.state.bunkers[1][x as usize], BUNKER_HEALTH - 1);
+	}
+
+	#[test]
+	fn bullets_cost_lives() {
+		let mut state = new_state();
+		let middle = state.player_x + PLAYER.width() / 2;
+		state.bullets.push((middle, state.player_top() as f32 - 0.5));
+		let mut game = harness(state);
+		game.step();
+		assert_eq!(game.state.lives, LIVES - 1);
+		assert_eq!(game.state.respawn_ticks, RESPAWN_TICKS);
+		// Frozen until the cannon's back
+		let formation = game.state.formation;
+		game.press(Action::MoveLeft).run(RESPAWN_TICKS - 1);
+		assert_eq!(game.state.formation, formation);
+		assert_eq!(game.state.player_x + PLAYER.width() / 2, middle);
+		game.state.lives = 1;
+		game.state.respawn_ticks = 0;
+		let top = game.state.player_top() as f32;
+		game.state.bullets.push((middle, top - 0.5));
+		assert!(game.run_until_finished(1));
+	}
+
+	#[test]
+	fn reaching_the_cannon_ends_the_game() {
+		let mut state = new_state();
+		state.formation.1 = state.player_top() as i32 - 1 - (ALIEN_ROWS as i32 - 1) * ALIEN_SPACING.1 as i32;
+		// About to drop a row at the left edge
+		state.formation.0 = 0;
+		state.marching_right = false;
+		state.march_ticks = 1;
+		let mut game = harness(state);
+		assert!(game.run_until_finished(1));
+	}
+
+	#[test]
+	fn ufo_is_worth_a_mystery_score() {
+		let mut state = new_state();
+		clear_aliens(&mut state);
+		state.aliens[0][0] = true;
+		state.march_ticks = u32::MAX;
+		state.ufo = Some(Ufo { x: 30.0, right: false });
+		state.shot = Some((30, 2.0));
+		let mut game = harness(state);
+		game.run(2);
+		assert_eq!(game.state.ufo, None);
+		assert!(UFO_POINTS.contains(&game.state.score));
+	}
+
+	#[test]
+	fn clearing_a_wave_brings_the_next() {
+		let mut state = new_state();
+		clear_aliens(&mut state);
+		let mut game = harness(state);
+		game.step();
+		assert_eq!(game.state.wave, 2);
+		assert_eq!(game.state.aliens_left(), ALIEN_ROWS * ALIEN_COLUMNS);
+		assert_eq!(game.state.formation.1, FORMATION_TOP as i32 + 1);
+	}
+
+	#[test]
+	fn snapshot_wave() {
+		let mut state = new_state();
+		state.aliens[4][2] = false;
+		state.aliens[0][7] = false;
+		state.bunkers[0][6] = 1;
+		state.bunkers[1][6] = 0;
+		state.ufo = Some(Ufo { x: 10.0, right: true });
+		state.bullets.push((20, 12.0));
+		let mut game = harness(state);
+		game.press(Action::MoveRight).press(Action::Confirm).step();
+		assert_snapshot("invaders_wave", &game.frame());
+	}
+}
@@ -0,0 +1,533 @@
+use std::{collections::VecDeque, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use rand::prelude::*;
+use termion::event::MouseButton;
+use crate::common::{
+	achievements::*,
+	config::*,
+	game::*,
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{boxes::BoxPrint, layout::*, text::TextPrint, theme::*, toast::draw_toast, util::*}
+};
+
+// Each square is two columns wide so the board looks square
+static CELL_WIDTH: u16 = 2;
+static TICK_TIME: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cover {
+	Hidden,
+	Flagged,
+	Revealed
+}
+
+struct GameState {
+	width: u16,
+	height: u16,
+	mines: u16,
+	level: MinesLevel,
+	// Empty until the first reveal, the mines are placed around wherever that is
+	mine: Vec<bool>,
+	cover: Vec<Cover>,
+	cursor: (u16, u16),
+	// Set by the first reveal, the clock stops with the time taken once the game ends
+	started_at: Option<Instant>,
+	time_taken: Option<Duration>,
+	exploded: Option<usize>,
+	won: bool,
+	quit: bool,
+	header_rect: Rect,
+	board_rect: Rect,
+	status_rect: Rect,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("minesweeper", &[
+		(Action::MoveUp, &["w", "up"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Confirm, &["space", "enter"]),
+		(Action::Flag, &["f"]),
+		(Action::Back, &["escape"])
+	])
+}
+
+impl GameState {
+	// The board is the area inside the border, mines are placed using the seed
+	fn new(board: (u16, u16, u16), level: MinesLevel, board_rect: Rect, header_rect: Rect, status_rect: Rect, seed: u64) -> GameState {
+		let (width, height, mines) = board;
+		let cells = width as usize * height as usize;
+		GameState {
+			width,
+			height,
+			mines,
+			level,
+			mine: vec![],
+			cover: vec![Cover::Hidden; cells],
+			cursor: (width / 2, height / 2),
+			started_at: None,
+			time_taken: None,
+			exploded: None,
+			won: false,
+			quit: false,
+			header_rect,
+			board_rect,
+			status_rect,
+			rng: StdRng::seed_from_u64(seed)
+		}
+	}
+	fn index(&self, (x, y): (u16, u16)) -> usize {
+		y as usize * self.width as usize + x as usize
+	}
+	fn neighbours(&self, index: usize) -> Vec<usize> {
+		let (width, height) = (self.width as i32, self.height as i32);
+		let (x, y) = (index as i32 % width, index as i32 / width);
+		let mut found = vec![];
+		for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+			let (nx, ny) = (x + dx, y + dy);
+			if (0..width).contains(&nx) && (0..height).contains(&ny) {
+				found.push((ny * width + nx) as usize);
+			}
+		}
+		found
+	}
+	fn adjacent_mines(&self, index: usize) -> usize {
+		self.neighbours(index).into_iter().filter(|i| self.mine.get(*i) == Some(&true)).count()
+	}
+	fn flags(&self) -> usize {
+		self.cover.iter().filter(|cover| **cover == Cover::Flagged).count()
+	}
+	// Keeps the first square revealed and its neighbours clear, so the first reveal always opens an area
+	fn place_mines(&mut self, safe: usize) {
+		let mut keep_clear = self.neighbours(safe);
+		keep_clear.push(safe);
+		let candidates = (0..self.cover.len()).filter(|i| !keep_clear.contains(i)).collect::<Vec<usize>>();
+		self.mine = vec![false; self.cover.len()];
+		for index in candidates.choose_multiple(&mut self.rng, self.mines as usize) {
+			self.mine[*index] = true;
+		}
+	}
+	fn started(&self) -> bool {
+		!self.mine.is_empty()
+	}
+	fn finished(&self) -> bool {
+		self.won || self.quit || self.exploded.is_some()
+	}
+	// Opens the square, and every square around it that has no mines next to it
+	fn reveal(&mut self, index: usize) {
+		if self.finished() || self.cover[index] != Cover::Hidden {return};
+		if !self.started() {
+			self.place_mines(index);
+		}
+		self.started_at.get_or_insert_with(Instant::now);
+		if self.mine[index] {
+			self.exploded = Some(index);
+			self.stop_clock();
+			return;
+		}
+		let mut queue = VecDeque::from([index]);
+		self.cover[index] = Cover::Revealed;
+		while let Some(next) = queue.pop_front() {
+			if self.adjacent_mines(next) > 0 {continue};
+			for neighbour in self.neighbours(next) {
+				if self.cover[neighbour] == Cover::Hidden {
+					self.cover[neighbour] = Cover::Revealed;
+					queue.push_back(neighbour);
+				}
+			}
+		}
+		self.check_won();
+	}
+	// Opens the unflagged squares around a number once it has as many flags around it
+	fn chord(&mut self, index: usize) {
+		if self.cover[index] != Cover::Revealed {return};
+		let neighbours = self.neighbours(index);
+		let flagged = neighbours.iter().filter(|i| self.cover[**i] == Cover::Flagged).count();
+		if flagged != self.adjacent_mines(index) {return};
+		for neighbour in neighbours {
+			self.reveal(neighbour);
+		}
+	}
+	fn toggle_flag(&mut self, index: usize) {
+		if self.finished() {return};
+		self.cover[index] = match self.cover[index] {
+			Cover::Hidden => Cover::Flagged,
+			Cover::Flagged => Cover::Hidden,
+			Cover::Revealed => Cover::Revealed
+		};
+	}
+	fn open(&mut self, index: usize) {
+		match self.cover[index] {
+			Cover::Hidden => self.reveal(index),
+			Cover::Revealed => self.chord(index),
+			Cover::Flagged => {}
+		}
+	}
+	fn check_won(&mut self) {
+		let revealed = self.cover.iter().filter(|cover| **cover == Cover::Revealed).count();
+		if revealed + self.mines as usize != self.cover.len() {return};
+		self.won = true;
+		self.stop_clock();
+		// Whatever's left is a mine
+		for (cover, mine) in self.cover.iter_mut().zip(&self.mine) {
+			if *mine {*cover = Cover::Flagged};
+		}
+		report_event("minesweeper", "cleared", level_rank(self.level));
+	}
+	fn move_cursor(&mut self, (dx, dy): (i32, i32)) {
+		let x = (self.cursor.0 as i32 + dx).clamp(0, self.width as i32 - 1);
+		let y = (self.cursor.1 as i32 + dy).clamp(0, self.height as i32 - 1);
+		self.cursor = (x as u16, y as u16);
+	}
+	// Clicks on the board move the cursor there as well
+	fn click(&mut self, button: MouseButton, coords: (u16, u16)) {
+		if self.finished() || !self.board_rect.contains(coords) {return};
+		self.cursor = ((coords.0 - self.board_rect.x) / CELL_WIDTH, coords.1 - self.board_rect.y);
+		let index = self.index(self.cursor);
+		match button {
+			MouseButton::Left => self.open(index),
+			MouseButton::Right => self.toggle_flag(index),
+			MouseButton::Middle => self.chord(index),
+			_ => {}
+		}
+	}
+	fn stop_clock(&mut self) {
+		if self.time_taken.is_none() {
+			self.time_taken = self.started_at.map(|started_at| started_at.elapsed());
+		}
+	}
+	// In tenths, like the clock shows
+	fn seconds(&self) -> f32 {
+		let elapsed = self.time_taken.or_else(|| self.started_at.map(|started_at| started_at.elapsed()));
+		elapsed.map_or(0.0, |elapsed| (elapsed.as_millis() / 100) as f32 / 10.0)
+	}
+}
+
+// How hard a cleared board was, for the achievements. Custom boards don't count
+fn level_rank(level: MinesLevel) -> u32 {
+	match level {
+		MinesLevel::Beginner => 1,
+		MinesLevel::Intermediate => 2,
+		MinesLevel::Expert => 3,
+		MinesLevel::Custom => 0
+	}
+}
+
+fn time_label(level: MinesLevel) -> Option<&'static str> {
+	match level {
+		MinesLevel::Beginner => Some("Beginner time"),
+		MinesLevel::Intermediate => Some("Intermediate time"),
+		MinesLevel::Expert => Some("Expert time"),
+		MinesLevel::Custom => None
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		if self.finished() {return};
+		let index = self.index(self.cursor);
+		match action {
+			Action::MoveUp => self.move_cursor((0, -1)),
+			Action::MoveDown => self.move_cursor((0, 1)),
+			Action::MoveLeft => self.move_cursor((-1, 0)),
+			Action::MoveRight => self.move_cursor((1, 0)),
+			Action::Confirm => self.open(index),
+			Action::Flag => self.toggle_flag(index),
+			Action::Back => self.quit = true,
+			_ => {}
+		}
+	}
+	// The clock is read when drawing, so there's nothing to step
+	fn tick(&mut self) {}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished()
+	}
+}
+
+// Text and colour of a square, the mines are shown once the game is lost
+fn cell_look(state: &GameState, index: usize) -> (String, ThemeColor) {
+	let theme = current_theme();
+	let mine = state.mine.get(index) == Some(&true);
+	let lost = state.exploded.is_some();
+	match state.cover[index] {
+		Cover::Flagged if lost && !mine => (" x".to_owned(), theme.entities.mines_mine),
+		Cover::Flagged => (" F".to_owned(), theme.entities.mines_mine),
+		Cover::Hidden if lost && mine => (" *".to_owned(), theme.text),
+		Cover::Hidden => (repeat_str(theme.glyphs.shade, CELL_WIDTH), theme.border),
+		Cover::Revealed => match state.adjacent_mines(index) {
+			0 => ("  ".to_owned(), theme.text),
+			count => (format!(" {}", count), theme.entities.mines_numbers[count - 1])
+		}
+	}
+}
+
+fn draw_board(state: &GameState) {
+	let theme = current_theme();
+	for index in 0..state.cover.len() {
+		let (x, y) = ((index % state.width as usize) as u16, (index / state.width as usize) as u16);
+		let coords = (state.board_rect.x + x * CELL_WIDTH, state.board_rect.y + y);
+		let (text, fg) = cell_look(state, index);
+		let bg = if state.exploded == Some(index) {
+			theme.entities.mines_mine
+		} else if (x, y) == state.cursor && !state.finished() {
+			theme.accent
+		} else {
+			theme.fill
+		};
+		let fg = if state.exploded == Some(index) || bg == theme.accent {theme.fill} else {fg};
+		print_clipped(&text, coords, Some(state.board_rect), Some(&bg), Some(&fg));
+	}
+}
+
+fn write_header(state: &GameState) {
+	let theme = current_theme();
+	let mines_left = state.mines as i32 - state.flags() as i32;
+	let color = if state.won {theme.accent} else {theme.text};
+	TextPrint::in_rect(&format!("Mines: {}", mines_left), state.header_rect, Align::Start)
+		.set_max_width(state.header_rect.width)
+		.color_fg(&color)
+		.print();
+	TextPrint::in_rect(&format!("Time: {}", state.seconds() as u32), state.header_rect, Align::End)
+		.color_fg(&color)
+		.print();
+}
+
+fn write_game_text(state: &GameState) {
+	let text = if state.won {
+		format!("Cleared in {:.1}s, press any key to continue", state.seconds())
+	} else if state.exploded.is_some() {
+		"Boom! Press any key to continue".to_owned()
+	} else {
+		format!("{}, {} mines", capitalise(state.level.name()), state.mines)
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn capitalise(text: &str) -> String {
+	let mut chars = text.chars();
+	chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(state.board_rect.outer(Padding::uniform(1))).print();
+	write_header(state);
+	draw_board(state);
+	write_game_text(state);
+	draw_toast();
+	cursor_to_end();
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let minesweeper = &setup.config.minesweeper;
+	let board = minesweeper.board();
+	// Mine counter and timer, the board with its border, then a status line
+	let frame = Rect::screen().centered((board.0 * CELL_WIDTH + 2, board.1 + 4));
+	let [header_rect, board_rect, status_rect] = Layout::vertical(&[Constraint::Fixed(1), Constraint::Min(3), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	let header_rect = header_rect.inner(Padding::symmetric(1, 0));
+	// Small boards are narrower than the messages
+	let status_rect = Rect::new((1, status_rect.y), (Rect::screen().width, 1));
+	GameState::new(board, minesweeper.level, board_rect.inner(Padding::uniform(1)), header_rect, status_rect, setup.seed)
+}
+
+pub struct Minesweeper {
+	setup: GameSetup
+}
+
+impl Game for Minesweeper {
+	fn run(&self) -> GameReturn {
+		let locked_state = Arc::from(Mutex::from(get_initial_state(&self.setup)));
+		let state_clone = locked_state.clone();
+		let bindings = default_bindings().load_or_default();
+		set_mouse_reporting(true);
+		let input_handler = spawn_event_handler(move |input| {
+			let mut state = state_clone.lock().unwrap();
+			match input {
+				Input::Key(key) => if let Some(action) = bindings.action_for(key) {
+					state.apply(action);
+				},
+				Input::Click(button, coords) => state.click(button, coords)
+			}
+			!state.finished()
+		});
+
+		run_game_loop(&locked_state, |state| {
+			write_screen(state);
+			if state.finished() {Tick::Stop} else {Tick::Continue(TICK_TIME)}
+		});
+		let _ = input_handler.join();
+		let state = locked_state.lock().unwrap();
+		// Leaving on purpose goes straight back, otherwise the board is left up to look over
+		if !state.quit {
+			write_screen(&state);
+			read_input();
+		}
+		set_mouse_reporting(false);
+		let time = time_label(state.level).filter(|_| state.won).map(|label| (label, state.seconds()));
+		GameReturn {
+			score: Score::None,
+			win_state: if state.won {WinState::Win} else if state.quit {WinState::None} else {WinState::Lose},
+			records: vec![],
+			times: time.into_iter().collect()
+		}
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "minesweeper",
+		name: "Minesweeper",
+		description: "Clear the board without setting off a mine, the numbers count the mines next to them",
+		min_players: 1,
+		max_players: 1,
+		options: &[GameOption::Minefield],
+		version: "1.0.0",
+		create: |setup| Box::new(Minesweeper { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "cleared_beginner",
+				name: "Minesweeper",
+				description: "Clear a beginner board",
+				event: "cleared",
+				goal: 1
+			},
+			Achievement {
+				id: "cleared_intermediate",
+				name: "Sapper",
+				description: "Clear an intermediate board",
+				event: "cleared",
+				goal: 2
+			},
+			Achievement {
+				id: "cleared_expert",
+				name: "Bomb disposal",
+				description: "Clear an expert board",
+				event: "cleared",
+				goal: 3
+			}
+		],
+		// Where the mines go depends on the first click, so every player's board would be different
+		daily: None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::Harness, snapshot::assert_snapshot};
+
+	// A board drawn at (2, 3) inside a border, with the header above and the status line under it
+	fn new_state(board: (u16, u16, u16), seed: u64) -> GameState {
+		let (width, height, _) = board;
+		let board_rect = Rect::new((2, 3), (width * CELL_WIDTH, height));
+		let header_rect = Rect::new((2, 1), (width * CELL_WIDTH, 1));
+		let status_rect = Rect::new((1, height + 4), (width * CELL_WIDTH + 2, 1));
+		GameState::new(board, MinesLevel::Custom, board_rect, header_rect, status_rect, seed)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		let size = (state.width * CELL_WIDTH + 2, state.height + 4);
+		Harness::new(state, size)
+	}
+
+	// Mines at the given squares, as if they'd already been placed
+	fn with_mines(width: u16, height: u16, mines: &[(u16, u16)]) -> GameState {
+		let mut state = new_state((width, height, mines.len() as u16), 1);
+		state.mine = vec![false; state.cover.len()];
+		for mine in mines {
+			let index = state.index(*mine);
+			state.mine[index] = true;
+		}
+		state
+	}
+
+	#[test]
+	fn first_reveal_is_safe() {
+		for seed in 0..20 {
+			let mut state = new_state((9, 9, 10), seed);
+			state.reveal(0);
+			assert_eq!(state.mine.iter().filter(|mine| **mine).count(), 10);
+			assert!(state.exploded.is_none());
+			for index in [0, 1, 9, 10] {
+				assert!(!state.mine[index]);
+			}
+			assert_eq!(state.adjacent_mines(0), 0);
+		}
+	}
+
+	#[test]
+	fn flood_fills_to_the_numbers() {
+		let mut state = with_mines(5, 5, &[(4, 4)]);
+		state.reveal(0);
+		let hidden = (0..state.cover.len()).filter(|i| state.cover[*i] != Cover::Revealed).collect::<Vec<usize>>();
+		assert_eq!(hidden, vec![24]);
+		assert!(state.won);
+		assert_eq!(state.cover[24], Cover::Flagged);
+	}
+
+	#[test]
+	fn chords_once_flagged() {
+		let mut state = with_mines(4, 3, &[(0, 0), (3, 0)]);
+		let number = state.index((1, 0));
+		state.cover[number] = Cover::Revealed;
+		state.chord(number);
+		assert_eq!(state.cover[state.index((1, 1))], Cover::Hidden);
+		state.toggle_flag(0);
+		state.chord(number);
+		assert_eq!(state.cover[state.index((0, 1))], Cover::Revealed);
+		assert!(state.exploded.is_none());
+
+		// A wrong flag sets off the mine it was hiding
+		let mut state = with_mines(4, 3, &[(0, 0)]);
+		let number = state.index((1, 1));
+		state.cover[number] = Cover::Revealed;
+		state.toggle_flag(state.index((2, 2)));
+		state.chord(number);
+		assert_eq!(state.exploded, Some(0));
+	}
+
+	#[test]
+	fn plays_with_keys_and_clicks() {
+		let mut game = harness(with_mines(6, 4, &[(5, 0), (5, 3)]));
+		game.press(Action::MoveLeft).press(Action::MoveLeft).press(Action::Confirm).run(15);
+		assert_eq!(game.state.cursor, (1, 2));
+		// Moved back as if a second and a half had passed since the first reveal
+		game.state.started_at = game.state.started_at.map(|started_at| started_at - Duration::from_millis(1500));
+		assert!((1.5..1.6).contains(&game.state.seconds()));
+		// Flag the top right square with the right button, then click the one under it
+		game.state.click(MouseButton::Right, (12, 3));
+		assert_eq!(game.state.cover[5], Cover::Flagged);
+		game.state.click(MouseButton::Left, (12, 4));
+		assert_eq!(game.state.cursor, (5, 1));
+		assert!(!game.state.won);
+		game.state.click(MouseButton::Left, (3, 10));
+		game.press(Action::MoveDown).press(Action::MoveDown).press(Action::MoveDown).press(Action::MoveRight).press(Action::Confirm);
+		assert_eq!(game.state.exploded, Some(23));
+		assert!(game.run_until_finished(1));
+		assert!(game.state.time_taken.is_some_and(|taken| taken >= Duration::from_millis(1500)));
+	}
+
+	#[test]
+	fn snapshot_lost_board() {
+		let mut game = harness(with_mines(8, 5, &[(1, 1), (6, 0), (6, 3), (2, 4)]));
+		game.press(Action::Confirm).run(32);
+		game.state.toggle_flag(game.state.index((6, 0)));
+		game.state.toggle_flag(game.state.index((0, 4)));
+		game.state.cursor = (6, 3);
+		game.state.started_at = game.state.started_at.map(|started_at| started_at - Duration::from_millis(3200));
+		game.press(Action::Confirm);
+		assert_snapshot("minesweeper_lost", &game.frame());
+	}
+}
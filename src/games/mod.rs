@@ -1,3 +1,25 @@
-pub mod snake;
-pub mod pong;
-pub mod quit;
\ No newline at end of file
+use crate::common::registry::*;
+
+pub(crate) mod snake;
+pub(crate) mod pong;
+pub(crate) mod tetris;
+pub(crate) mod tiles;
+pub(crate) mod minesweeper;
+pub(crate) mod breakout;
+pub(crate) mod invaders;
+pub(crate) mod tron;
+
+// Online Pong is started from the command line rather than the launcher
+pub use pong::online::{OnlinePong, Role};
+
+pub fn register_all(registry: &mut Registry) -> Result<(), RegistryError> {
+	registry.register(snake::get_game_entry())?;
+	registry.register(pong::get_game_entry())?;
+	registry.register(tetris::get_game_entry())?;
+	registry.register(tiles::get_game_entry())?;
+	registry.register(minesweeper::get_game_entry())?;
+	registry.register(breakout::get_game_entry())?;
+	registry.register(invaders::get_game_entry())?;
+	registry.register(tron::get_game_entry())?;
+	Ok(())
+}
@@ -0,0 +1,619 @@
+use std::{sync::{Arc, Mutex}, time::Duration};
+
+use rand::prelude::*;
+
+use crate::common::{achievements::*, ball::*, config::*, game::*, game_loop::*, headless::Simulation, input::*, registry::*, screen::*};
+
+use self::util::*;
+use self::bigtext::*;
+use self::boxes::*;
+use self::layout::*;
+use self::text::*;
+use self::theme::*;
+use self::toast::*;
+
+pub mod online;
+
+static PADDLE_HEIGHT: u8 = 3;
+static BALL_SPEED: f32 = 1.5;
+static PI: f32 = std::f32::consts::PI;
+// Off the ends of a paddle
+static MAX_ANGLE: f32 = PI / 6.0;
+static FRAME_TIME: Duration = Duration::from_millis(50);
+// Largest board, smaller screens get a smaller one
+static BOARD_SIZE: (u16, u16) = (50, 15);
+
+#[derive(Debug, PartialEq, Eq)]
+enum VerticalDirection {
+	Up,
+	Down
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+	Left,
+	Right
+}
+
+// What moves a paddle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+	Keys,
+	Computer(Difficulty),
+	// A player on the other end of a network connection
+	Remote
+}
+
+struct Paddle {
+	y: u16,
+	direction: VerticalDirection,
+	score: u16,
+}
+
+struct GameState {
+	bounds: (u16, u16),
+	offset: (u16, u16),
+	status_rect: Rect,
+	finished: bool,
+	paused: bool,
+	points_to_win: u16,
+	left_control: Control,
+	right_control: Control,
+	left_paddle: Paddle,
+	right_paddle: Paddle,
+	ball: Ball,
+	// Paddle hits since the last point
+	rally: u32,
+	longest_rally: u32,
+	rng: StdRng,
+	// The side played from this terminal
+	local_side: Side,
+	opponent: String,
+	// Set when neither side is "you", as in a match between two players at the same keyboard
+	player_names: Option<(String, String)>,
+	disconnected: bool
+}
+
+fn board_rect(state: &GameState) -> Rect {
+	Rect::new(state.offset, state.bounds)
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("pong", &[
+		(Action::MoveUp, &["w", "up"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+// For the right hand player when two share the keyboard, these take priority over the pong bindings
+pub fn right_player_bindings() -> Bindings {
+	Bindings::new("pong_right", &[
+		(Action::MoveUp, &["up"]),
+		(Action::MoveDown, &["down"])
+	])
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(board_rect(state).outer(Padding::uniform(1)))
+		.print();
+	draw_big_score(state);
+	draw_ball(state);
+	draw_paddles(state);
+	write_game_text(state);
+	if state.finished {
+		print_splash("Game over", &format!("{} {} - {}", get_win_text(state), state.left_paddle.score, state.right_paddle.score));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+fn draw_big_score(state: &GameState) {
+	let theme = current_theme();
+	let score = format!("{}-{}", state.left_paddle.score, state.right_paddle.score);
+	let rect = board_rect(state).inner(Padding { top: 1, ..Padding::default() });
+	let mut big_score = BigText::in_rect(&score, rect, Align::Center);
+	if !big_score.fits(rect) {return};
+	big_score
+		.color_fg(&theme.border)
+		.print();
+}
+
+impl GameState {
+	// Scores as (this player's, the opponent's)
+	fn local_scores(&self) -> (u16, u16) {
+		match self.local_side {
+			Side::Left => (self.left_paddle.score, self.right_paddle.score),
+			Side::Right => (self.right_paddle.score, self.left_paddle.score)
+		}
+	}
+}
+
+fn get_win_text(state: &GameState) -> String {
+	let (score, opponent_score) = state.local_scores();
+	if state.disconnected {
+		format!("{} disconnected", state.opponent)
+	} else if let Some((left_name, right_name)) = &state.player_names {
+		format!("{} wins", if state.left_paddle.score > state.right_paddle.score {left_name} else {right_name})
+	} else if score > opponent_score {
+		"You win".to_owned()
+	} else {
+		format!("{} wins", state.opponent)
+	}
+}
+
+fn get_game_return(state: &GameState) -> GameReturn {
+	let (score, opponent_score) = state.local_scores();
+	GameReturn {
+		score: if state.player_names.is_some() {
+			Score::Versus(score as f32, opponent_score as f32)
+		} else {
+			Score::TwoPlayer(score as f32, opponent_score as f32)
+		},
+		win_state: if state.disconnected {
+			WinState::Disconnected
+		} else if score > opponent_score {
+			WinState::Win
+		} else {
+			WinState::Lose
+		},
+		records: vec![("Longest rally", state.longest_rally)],
+		times: vec![]
+	}
+}
+
+fn draw_paddles(state: &GameState) {
+	let theme = current_theme();
+	for (paddle_i, paddle) in [&state.left_paddle, &state.right_paddle].into_iter().enumerate() {
+		for i in 0..=(PADDLE_HEIGHT-1) {
+			let x: u16 = if paddle_i == 0 {0} else {state.bounds.0 - 1};
+			print_clipped(theme.glyphs.block, (
+				state.offset.0 + x,
+				state.offset.1 + paddle.y + i as u16 - 1
+			), Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.pong_paddle))
+		}
+	}
+}
+
+fn write_game_text(game_state: &GameState) {
+	let text = if game_state.finished {
+		format!("{}, score: {} - {}, press any key to continue", get_win_text(game_state), game_state.left_paddle.score, game_state.right_paddle.score)
+	} else if game_state.paused {
+		format!("Paused, score: {} - {}", game_state.left_paddle.score, game_state.right_paddle.score)
+	} else {
+		format!("Score: {} - {}", game_state.left_paddle.score, game_state.right_paddle.score)
+	};
+	TextPrint::in_rect(&text, game_state.status_rect, Align::Center)
+		.set_max_width(game_state.status_rect.width)
+		.print();
+}
+
+fn draw_ball(state: &GameState) {
+	let theme = current_theme();
+	print_clipped(theme.glyphs.ball, (
+		state.offset.0 + (state.ball.pos.0.floor() as u16),
+		state.offset.1 + (state.ball.pos.1.floor() as u16),
+	), Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.pong_ball));
+}
+
+impl GameState {
+	// The board is the area inside the border
+	fn new(board: Rect, status_rect: Rect, points_to_win: u16, difficulty: Difficulty, seed: u64) -> GameState {
+		let bounds = (std::cmp::max(board.width, 2), std::cmp::max(board.height, PADDLE_HEIGHT as u16));
+		let mut state = GameState {
+			bounds,
+			finished: false,
+			paused: false,
+			points_to_win: std::cmp::max(points_to_win, 1),
+			left_control: Control::Keys,
+			right_control: Control::Computer(difficulty),
+			left_paddle: Paddle {
+				direction: VerticalDirection::Down,
+				y: 1,
+				score: 0
+			},
+			right_paddle: Paddle {
+				direction: VerticalDirection::Down,
+				y: 1,
+				score: 0
+			},
+			ball: Ball::new((2.0, 1.0), Axis::Horizontal, true, MAX_ANGLE, BALL_SPEED),
+			rally: 0,
+			longest_rally: 0,
+			rng: StdRng::seed_from_u64(seed),
+			local_side: Side::Left,
+			opponent: "Computer".to_owned(),
+			player_names: None,
+			disconnected: false,
+			offset: board.coords(),
+			status_rect
+		};
+		serve(&mut state, Side::Right);
+		state
+	}
+	fn control(&self, side: Side) -> Control {
+		match side {
+			Side::Left => self.left_control,
+			Side::Right => self.right_control
+		}
+	}
+	fn paddle_mut(&mut self, side: Side) -> &mut Paddle {
+		match side {
+			Side::Left => &mut self.left_paddle,
+			Side::Right => &mut self.right_paddle
+		}
+	}
+	fn apply_to(&mut self, side: Side, action: Action) {
+		match action {
+			Action::Pause => self.paused = !self.paused,
+			_ if self.paused => {},
+			Action::MoveUp => self.paddle_mut(side).direction = VerticalDirection::Up,
+			Action::MoveDown => self.paddle_mut(side).direction = VerticalDirection::Down,
+			_ => {}
+		};
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		self.apply_to(Side::Left, action);
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished {return};
+		update_state(self);
+		if is_over(self) {
+			self.finished = true;
+			report_result(self);
+		}
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+// Board inside its border, and the status line under it with a gap
+fn board_layout(bounds: (u16, u16)) -> (Rect, Rect) {
+	let frame = Rect::screen().centered((bounds.0.saturating_add(2), bounds.1.saturating_add(4)));
+	let [board_rect, _, status_rect] = Layout::vertical(&[Constraint::Min(3), Constraint::Fixed(1), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	(board_rect.inner(Padding::uniform(1)), status_rect)
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let config = &setup.config;
+	let (board, status_rect) = board_layout(BOARD_SIZE);
+	GameState::new(board, status_rect, config.pong.points_to_win, config.difficulty, setup.seed)
+}
+
+fn update_paddles(state: &mut GameState) {
+	for paddle in [&mut state.left_paddle, &mut state.right_paddle].into_iter() {
+		match paddle.direction {
+			VerticalDirection::Down => paddle.y += 1,
+			VerticalDirection::Up => paddle.y -= 1
+		}
+		paddle.y = std::cmp::min(paddle.y, state.bounds.1 + 1 - PADDLE_HEIGHT as u16);
+		paddle.y = std::cmp::max(paddle.y, 1);
+	}
+}
+
+// From the side the ball is heading away from, at a random height and angle
+fn serve(state: &mut GameState, towards: Side) {
+	let x = match towards {
+		Side::Right => 2.0,
+		Side::Left => (state.bounds.0 - 1) as f32
+	};
+	let y = state.rng.gen_range(1.0..(state.bounds.1 as f32 - 1.0));
+	let angle = state.rng.gen_range((PI / 12.0)..=(PI / 6.0));
+	let angle = if state.rng.gen() {angle} else {-angle};
+	state.ball = Ball::new((x, y), Axis::Horizontal, towards == Side::Right, angle, BALL_SPEED);
+}
+
+fn hit_paddle(state: &mut GameState) {
+	state.rally += 1;
+	state.longest_rally = std::cmp::max(state.longest_rally, state.rally);
+	// Rallies between two computers don't count
+	if state.left_control == Control::Keys || state.right_control == Control::Keys {
+		report_event("pong", "rally", state.rally);
+	}
+}
+
+fn report_result(state: &GameState) {
+	// The achievements are all for beating the computer
+	let Control::Computer(difficulty) = state.right_control else {return};
+	if state.left_control != Control::Keys || state.left_paddle.score <= state.right_paddle.score {return};
+	report_event("pong", "win", 1);
+	if state.right_paddle.score == 0 {
+		report_event("pong", "shutout", 1);
+	}
+	if difficulty == Difficulty::Hard {
+		report_event("pong", "hard_win", 1);
+	}
+}
+
+fn update_ball(state: &mut GameState) {
+	state.ball.step(state.bounds.1 as f32);
+	let x = state.ball.pos.0;
+	// The side the ball has reached, and whether it's already gone past the paddle
+	let (side, gone) = if x < 1.0 {
+		(Side::Left, x < -1.0)
+	} else if x > (state.bounds.0 - 1) as f32 {
+		(Side::Right, x > state.bounds.0 as f32 + 1.0)
+	} else {
+		return;
+	};
+	// Paddle cells run from y - 1 to y + 1
+	let top = state.paddle_mut(side).y as f32 - 1.0;
+	if gone || !state.ball.is_over(top, PADDLE_HEIGHT as f32) {
+		let scorer = if side == Side::Left {Side::Right} else {Side::Left};
+		state.paddle_mut(scorer).score += 1;
+		state.rally = 0;
+		serve(state, scorer);
+	} else {
+		state.ball.bounce_off(top, PADDLE_HEIGHT as f32, MAX_ANGLE);
+		hit_paddle(state);
+	}
+}
+
+fn update_cpu(state: &mut GameState, side: Side, difficulty: Difficulty) {
+	// How far across the ball has to be before the computer reacts to it, measured from the left for the right paddle
+	let reaction_point = match difficulty {
+		Difficulty::Easy => state.bounds.0 as f32 * 0.75,
+		Difficulty::Normal => (state.bounds.0 / 2) as f32,
+		Difficulty::Hard => 0.0
+	};
+	let ball_x = state.ball.pos.0;
+	let coming = match side {
+		Side::Left => ball_x < state.bounds.0 as f32 - reaction_point && !state.ball.forward,
+		Side::Right => ball_x > reaction_point && state.ball.forward
+	};
+	if coming {
+		let ball_y = state.ball.pos.1;
+		let paddle = state.paddle_mut(side);
+		paddle.direction = if ball_y > paddle.y as f32 + (PADDLE_HEIGHT as f32 / 2.0) {VerticalDirection::Down} else {VerticalDirection::Up};
+	}
+}
+
+fn update_state(state: &mut GameState) {
+	for side in [Side::Left, Side::Right] {
+		if let Control::Computer(difficulty) = state.control(side) {
+			update_cpu(state, side, difficulty);
+		}
+	}
+	update_paddles(state);
+	update_ball(state);
+}
+
+fn is_over(state: &GameState) -> bool {
+	state.left_paddle.score == state.points_to_win || state.right_paddle.score == state.points_to_win
+}
+
+// Plays the game out on this terminal, whoever controls the paddles
+fn play(state: GameState) -> GameReturn {
+	let locked_state = Arc::from(Mutex::from(state));
+	let state_clone = locked_state.clone();
+	let bindings = default_bindings().load_or_default();
+	let right_bindings = right_player_bindings().load_or_default();
+	let input_handler = spawn_input_handler(move |key| {
+		let mut state = state_clone.lock().unwrap();
+		if state.finished {return false;}
+		let right_action = right_bindings.action_for(key).filter(|_| state.right_control == Control::Keys);
+		let side_action = match right_action {
+			Some(action) => Some((Side::Right, action)),
+			None => bindings.action_for(key).map(|action| (Side::Left, action))
+		};
+		// Anyone can pause, but the computer's paddle isn't theirs to move
+		if let Some((side, action)) = side_action {
+			if action == Action::Pause || state.control(side) == Control::Keys {
+				state.apply_to(side, action);
+			}
+		}
+		true
+	});
+	run_game_loop(&locked_state, |state| {
+		state.tick();
+		write_screen(state);
+		if state.finished {Tick::Stop} else {Tick::Continue(FRAME_TIME)}
+	});
+	let game_return = get_game_return(&locked_state.lock().unwrap());
+	let _ = input_handler.join();
+	game_return
+}
+
+pub struct Pong {
+	setup: GameSetup
+}
+
+impl Game for Pong {
+	fn run(&self) -> GameReturn {
+		play(get_initial_state(&self.setup))
+	}
+}
+
+// A match between two named players, each on the keyboard or the computer. The result is from
+// the left hand player's side
+pub struct Versus {
+	setup: GameSetup,
+	left: (String, Control),
+	right: (String, Control)
+}
+
+impl Versus {
+	pub fn new(setup: GameSetup, left: (String, Control), right: (String, Control)) -> Versus {
+		Versus {
+			setup,
+			left,
+			right
+		}
+	}
+}
+
+impl Game for Versus {
+	fn run(&self) -> GameReturn {
+		let mut state = get_initial_state(&self.setup);
+		state.left_control = self.left.1;
+		state.right_control = self.right.1;
+		state.opponent = self.right.0.clone();
+		state.player_names = Some((self.left.0.clone(), self.right.0.clone()));
+		play(state)
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "pong",
+		name: "Pong",
+		description: "First to the target score against the computer",
+		min_players: 1,
+		max_players: 2,
+		options: &[GameOption::Difficulty, GameOption::PongPointsToWin],
+		version: "1.1.0",
+		create: |setup| Box::new(Pong { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "win",
+				name: "First win",
+				description: "Win a game",
+				event: "win",
+				goal: 1
+			},
+			Achievement {
+				id: "rally_20",
+				name: "Marathon",
+				description: "Keep a rally going for 20 hits",
+				event: "rally",
+				goal: 20
+			},
+			Achievement {
+				id: "shutout",
+				name: "Shutout",
+				description: "Win without the computer scoring",
+				event: "shutout",
+				goal: 1
+			},
+			Achievement {
+				id: "hard_win",
+				name: "Hard fought",
+				description: "Win on hard difficulty",
+				event: "hard_win",
+				goal: 1
+			}
+		],
+		daily: Some(|config, day| {
+			config.pong = PongConfig { points_to_win: 5 };
+			config.difficulty = Difficulty::all()[day as usize % 3];
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::Harness, snapshot::assert_snapshot};
+
+	// Board drawn at (2, 2) inside a border, with the status line two rows under it
+	fn new_state(points_to_win: u16) -> GameState {
+		let board = Rect::new((2, 2), (20, 10));
+		let status_rect = Rect::new((1, 14), (22, 1));
+		GameState::new(board, status_rect, points_to_win, Difficulty::Normal, 1)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		Harness::new(state, (22, 15))
+	}
+
+	#[test]
+	fn named_players_get_a_versus_score() {
+		let mut state = new_state(10);
+		(state.left_paddle.score, state.right_paddle.score) = (3, 1);
+		assert!(matches!(get_game_return(&state).score, Score::TwoPlayer(3.0, 1.0)));
+		state.player_names = Some(("Ada".to_owned(), "Grace".to_owned()));
+		assert!(matches!(get_game_return(&state).score, Score::Versus(3.0, 1.0)));
+	}
+
+	#[test]
+	fn missed_ball_scores_for_the_other_side() {
+		let mut state = new_state(10);
+		state.ball.pos = (3.0, 8.0);
+		(state.ball.forward, state.ball.angle) = (false, 0.0);
+		state.left_paddle.direction = VerticalDirection::Up;
+		let mut game = harness(state);
+		game.run(2);
+		assert_eq!(game.state.right_paddle.score, 1);
+		assert_eq!(game.state.left_paddle.score, 0);
+		assert_eq!(game.state.ball.pos.0, 2.0);
+		assert!(game.state.ball.forward);
+	}
+
+	#[test]
+	fn ball_bounces_off_paddle() {
+		let mut state = new_state(10);
+		state.ball.pos = (2.0, 5.0);
+		(state.ball.forward, state.ball.angle) = (false, 0.0);
+		state.left_paddle.y = 4;
+		let mut game = harness(state);
+		game.step();
+		assert!(game.state.ball.forward);
+		assert_eq!(game.state.right_paddle.score, 0);
+		assert_eq!(game.state.longest_rally, 1);
+		game.step();
+		assert!(game.state.ball.pos.0 > 1.0);
+	}
+
+	#[test]
+	fn ball_bounces_off_bottom_wall() {
+		let mut state = new_state(10);
+		state.ball.pos = (10.0, 9.5);
+		(state.ball.forward, state.ball.angle) = (true, PI / 6.0);
+		let mut game = harness(state);
+		game.step();
+		assert!(game.state.ball.angle < 0.0);
+		assert!(game.state.ball.pos.1 <= 10.0);
+	}
+
+	#[test]
+	fn game_ends_at_points_to_win() {
+		let mut state = new_state(1);
+		state.ball.pos = (3.0, 8.0);
+		(state.ball.forward, state.ball.angle) = (false, 0.0);
+		state.left_paddle.direction = VerticalDirection::Up;
+		let mut game = harness(state);
+		assert!(game.run_until_finished(10));
+		assert_eq!(game.ticks, 2);
+		assert!(game.frame().contains("Computer wins"));
+	}
+
+	#[test]
+	fn paddle_follows_input() {
+		let mut game = harness(new_state(10));
+		game.run_script(&[(0, Action::MoveDown), (3, Action::MoveUp)]);
+		assert_eq!(game.state.left_paddle.y, 3);
+	}
+
+	#[test]
+	fn frame_shows_paddles_and_score() {
+		let mut game = harness(new_state(10));
+		game.press(Action::MoveUp).step();
+		let frame = game.frame();
+		let block = current_theme().glyphs.block;
+		// Paddle cells run from y - 1 to y + 1 below the top of the board
+		for y in 2..=4 {
+			assert_eq!(frame.get((2, y)).unwrap().symbol, block);
+		}
+		assert!(frame.contains("Score: 0 - 0"));
+	}
+
+	#[test]
+	fn snapshot_after_twenty_ticks() {
+		let mut game = harness(new_state(10));
+		game.run_script(&[(5, Action::MoveUp)]);
+		game.run(14);
+		assert_eq!(game.ticks, 20);
+		assert_snapshot("pong_tick_20", &game.frame());
+	}
+}
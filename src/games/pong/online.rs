@@ -0,0 +1,628 @@
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, mpsc::{self, Sender}};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::common::net::*;
+
+use super::*;
+
+static PROTOCOL_VERSION: u32 = 2;
+static CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+static HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+static WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+// Ticks between a key being pressed and it taking effect, for both players. Long enough for the
+// joining player's input to reach the host in time on most connections
+static INPUT_DELAY: u32 = 3;
+static LOBBY_POLL_TIME: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug)]
+pub enum Role {
+	// Address to listen on, the host plays on the left and runs the game
+	Host(String),
+	// Address of the host, the joining player plays on the right
+	Join(String)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Hello {
+	version: u32,
+	// Largest board the joining player's screen fits
+	bounds: (u16, u16),
+	name: String
+}
+
+// The board is the smaller of the two players' largest boards
+#[derive(Clone, Debug, PartialEq)]
+struct Welcome {
+	bounds: (u16, u16),
+	points_to_win: u16,
+	name: String
+}
+
+// Everything the joining player needs to draw a frame
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+	tick: u32,
+	ball_pos: (f32, f32),
+	paddles: (u16, u16),
+	scores: (u16, u16),
+	paused: bool,
+	finished: bool
+}
+
+// The host runs the game and sends a snapshot every tick. Input from either player is tagged with
+// the host tick it was pressed on and takes effect INPUT_DELAY ticks later, so both players' moves
+// land alike as long as the joining player's get to the host within the delay. Any later than that
+// and they're applied on the next tick
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+	Hello(Hello),
+	Welcome(Welcome),
+	Input(u32, Action),
+	State(Snapshot),
+	Bye
+}
+
+fn parse_field<T: std::str::FromStr>(field: Option<&str>, line: &str) -> Result<T, NetError> {
+	field.and_then(|field| field.parse().ok()).ok_or_else(|| NetError::Protocol(line.to_owned()))
+}
+
+impl Message {
+	fn encode(&self) -> String {
+		match self {
+			Message::Hello(hello) => format!("HELLO {} {} {} {}", hello.version, hello.bounds.0, hello.bounds.1, hello.name),
+			Message::Welcome(welcome) => format!(
+				"WELCOME {} {} {} {}",
+				welcome.bounds.0, welcome.bounds.1, welcome.points_to_win, welcome.name
+			),
+			Message::Input(tick, action) => format!("INPUT {} {}", tick, action.name()),
+			Message::State(snapshot) => format!(
+				"STATE {} {:.3} {:.3} {} {} {} {} {} {}",
+				snapshot.tick,
+				snapshot.ball_pos.0, snapshot.ball_pos.1,
+				snapshot.paddles.0, snapshot.paddles.1,
+				snapshot.scores.0, snapshot.scores.1,
+				snapshot.paused as u8, snapshot.finished as u8
+			),
+			Message::Bye => "BYE".to_owned()
+		}
+	}
+	fn decode(line: &str) -> Result<Message, NetError> {
+		let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+		// Names go last so they can contain spaces
+		let mut fields = rest.splitn(4, ' ');
+		let message = match kind {
+			"HELLO" => Message::Hello(Hello {
+				version: parse_field(fields.next(), line)?,
+				bounds: (parse_field(fields.next(), line)?, parse_field(fields.next(), line)?),
+				name: fields.next().unwrap_or("").to_owned()
+			}),
+			"WELCOME" => Message::Welcome(Welcome {
+				bounds: (parse_field(fields.next(), line)?, parse_field(fields.next(), line)?),
+				points_to_win: parse_field(fields.next(), line)?,
+				name: fields.next().unwrap_or("").to_owned()
+			}),
+			"INPUT" => {
+				let mut fields = rest.splitn(2, ' ');
+				let tick = parse_field(fields.next(), line)?;
+				let action = fields.next().and_then(Action::from_name).ok_or_else(|| NetError::Protocol(line.to_owned()))?;
+				Message::Input(tick, action)
+			},
+			"STATE" => {
+				let mut fields = rest.split(' ');
+				let mut next = || fields.next();
+				Message::State(Snapshot {
+					tick: parse_field(next(), line)?,
+					ball_pos: (parse_field(next(), line)?, parse_field(next(), line)?),
+					paddles: (parse_field(next(), line)?, parse_field(next(), line)?),
+					scores: (parse_field(next(), line)?, parse_field(next(), line)?),
+					paused: parse_field::<u8>(next(), line)? == 1,
+					finished: parse_field::<u8>(next(), line)? == 1
+				})
+			},
+			"BYE" => Message::Bye,
+			_ => return Err(NetError::Protocol(line.to_owned()))
+		};
+		Ok(message)
+	}
+}
+
+fn send(writer: &mut LineWriter, message: &Message) -> Result<(), NetError> {
+	writer.send(&message.encode())
+}
+
+fn recv(reader: &mut LineReader) -> Result<Message, NetError> {
+	Message::decode(&reader.recv()?)
+}
+
+impl Snapshot {
+	fn of(session: &Session) -> Snapshot {
+		let state = &session.state;
+		Snapshot {
+			tick: session.tick,
+			ball_pos: state.ball.pos,
+			paddles: (state.left_paddle.y, state.right_paddle.y),
+			scores: (state.left_paddle.score, state.right_paddle.score),
+			paused: state.paused,
+			finished: state.finished
+		}
+	}
+	fn apply(&self, session: &mut Session) {
+		session.tick = self.tick;
+		let state = &mut session.state;
+		state.ball.pos = self.ball_pos;
+		(state.left_paddle.y, state.right_paddle.y) = self.paddles;
+		(state.left_paddle.score, state.right_paddle.score) = self.scores;
+		state.paused = self.paused;
+		state.finished = self.finished;
+	}
+}
+
+// Each end's half of the connection, with what it learnt from the handshake
+type HostConnection = (LineReader, LineWriter, Hello, Welcome);
+type JoinConnection = (LineReader, LineWriter, Welcome);
+
+// Largest board that fits this screen
+fn largest_bounds() -> (u16, u16) {
+	let (board, _) = board_layout(BOARD_SIZE);
+	(board.width, board.height)
+}
+
+fn fits_screen(bounds: (u16, u16)) -> bool {
+	let (board, _) = board_layout(bounds);
+	(board.width, board.height) == bounds
+}
+
+// Waits for the joining player's hello and answers with the board they'll play on, which the
+// host's state has to be remade to match
+fn host_handshake(stream: TcpStream, state: &GameState, name: &str) -> Result<HostConnection, NetError> {
+	let (mut reader, mut writer) = split_lines(stream)?;
+	reader.set_timeout(Some(HANDSHAKE_TIMEOUT))?;
+	writer.set_timeout(Some(WRITE_TIMEOUT))?;
+	let hello = match recv(&mut reader)? {
+		Message::Hello(hello) if hello.version == PROTOCOL_VERSION => hello,
+		message => return Err(NetError::Protocol(message.encode()))
+	};
+	let welcome = Welcome {
+		bounds: (std::cmp::min(state.bounds.0, hello.bounds.0), std::cmp::min(state.bounds.1, hello.bounds.1)),
+		points_to_win: state.points_to_win,
+		name: name.to_owned()
+	};
+	send(&mut writer, &Message::Welcome(welcome.clone()))?;
+	reader.set_timeout(None)?;
+	Ok((reader, writer, hello, welcome))
+}
+
+fn join_handshake(stream: TcpStream, name: &str, bounds: (u16, u16)) -> Result<JoinConnection, NetError> {
+	let (mut reader, mut writer) = split_lines(stream)?;
+	reader.set_timeout(Some(HANDSHAKE_TIMEOUT))?;
+	writer.set_timeout(Some(WRITE_TIMEOUT))?;
+	send(&mut writer, &Message::Hello(Hello {
+		version: PROTOCOL_VERSION,
+		bounds,
+		name: name.to_owned()
+	}))?;
+	let welcome = match recv(&mut reader)? {
+		Message::Welcome(welcome) => welcome,
+		message => return Err(NetError::Protocol(message.encode()))
+	};
+	reader.set_timeout(None)?;
+	Ok((reader, writer, welcome))
+}
+
+struct Session {
+	state: GameState,
+	started: bool,
+	cancelled: bool,
+	// Host ticks so far, the joining player's copy is from the last snapshot
+	tick: u32,
+	// Input waiting for its tick, with the tick it's due on
+	scheduled: Vec<(u32, Side, Action)>
+}
+
+impl Session {
+	fn new(state: GameState) -> Session {
+		Session {
+			state,
+			started: false,
+			cancelled: false,
+			tick: 0,
+			scheduled: vec![]
+		}
+	}
+	// The pressed tick comes from the other player, so it's kept within the delay of now. Late input
+	// goes in on the next tick, and input claiming to be from the future can't pile up or overflow
+	fn schedule(&mut self, side: Side, pressed: u32, action: Action) {
+		let due = pressed.saturating_add(INPUT_DELAY).clamp(self.tick, self.tick.saturating_add(INPUT_DELAY));
+		self.scheduled.push((due, side, action));
+	}
+	// Applies the input that's due, in the order it arrived, then steps the game
+	fn advance(&mut self) {
+		let tick = self.tick;
+		let (due, later) = std::mem::take(&mut self.scheduled).into_iter().partition::<Vec<_>, _>(|(due, _, _)| *due <= tick);
+		self.scheduled = later;
+		for (_, side, action) in due {
+			self.state.apply_to(side, action);
+		}
+		self.state.tick();
+		self.tick += 1;
+	}
+	// Leaving without a finished game means the other player went away
+	fn end_connection(&mut self) {
+		if !self.state.finished {
+			self.state.disconnected = true;
+		}
+	}
+}
+
+fn spawn_host_reader(mut reader: LineReader, shared: Arc<Mutex<Session>>) -> JoinHandle<()> {
+	thread::spawn(move || loop {
+		match recv(&mut reader) {
+			Ok(Message::Input(tick, action)) => shared.lock().unwrap().schedule(Side::Right, tick, action),
+			Ok(Message::Bye) | Err(_) => break shared.lock().unwrap().end_connection(),
+			Ok(_) => {}
+		}
+	})
+}
+
+fn spawn_join_reader(mut reader: LineReader, shared: Arc<Mutex<Session>>) -> JoinHandle<()> {
+	thread::spawn(move || loop {
+		match recv(&mut reader) {
+			Ok(Message::State(snapshot)) => snapshot.apply(&mut shared.lock().unwrap()),
+			Ok(Message::Bye) | Err(_) => break shared.lock().unwrap().end_connection(),
+			Ok(_) => {}
+		}
+	})
+}
+
+// Sends on its own thread so a slow connection never holds up the game while it has the session
+// locked. Stops after sending Bye, or when a write fails or times out
+fn spawn_writer(mut writer: LineWriter, shared: Arc<Mutex<Session>>) -> (Sender<Message>, JoinHandle<()>) {
+	let (sender, receiver) = mpsc::channel();
+	let handle = thread::spawn(move || {
+		for message in receiver {
+			if send(&mut writer, &message).is_err() {
+				shared.lock().unwrap().end_connection();
+				break;
+			}
+			if message == Message::Bye {break};
+		}
+		writer.close();
+	});
+	(sender, handle)
+}
+
+// Escape leaves the lobby, after that keys go to the game until it finishes
+fn spawn_session_input<F>(shared: Arc<Mutex<Session>>, mut on_action: F) -> JoinHandle<()> where F: FnMut(&mut Session, Action) + Send + 'static {
+	let bindings = default_bindings().load_or_default();
+	spawn_input_handler(move |key| {
+		let mut session = shared.lock().unwrap();
+		if session.state.finished || session.cancelled {return false;}
+		if !session.started {
+			session.cancelled = key == InputKey::Escape;
+			return !session.cancelled;
+		}
+		if let Some(action) = bindings.action_for(key) {
+			on_action(&mut session, action);
+		}
+		true
+	})
+}
+
+fn draw_lobby(lines: &[String]) {
+	clear_screen();
+	let width = lines.iter().map(|line| str_width(line)).max().unwrap_or(0) + 4;
+	let box_print = BoxPrint::new((width, lines.len() as u16 + 2));
+	box_print.print();
+	let inner = box_print.inner();
+	for (i, line) in lines.iter().enumerate() {
+		TextPrint::in_rect(line, inner.row(i as u16), Align::Center)
+			.set_max_width(inner.width)
+			.print();
+	}
+	cursor_to_end();
+}
+
+fn no_game() -> GameReturn {
+	GameReturn::new(Score::None, WinState::None)
+}
+
+// Shown until a key is pressed, which also ends the session's input handler
+fn show_error(shared: &Arc<Mutex<Session>>, input_handler: JoinHandle<()>, message: String) -> GameReturn {
+	shared.lock().unwrap().cancelled = true;
+	draw_lobby(&[message, "Press any key to continue".to_owned()]);
+	let _ = input_handler.join();
+	no_game()
+}
+
+// Before the game starts, so there's no session input handler to stop
+fn show_join_error(message: String) -> GameReturn {
+	draw_lobby(&[message, "Press any key to continue".to_owned()]);
+	read_key();
+	no_game()
+}
+
+pub struct OnlinePong {
+	role: Role,
+	setup: GameSetup
+}
+
+impl OnlinePong {
+	pub fn new(role: Role, setup: GameSetup) -> OnlinePong {
+		OnlinePong {
+			role,
+			setup
+		}
+	}
+	fn run_host(&self, addr: &str) -> GameReturn {
+		let mut state = get_initial_state(&self.setup);
+		state.right_control = Control::Remote;
+		let shared = Arc::new(Mutex::new(Session::new(state)));
+		let input_handler = spawn_session_input(shared.clone(), |session, action| {
+			let tick = session.tick;
+			session.schedule(Side::Left, tick, action);
+		});
+		let listener = match TcpListener::bind(addr).and_then(|listener| listener.set_nonblocking(true).map(|_| listener)) {
+			Ok(listener) => listener,
+			Err(err) => return show_error(&shared, input_handler, format!("Couldn't host on {}, {}", addr, err))
+		};
+		let local_addr = listener.local_addr().map_or(addr.to_owned(), |local_addr| local_addr.to_string());
+
+		let mut accepted: Option<Result<TcpStream, NetError>> = None;
+		run_game_loop(&shared, |session| {
+			if session.cancelled {return Tick::Stop};
+			draw_lobby(&[
+				format!("Hosting Pong on {}", local_addr),
+				"Waiting for a player to join".to_owned(),
+				"Press escape to cancel".to_owned()
+			]);
+			match listener.accept() {
+				Err(err) if err.kind() == ErrorKind::WouldBlock => Tick::Continue(LOBBY_POLL_TIME),
+				result => {
+					accepted = Some(result.map(|(stream, _)| stream).map_err(NetError::from));
+					Tick::Stop
+				}
+			}
+		});
+		let stream = match accepted {
+			Some(Ok(stream)) => stream,
+			Some(Err(err)) => return show_error(&shared, input_handler, format!("Couldn't accept a player, {}", err)),
+			None => {
+				let _ = input_handler.join();
+				return no_game();
+			}
+		};
+		let handshake = stream.set_nonblocking(false).map_err(NetError::from).and_then(|_| {
+			host_handshake(stream, &shared.lock().unwrap().state, &self.setup.config.player_name)
+		});
+		let (reader, writer, hello, welcome) = match handshake {
+			Ok(connection) => connection,
+			Err(err) => return show_error(&shared, input_handler, format!("Couldn't start the game, {}", err))
+		};
+
+		{
+			let mut session = shared.lock().unwrap();
+			let (board, status_rect) = board_layout(welcome.bounds);
+			let config = &self.setup.config;
+			session.state = GameState::new(board, status_rect, config.pong.points_to_win, config.difficulty, self.setup.seed);
+			session.state.right_control = Control::Remote;
+			session.state.opponent = hello.name;
+			session.started = true;
+		}
+		let reader_thread = spawn_host_reader(reader, shared.clone());
+		let (sender, writer_thread) = spawn_writer(writer, shared.clone());
+		run_game_loop(&shared, |session| {
+			if session.state.disconnected {
+				session.state.finished = true;
+			}
+			session.advance();
+			if sender.send(Message::State(Snapshot::of(session))).is_err() {
+				session.end_connection();
+				session.state.finished = true;
+			}
+			write_screen(&session.state);
+			if session.state.finished {Tick::Stop} else {Tick::Continue(FRAME_TIME)}
+		});
+		let _ = sender.send(Message::Bye);
+		let _ = writer_thread.join();
+		let _ = reader_thread.join();
+		let game_return = get_game_return(&shared.lock().unwrap().state);
+		let _ = input_handler.join();
+		game_return
+	}
+	fn run_join(&self, addr: &str) -> GameReturn {
+		draw_lobby(&[format!("Joining Pong on {}", addr)]);
+		let connection = connect(addr, CONNECT_TIMEOUT).and_then(|stream| {
+			join_handshake(stream, &self.setup.config.player_name, largest_bounds())
+		});
+		let (reader, writer, welcome) = match connection {
+			Ok((_, writer, welcome)) if !fits_screen(welcome.bounds) => {
+				writer.close();
+				return show_join_error(format!("The host's {}x{} board doesn't fit this terminal", welcome.bounds.0, welcome.bounds.1));
+			},
+			Ok(connection) => connection,
+			Err(err) => return show_join_error(format!("Couldn't join {}, {}", addr, err))
+		};
+
+		let (board, status_rect) = board_layout(welcome.bounds);
+		let mut state = GameState::new(board, status_rect, welcome.points_to_win, Difficulty::Normal, 0);
+		state.bounds = welcome.bounds;
+		state.local_side = Side::Right;
+		state.opponent = welcome.name;
+		state.right_control = Control::Remote;
+		let mut session = Session::new(state);
+		session.started = true;
+		let shared = Arc::new(Mutex::new(session));
+		let (sender, writer_thread) = spawn_writer(writer, shared.clone());
+		let input_sender = sender.clone();
+		let input_handler = spawn_session_input(shared.clone(), move |session, action| {
+			let _ = input_sender.send(Message::Input(session.tick, action));
+		});
+		let reader_thread = spawn_join_reader(reader, shared.clone());
+		run_game_loop(&shared, |session| {
+			if session.state.disconnected {
+				session.state.finished = true;
+			}
+			write_screen(&session.state);
+			if session.state.finished {Tick::Stop} else {Tick::Continue(FRAME_TIME)}
+		});
+		let _ = sender.send(Message::Bye);
+		let _ = writer_thread.join();
+		let _ = reader_thread.join();
+		let game_return = get_game_return(&shared.lock().unwrap().state);
+		let _ = input_handler.join();
+		game_return
+	}
+}
+
+impl Game for OnlinePong {
+	fn run(&self) -> GameReturn {
+		match &self.role {
+			Role::Host(addr) => self.run_host(addr),
+			Role::Join(addr) => self.run_join(addr)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+
+	use super::*;
+
+	fn new_state() -> GameState {
+		GameState::new(Rect::new((2, 2), (20, 10)), Rect::new((1, 14), (22, 1)), 5, Difficulty::Normal, 1)
+	}
+
+	// Polls for up to a second, for changes made by another thread
+	fn wait_for<F: Fn() -> bool>(condition: F) -> bool {
+		for _ in 0..100 {
+			if condition() {return true};
+			thread::sleep(Duration::from_millis(10));
+		}
+		false
+	}
+
+	// Host and joining ends of a localhost connection, after the handshake
+	fn connect_pair() -> (HostConnection, JoinConnection) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		let joining = thread::spawn(move || {
+			join_handshake(connect(&addr, CONNECT_TIMEOUT).unwrap(), "Ada Lovelace", (30, 8)).unwrap()
+		});
+		let (stream, _) = listener.accept().unwrap();
+		let host = host_handshake(stream, &new_state(), "Grace").unwrap();
+		(host, joining.join().unwrap())
+	}
+
+	#[test]
+	fn messages_survive_encoding() {
+		let messages = [
+			Message::Hello(Hello { version: PROTOCOL_VERSION, bounds: (48, 12), name: "Ada Lovelace".to_owned() }),
+			Message::Welcome(Welcome { bounds: (50, 15), points_to_win: 10, name: "Grace".to_owned() }),
+			Message::Input(41, Action::MoveDown),
+			Message::State(Snapshot { tick: 42, ball_pos: (12.5, 3.25), paddles: (4, 9), scores: (2, 7), paused: true, finished: false }),
+			Message::Bye
+		];
+		for message in messages {
+			assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+		}
+	}
+
+	#[test]
+	fn rejects_unknown_messages() {
+		assert!(Message::decode("JUMP 3").is_err());
+		assert!(Message::decode("INPUT 3 fly").is_err());
+		assert!(Message::decode("INPUT up").is_err());
+		assert!(Message::decode("STATE 1 2 3").is_err());
+	}
+
+	#[test]
+	fn handshake_on_localhost() {
+		let ((_, _, hello, host_welcome), (_, _, welcome)) = connect_pair();
+		assert_eq!(hello, Hello { version: PROTOCOL_VERSION, bounds: (30, 8), name: "Ada Lovelace".to_owned() });
+		// The host's board is wider and the joining player's screen shorter
+		assert_eq!(welcome, Welcome { bounds: (20, 8), points_to_win: 5, name: "Grace".to_owned() });
+		assert_eq!(host_welcome, welcome);
+	}
+
+	#[test]
+	fn host_schedules_input_and_notices_disconnects() {
+		let ((host_reader, _host_writer, _, _), (_, mut join_writer, _)) = connect_pair();
+		let shared = Arc::new(Mutex::new(Session::new(new_state())));
+		let reader_thread = spawn_host_reader(host_reader, shared.clone());
+		send(&mut join_writer, &Message::Input(0, Action::MoveUp)).unwrap();
+		assert!(wait_for(|| shared.lock().unwrap().scheduled == vec![(INPUT_DELAY, Side::Right, Action::MoveUp)]));
+		join_writer.close();
+		reader_thread.join().unwrap();
+		let state = &shared.lock().unwrap().state;
+		assert!(state.disconnected);
+		assert!(matches!(get_game_return(state).win_state, WinState::Disconnected));
+	}
+
+	#[test]
+	fn input_waits_for_its_tick() {
+		let mut session = Session::new(new_state());
+		session.schedule(Side::Left, 0, Action::MoveUp);
+		for _ in 0..INPUT_DELAY {
+			session.advance();
+			assert_eq!(session.state.left_paddle.direction, VerticalDirection::Down);
+		}
+		session.advance();
+		assert_eq!(session.state.left_paddle.direction, VerticalDirection::Up);
+		// Too late for its tick, so it goes in on the next one
+		session.schedule(Side::Right, 0, Action::MoveUp);
+		session.advance();
+		assert_eq!(session.state.right_paddle.direction, VerticalDirection::Up);
+		assert!(session.scheduled.is_empty());
+		assert_eq!(session.tick, INPUT_DELAY + 2);
+	}
+
+	#[test]
+	fn handshake_refuses_an_endless_hello() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		let joining = thread::spawn(move || {
+			let mut stream = connect(&addr, CONNECT_TIMEOUT).unwrap();
+			let _ = stream.write_all("HELLO\t".repeat(2000).as_bytes());
+			stream
+		});
+		let (stream, _) = listener.accept().unwrap();
+		assert!(matches!(host_handshake(stream, &new_state(), "Grace"), Err(NetError::TooLong)));
+		drop(joining.join());
+	}
+
+	#[test]
+	fn future_input_waits_no_longer_than_the_delay() {
+		let mut session = Session::new(new_state());
+		session.schedule(Side::Left, u32::MAX, Action::MoveUp);
+		assert_eq!(session.scheduled[0].0, INPUT_DELAY);
+		for _ in 0..=INPUT_DELAY {
+			session.advance();
+		}
+		assert_eq!(session.state.left_paddle.direction, VerticalDirection::Up);
+		assert!(session.scheduled.is_empty());
+	}
+
+	#[test]
+	fn joining_player_follows_host_state() {
+		let ((_, mut host_writer, _, _), (join_reader, _join_writer, welcome)) = connect_pair();
+		let mut state = new_state();
+		state.local_side = Side::Right;
+		state.opponent = welcome.name;
+		let shared = Arc::new(Mutex::new(Session::new(state)));
+		let reader_thread = spawn_join_reader(join_reader, shared.clone());
+		let snapshot = Snapshot { tick: 30, ball_pos: (3.0, 4.0), paddles: (2, 6), scores: (5, 3), paused: false, finished: true };
+		send(&mut host_writer, &Message::State(snapshot.clone())).unwrap();
+		send(&mut host_writer, &Message::Bye).unwrap();
+		reader_thread.join().unwrap();
+		let session = shared.lock().unwrap();
+		assert_eq!(Snapshot::of(&session), snapshot);
+		assert_eq!(session.tick, 30);
+		let state = &session.state;
+		assert!(!state.disconnected);
+		assert_eq!(get_win_text(state), "Grace wins");
+	}
+}
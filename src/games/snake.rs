@@ -1,10 +1,19 @@
-use std::{sync::{Arc, Mutex}, thread::{self, sleep}, time::Duration};
+use std::{collections::HashSet, sync::{Arc, Mutex}, time::Duration};
 use rand::prelude::*;
 use crate::common::{
+	achievements::*,
+	config::*,
 	game::*,
-	screen::{boxes::BoxPrint, util::*}
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::current_theme, toast::draw_toast, util::*}
 };
 
+// Smallest board in each direction, so there's always somewhere to put the first food
+static MIN_BOARD_SIZE: u16 = 2;
+
 #[derive(PartialEq)]
 enum Direction {
 	Left,
@@ -19,59 +28,79 @@ struct GameState {
 	food_locations: Vec<(u16, u16)>,
 	bounds: (u16, u16),
 	offset: (u16, u16),
+	status_rect: Rect,
 	finished: bool,
+	paused: bool,
+	speed: u16,
 	score: u32,
-	inputs_to_handle: Vec<char>
+	inputs_to_handle: Vec<Action>,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("snake", &[
+		(Action::MoveUp, &["w", "up"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+fn board_rect(state: &GameState) -> Rect {
+	Rect::new(state.offset, state.bounds)
 }
 
 fn draw_snakes(state: &GameState) {
-	let body_char: &str = "█";
-	for n in 0..state.bodies.len() {
-		let (x, y) = state.bodies[n];
-		print_at(body_char, (
+	let theme = current_theme();
+	for (x, y) in &state.bodies {
+		print_clipped(theme.glyphs.block, (
 			state.offset.0 + x - 1,
 			state.offset.1 + y - 1
-		));
+		), Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.snake_body));
 	}
 }
 
 fn draw_food(state: &GameState) {
-	let body_char: &str = "█";
-	for n in 0..state.food_locations.len() {
-		let (x, y) = state.food_locations[n];
-		print_at_with_cols(
-			body_char, (
+	let theme = current_theme();
+	for (x, y) in &state.food_locations {
+		print_clipped(
+			theme.glyphs.food, (
 				state.offset.0 + x - 1,
 				state.offset.1 + y - 1
 			),
-			None,
-			Some(&termion::color::Red)
+			Some(board_rect(state)),
+			Some(&theme.fill),
+			Some(&theme.entities.snake_food)
 		);
-	}	
+	}
 }
 
 fn write_screen(game_state: &GameState) {
 	clear_screen();
-	BoxPrint::new((game_state.bounds.0 + 2, game_state.bounds.1 + 2))
-		.at_coords((game_state.offset.0 - 1, game_state.offset.1 - 1))
+	BoxPrint::in_rect(board_rect(game_state).outer(Padding::uniform(1)))
 		.print();
 	draw_food(game_state);
 	draw_snakes(game_state);
-	write_game_text(&game_state);
+	write_game_text(game_state);
+	if game_state.finished {
+		print_splash("Game over", &format!("Score: {}", game_state.score));
+	}
+	draw_toast();
 	cursor_to_end();
 }
 
 fn write_game_text(game_state: &GameState) {
-	let text: String;
-	if game_state.finished {
-		text = format!("Game over, score: {}, press any key to continue", game_state.score);
+	let text = if game_state.finished {
+		format!("Game over, score: {}, press any key to continue", game_state.score)
+	} else if game_state.paused {
+		format!("Paused, score: {}", game_state.score)
 	} else {
-		text = format!("Score: {}", game_state.score);
-	}
-	print_at(&text, (
-		game_state.offset.0 + game_state.bounds.0 / 2 - (text.len() / 2) as u16,
-		game_state.offset.1 + game_state.bounds.1 + 1
-	));
+		format!("Score: {}", game_state.score)
+	};
+	TextPrint::in_rect(&text, game_state.status_rect, Align::Center)
+		.set_max_width(game_state.status_rect.width)
+		.print();
 }
 
 fn update_state(game_state: &mut GameState) {
@@ -91,40 +120,36 @@ fn update_state(game_state: &mut GameState) {
 }
 
 fn check_on_food(state: &mut GameState) {
-	let (x, y) = state.bodies.last().unwrap();
-	let mut add = false;
-	for i in 0..state.food_locations.len() {
-		if *x == state.food_locations[i].0 && *y == state.food_locations[i].1 {
-			add = true
-		}
-	}
+	let head = state.bodies.last().unwrap();
+	let add = state.food_locations.contains(head);
 	if add {
 		let last = state.bodies[0];
-		state.bodies.reverse();
-		state.bodies.append(&mut vec![last.clone()]);
-		state.bodies.reverse();
-		state.food_locations = vec![generate_new_food(state.bounds, &state.bodies)];
+		state.bodies.insert(0, last);
 		state.score += 1;
+		report_event("snake", "length", state.bodies.len() as u32);
+		// The snake has filled the board
+		let Some(food) = generate_new_food(state.bounds, &state.bodies, &mut state.rng) else {
+			state.food_locations.clear();
+			state.finished = true;
+			return;
+		};
+		state.food_locations = vec![food];
 	}
 }
 
-fn generate_new_food(bounds: (u16, u16), ignore_locations: &Vec<(u16, u16)>) -> (u16, u16) {
+// None when every cell is taken
+fn generate_new_food(bounds: (u16, u16), ignore_locations: &[(u16, u16)], rng: &mut StdRng) -> Option<(u16, u16)> {
 	let (w, h) = bounds;
-	let mut rng = rand::thread_rng();
+	let taken = ignore_locations.iter().filter(|(x, y)| (1..=w).contains(x) && (1..=h).contains(y)).collect::<HashSet<_>>();
+	if taken.len() >= w as usize * h as usize {return None};
 	let mut x: u16;
 	let mut y: u16;
 	loop {
 		x = rng.gen_range(1..=w);
 		y = rng.gen_range(1..=h);
-		let mut found = false;
-		for i in 0..ignore_locations.len() {
-			if ignore_locations[i].0 == x && ignore_locations[i].1 == y {
-				found = true
-			}
-		}
-		if !found {break;}
+		if !ignore_locations.contains(&(x, y)) {break;}
 	}
-	(x, y)
+	Some((x, y))
 }
 
 fn is_over(state: &GameState) -> bool {
@@ -133,92 +158,281 @@ fn is_over(state: &GameState) -> bool {
 	if same_as_head_vec.len() > 1 {return true};
 	if head.0 > state.bounds.0 || head.0 == 0 {return true};
 	if head.1 > state.bounds.1 || head.1 == 0 {return true};
-	return false
+	false
 }
 
-fn get_initial_state() -> GameState {
-	let (w, h) = termion::terminal_size().unwrap();
-	let bounds = (std::cmp::min(w-3, 30), std::cmp::min(h-5, 10));
-	let bodies = vec![(1, 1)];
-	GameState {
-		head_direction: Direction::Right,
-		bodies,
-		bounds: bounds,
-		offset: get_centered_coords(bounds),
-		food_locations: vec![generate_new_food(bounds, &vec![(1,1)])],
-		finished: false,
-		score: 0,
-		inputs_to_handle: vec![]
+impl GameState {
+	// The board is the area inside the border, food is placed using the seed
+	fn new(board: Rect, status_rect: Rect, speed: u16, seed: u64) -> GameState {
+		let bounds = (board.width.max(MIN_BOARD_SIZE), board.height.max(MIN_BOARD_SIZE));
+		let mut rng = StdRng::seed_from_u64(seed);
+		let bodies = vec![(1, 1)];
+		GameState {
+			head_direction: Direction::Right,
+			food_locations: generate_new_food(bounds, &bodies, &mut rng).into_iter().collect(),
+			bodies,
+			bounds,
+			offset: board.coords(),
+			status_rect,
+			finished: false,
+			paused: false,
+			speed,
+			score: 0,
+			inputs_to_handle: vec![],
+			rng
+		}
+	}
+	fn frame_delay(&self) -> Duration {
+		let mut sleep_ms = self.speed as f64;
+		// Rows are taller than columns are wide, so vertical moves are slowed to look even
+		if !self.paused && (self.head_direction == Direction::Up || self.head_direction == Direction::Down) {sleep_ms *= 1.75};
+		Duration::from_millis(sleep_ms.floor() as u64)
 	}
 }
 
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		if action == Action::Pause {
+			self.paused = !self.paused;
+		} else if !self.paused {
+			self.inputs_to_handle.push(action);
+		}
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished {return};
+		handle_input(self);
+		check_on_food(self);
+		if self.finished {return};
+		update_state(self);
+		if is_over(self) {
+			self.finished = true;
+		}
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let config = &setup.config;
+	// Board with its border, plus a status line underneath
+	let size = (config.snake.width.max(MIN_BOARD_SIZE), config.snake.height.max(MIN_BOARD_SIZE));
+	let frame = Rect::screen().centered((size.0.saturating_add(2), size.1.saturating_add(3)));
+	let [board_rect, status_rect] = Layout::vertical(&[Constraint::Min(3), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	GameState::new(board_rect.inner(Padding::uniform(1)), status_rect, config.snake.speed, setup.seed)
+}
+
 fn handle_input(state: &mut GameState) {
-	if state.inputs_to_handle.len() == 0 {return};
-	match state.inputs_to_handle[0].to_ascii_lowercase() {
-		'w' => {if state.head_direction != Direction::Down {state.head_direction = Direction::Up;}},
-		'a' => {if state.head_direction != Direction::Right {state.head_direction = Direction::Left;}},
-		's' => {if state.head_direction != Direction::Up {state.head_direction = Direction::Down;}},
-		'd' => {if state.head_direction != Direction::Left {state.head_direction = Direction::Right}},
+	if state.inputs_to_handle.is_empty() {return};
+	match state.inputs_to_handle[0] {
+		Action::MoveUp if state.head_direction != Direction::Down => state.head_direction = Direction::Up,
+		Action::MoveLeft if state.head_direction != Direction::Right => state.head_direction = Direction::Left,
+		Action::MoveDown if state.head_direction != Direction::Up => state.head_direction = Direction::Down,
+		Action::MoveRight if state.head_direction != Direction::Left => state.head_direction = Direction::Right,
 		_ => {}
 	};
 	state.inputs_to_handle.remove(0);
 }
 
-fn add_input_to_handle(locked_state: Arc<Mutex<GameState>>, input: char) -> Arc<Mutex<GameState>> {
-	{
-		let mut state = locked_state.lock().unwrap();
-		state.inputs_to_handle.push(input);
-	}
-	locked_state	
+pub struct Snake {
+	setup: GameSetup
 }
 
-pub struct Snake {}
-impl<'a> Game<'a> for Snake {
+impl Game for Snake {
 	fn run(&self) -> GameReturn {
-		let locked_state = Arc::from(Mutex::from(get_initial_state()));
-		let mut state_clone = locked_state.clone();
-		let mut game_return = GameReturn::default();
-		let input_handler = thread::spawn(move || {
-			let term = console::Term::stdout();
-			loop {
-				let char = term.read_char().unwrap();
-				if state_clone.lock().unwrap().finished {break;}
-				state_clone = add_input_to_handle(state_clone, char);
+		let locked_state = Arc::from(Mutex::from(get_initial_state(&self.setup)));
+		let state_clone = locked_state.clone();
+		let bindings = default_bindings().load_or_default();
+		let input_handler = spawn_input_handler(move |key| {
+			if state_clone.lock().unwrap().finished {return false;}
+			if let Some(action) = bindings.action_for(key) {
+				state_clone.lock().unwrap().apply(action);
 			}
+			true
 		});
 
-		loop {
-			let mut sleep_ms: f64 = 50.0;
-			{
-				let mut state = locked_state.lock().unwrap();
-				let state_ref = &mut state;
-				handle_input(state_ref);
-				check_on_food(state_ref);
-				update_state(state_ref);
-				write_screen(state_ref);
-				let over = is_over(&state);
-				if over {
-					state.finished = true;
-					break;
-				}
-				if state.head_direction == Direction::Up || state.head_direction == Direction::Down {sleep_ms = sleep_ms * 1.75};
+		run_game_loop(&locked_state, |state| {
+			state.tick();
+			write_screen(state);
+			if state.finished {Tick::Stop} else {Tick::Continue(state.frame_delay())}
+		});
+		let game_return = {
+			let state = locked_state.lock().unwrap();
+			GameReturn {
+				score: Score::SinglePlayer(state.score as f32),
+				win_state: if state.food_locations.is_empty() {WinState::Win} else {WinState::Lose},
+				records: vec![("Longest snake", state.bodies.len() as u32)],
+				times: vec![]
 			}
-			sleep(Duration::from_millis(sleep_ms.floor() as u64));
-		}
-		{
-			let mut state = locked_state.lock().unwrap();
-			let state_ref = &mut state;
-			game_return = GameReturn {
-				score: Score::SinglePlayer(state_ref.score as f32),
-				win_state: WinState::Lose
-			};
-			write_screen(state_ref);
-		}
+		};
 		let _ = input_handler.join();
 		game_return
 	}
 }
 
-pub fn get_game_instance<'a>() -> GameInstance<'a> {
-	(Box::new(Snake {}), "snake")
-}
\ No newline at end of file
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "snake",
+		name: "Snake",
+		description: "Eat the food and grow without hitting the walls or yourself",
+		min_players: 1,
+		max_players: 1,
+		options: &[GameOption::SnakeBoardSize, GameOption::SnakeSpeed],
+		version: "1.1.0",
+		create: |setup| Box::new(Snake { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "length_10",
+				name: "Growing",
+				description: "Grow to a length of 10",
+				event: "length",
+				goal: 10
+			},
+			Achievement {
+				id: "length_50",
+				name: "Long snake",
+				description: "Grow to a length of 50",
+				event: "length",
+				goal: 50
+			}
+		],
+		daily: Some(|config, day| {
+			config.snake = SnakeConfig {
+				speed: [80, 50, 30][day as usize % 3],
+				..SnakeConfig::default()
+			};
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::*, snapshot::assert_snapshot};
+
+	fn new_state(bounds: (u16, u16), seed: u64) -> GameState {
+		let (board, status_rect) = board_rects(bounds);
+		GameState::new(board, status_rect, 50, seed)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		let size = (state.bounds.0 + 2, state.bounds.1 + 4);
+		Harness::new(state, size)
+	}
+
+	#[test]
+	fn dies_when_hitting_the_wall() {
+		let mut state = new_state((5, 3), 1);
+		state.food_locations = vec![(1, 3)];
+		let mut game = harness(state);
+		assert!(game.run_until_finished(20));
+		assert_eq!(game.ticks, 5);
+		assert_eq!(game.state.bodies.last(), Some(&(6, 1)));
+	}
+
+	#[test]
+	fn dies_when_hitting_itself() {
+		let mut state = new_state((6, 6), 1);
+		state.bodies = vec![(1, 2), (2, 2), (3, 2), (3, 3), (2, 3)];
+		state.head_direction = Direction::Left;
+		state.food_locations = vec![(6, 6)];
+		let mut game = harness(state);
+		game.press(Action::MoveUp).step();
+		assert!(game.state.finished);
+		assert_eq!(game.state.bodies.last(), Some(&(2, 2)));
+	}
+
+	#[test]
+	fn ignores_turning_back_on_itself() {
+		let mut state = new_state((6, 6), 1);
+		state.bodies = vec![(2, 3), (3, 3)];
+		state.food_locations = vec![(6, 6)];
+		let mut game = harness(state);
+		game.run_script(&[(0, Action::MoveLeft)]);
+		assert!(!game.state.finished);
+		assert_eq!(game.state.bodies, vec![(3, 3), (4, 3)]);
+	}
+
+	#[test]
+	fn grows_after_eating_food() {
+		let mut state = new_state((10, 5), 7);
+		state.food_locations = vec![(2, 1)];
+		let mut game = harness(state);
+		game.run(2);
+		assert_eq!(game.state.score, 1);
+		assert_eq!(game.state.bodies, vec![(2, 1), (3, 1)]);
+		let food = game.state.food_locations[0];
+		assert!(!game.state.bodies.contains(&food));
+		assert!(food.0 >= 1 && food.0 <= 10 && food.1 >= 1 && food.1 <= 5);
+	}
+
+	#[test]
+	fn ends_when_the_board_is_full() {
+		let mut state = new_state((2, 2), 1);
+		state.bodies = vec![(1, 2), (1, 1), (2, 1), (2, 2)];
+		state.head_direction = Direction::Down;
+		state.food_locations = vec![(2, 2)];
+		let mut game = harness(state);
+		game.step();
+		assert!(game.state.finished);
+		assert_eq!(game.state.score, 1);
+		assert!(game.state.food_locations.is_empty());
+		assert_eq!(game.state.bodies.last(), Some(&(2, 2)));
+	}
+
+	#[test]
+	fn board_is_at_least_two_by_two() {
+		let state = new_state((1, 1), 1);
+		assert_eq!(state.bounds, (2, 2));
+		assert_eq!(state.food_locations.len(), 1);
+	}
+
+	#[test]
+	fn food_is_placed_from_the_seed() {
+		assert_eq!(new_state((30, 10), 42).food_locations, new_state((30, 10), 42).food_locations);
+	}
+
+	#[test]
+	fn paused_snake_doesnt_move() {
+		let mut game = harness(new_state((20, 5), 1));
+		game.press(Action::Pause).run(3);
+		assert_eq!(game.state.bodies, vec![(1, 1)]);
+		assert!(game.frame().contains("Paused, score: 0"));
+	}
+
+	#[test]
+	fn frame_shows_snake_and_score() {
+		let mut state = new_state((10, 5), 1);
+		state.food_locations = vec![(5, 5)];
+		let mut game = harness(state);
+		game.run(2);
+		let frame = game.frame();
+		let glyphs = current_theme().glyphs;
+		// Board cell (x, y) is drawn at (x + 1, y + 1)
+		assert_eq!(frame.get((4, 2)).unwrap().symbol, glyphs.block);
+		assert_eq!(frame.get((6, 6)).unwrap().symbol, glyphs.food);
+		assert_eq!(frame.find("Score: 0"), Some((3, 8)));
+	}
+
+	#[test]
+	fn snapshot_after_ten_ticks() {
+		let mut game = harness(new_state((16, 6), 3));
+		game.run_script(&[(4, Action::MoveDown), (7, Action::MoveRight)]);
+		game.run(2);
+		assert_eq!(game.ticks, 10);
+		assert_snapshot("snake_tick_10", &game.frame());
+	}
+
+	#[test]
+	fn snapshot_game_over() {
+		let mut game = harness(new_state((16, 6), 3));
+		assert!(game.run_until_finished(50));
+		assert_snapshot("snake_game_over", &game.frame());
+	}
+}
@@ -0,0 +1,625 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use crate::common::{
+	achievements::*,
+	game::*,
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::*, toast::draw_toast, util::*}
+};
+
+static FRAME_TIME: Duration = Duration::from_millis(20);
+static WIDTH: usize = 10;
+// Pieces start in the hidden rows above the visible twenty
+static HIDDEN: usize = 2;
+static HEIGHT: usize = 22;
+// How long a piece can sit on something before it locks, and how often moving it can restart that
+static LOCK_TICKS: u32 = 25;
+static MAX_LOCK_RESETS: u32 = 15;
+static LINES_PER_LEVEL: u32 = 10;
+static NEXT_SHOWN: usize = 3;
+// Cells are two columns wide so they come out roughly square
+static CELL_WIDTH: u16 = 2;
+static PANEL_WIDTH: u16 = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+	I,
+	J,
+	L,
+	O,
+	S,
+	T,
+	Z
+}
+
+impl Kind {
+	fn all() -> [Kind; 7] {
+		[Kind::I, Kind::J, Kind::L, Kind::O, Kind::S, Kind::T, Kind::Z]
+	}
+	// The spawn orientation as (x, y) from the top left of the piece's bounding box
+	fn cells(&self) -> [(i8, i8); 4] {
+		match self {
+			Kind::I => [(0, 1), (1, 1), (2, 1), (3, 1)],
+			Kind::J => [(0, 0), (0, 1), (1, 1), (2, 1)],
+			Kind::L => [(2, 0), (0, 1), (1, 1), (2, 1)],
+			Kind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+			Kind::S => [(1, 0), (2, 0), (0, 1), (1, 1)],
+			Kind::T => [(1, 0), (0, 1), (1, 1), (2, 1)],
+			Kind::Z => [(0, 0), (1, 0), (1, 1), (2, 1)]
+		}
+	}
+	fn box_size(&self) -> i8 {
+		match self {
+			Kind::I => 4,
+			Kind::O => 2,
+			_ => 3
+		}
+	}
+	fn color(&self) -> ThemeColor {
+		current_theme().entities.tetris_pieces[*self as usize]
+	}
+}
+
+// SRS wall kicks, tried in order when rotating from each orientation, in (x, y) with y up.
+// Indexed by the orientation rotated from, clockwise then anticlockwise
+static KICKS: [[[(i8, i8); 5]; 2]; 4] = [
+	[[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]],
+	[[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)], [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]],
+	[[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)], [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]],
+	[[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)], [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]]
+];
+static I_KICKS: [[[(i8, i8); 5]; 2]; 4] = [
+	[[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]],
+	[[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]],
+	[[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]],
+	[[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]]
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Piece {
+	kind: Kind,
+	// The top left of the bounding box, in board cells from the top left of the hidden rows
+	x: i8,
+	y: i8,
+	// Quarter turns clockwise from the spawn orientation
+	rotation: u8
+}
+
+impl Piece {
+	fn spawn(kind: Kind) -> Piece {
+		Piece {
+			kind,
+			x: if kind == Kind::O {4} else {3},
+			y: 0,
+			rotation: 0
+		}
+	}
+	fn cells(&self) -> [(i8, i8); 4] {
+		let size = self.kind.box_size();
+		self.kind.cells().map(|(mut x, mut y)| {
+			for _ in 0..self.rotation {
+				(x, y) = (size - 1 - y, x);
+			}
+			(self.x + x, self.y + y)
+		})
+	}
+	fn moved(&self, dx: i8, dy: i8) -> Piece {
+		Piece { x: self.x + dx, y: self.y + dy, ..*self }
+	}
+}
+
+type Board = Vec<[Option<Kind>; WIDTH]>;
+
+fn fits(board: &Board, piece: &Piece) -> bool {
+	piece.cells().iter().all(|(x, y)| {
+		(0..WIDTH as i8).contains(x) && (0..HEIGHT as i8).contains(y) && board[*y as usize][*x as usize].is_none()
+	})
+}
+
+// Seconds per row from the guideline's curve, as a number of ticks
+fn gravity_ticks(level: u32) -> u32 {
+	let level = std::cmp::min(level, 20) as i32;
+	let seconds = (0.8 - (level - 1) as f64 * 0.007).powi(level - 1);
+	std::cmp::max(1, (seconds / FRAME_TIME.as_secs_f64()).round() as u32)
+}
+
+struct GameState {
+	board: Board,
+	piece: Piece,
+	queue: VecDeque<Kind>,
+	hold: Option<Kind>,
+	// Only once per piece, until it locks
+	can_hold: bool,
+	board_rect: Rect,
+	hold_rect: Rect,
+	next_rect: Rect,
+	status_rect: Rect,
+	finished: bool,
+	paused: bool,
+	score: u32,
+	lines: u32,
+	level: u32,
+	gravity_count: u32,
+	lock_count: u32,
+	lock_resets: u32,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("tetris", &[
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::RotateRight, &["w", "up", "x"]),
+		(Action::RotateLeft, &["z", "q"]),
+		(Action::Drop, &["space"]),
+		(Action::Hold, &["c"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+impl GameState {
+	// The board is the area inside the border, pieces come from the seed
+	fn new(board_rect: Rect, hold_rect: Rect, next_rect: Rect, status_rect: Rect, seed: u64) -> GameState {
+		let mut state = GameState {
+			board: vec![[None; WIDTH]; HEIGHT],
+			piece: Piece::spawn(Kind::I),
+			queue: VecDeque::new(),
+			hold: None,
+			can_hold: true,
+			board_rect,
+			hold_rect,
+			next_rect,
+			status_rect,
+			finished: false,
+			paused: false,
+			score: 0,
+			lines: 0,
+			level: 1,
+			gravity_count: 0,
+			lock_count: 0,
+			lock_resets: 0,
+			rng: StdRng::seed_from_u64(seed)
+		};
+		let first = state.take_next();
+		state.spawn(first);
+		state
+	}
+	// Pieces come in shuffled bags of all seven
+	fn take_next(&mut self) -> Kind {
+		while self.queue.len() <= NEXT_SHOWN {
+			let mut bag = Kind::all();
+			bag.shuffle(&mut self.rng);
+			self.queue.extend(bag);
+		}
+		self.queue.pop_front().unwrap()
+	}
+	fn spawn(&mut self, kind: Kind) {
+		self.piece = Piece::spawn(kind);
+		self.gravity_count = 0;
+		self.lock_count = 0;
+		self.lock_resets = 0;
+		if !fits(&self.board, &self.piece) {
+			self.finished = true;
+			return;
+		}
+		// Straight into view if there's room
+		self.try_move(0, 1);
+	}
+	fn grounded(&self) -> bool {
+		!fits(&self.board, &self.piece.moved(0, 1))
+	}
+	fn place(&mut self, piece: Piece) -> bool {
+		if !fits(&self.board, &piece) {return false};
+		self.piece = piece;
+		if self.grounded() && self.lock_resets < MAX_LOCK_RESETS {
+			self.lock_count = 0;
+			self.lock_resets += 1;
+		}
+		true
+	}
+	fn try_move(&mut self, dx: i8, dy: i8) -> bool {
+		self.place(self.piece.moved(dx, dy))
+	}
+	fn rotate(&mut self, clockwise: bool) {
+		let piece = self.piece;
+		let rotated = Piece { rotation: (piece.rotation + if clockwise {1} else {3}) % 4, ..piece };
+		let kicks = match piece.kind {
+			Kind::O => return,
+			Kind::I => &I_KICKS,
+			_ => &KICKS
+		};
+		for (dx, dy) in kicks[piece.rotation as usize][if clockwise {0} else {1}] {
+			if self.place(rotated.moved(dx, -dy)) {return};
+		}
+	}
+	fn ghost(&self) -> Piece {
+		let mut ghost = self.piece;
+		while fits(&self.board, &ghost.moved(0, 1)) {
+			ghost = ghost.moved(0, 1);
+		}
+		ghost
+	}
+	fn hard_drop(&mut self) {
+		let ghost = self.ghost();
+		self.score += 2 * (ghost.y - self.piece.y) as u32;
+		self.piece = ghost;
+		self.lock();
+	}
+	fn hold(&mut self) {
+		if !self.can_hold {return};
+		let held = self.piece.kind;
+		let next = match self.hold.take() {
+			Some(kind) => kind,
+			None => self.take_next()
+		};
+		self.hold = Some(held);
+		self.can_hold = false;
+		self.spawn(next);
+	}
+	fn lock(&mut self) {
+		let cells = self.piece.cells();
+		for (x, y) in cells {
+			self.board[y as usize][x as usize] = Some(self.piece.kind);
+		}
+		self.clear_lines();
+		// Locking entirely out of sight ends the game
+		if cells.iter().all(|(_, y)| (*y as usize) < HIDDEN) {
+			self.finished = true;
+			return;
+		}
+		self.can_hold = true;
+		let next = self.take_next();
+		self.spawn(next);
+	}
+	fn clear_lines(&mut self) {
+		self.board.retain(|row| row.iter().any(|cell| cell.is_none()));
+		let cleared = HEIGHT - self.board.len();
+		if cleared == 0 {return};
+		for _ in 0..cleared {
+			self.board.insert(0, [None; WIDTH]);
+		}
+		let points = [0, 100, 300, 500, 800][cleared];
+		self.score += points * self.level;
+		self.lines += cleared as u32;
+		self.level = std::cmp::max(self.level, self.lines / LINES_PER_LEVEL + 1);
+		report_event("tetris", "cleared", cleared as u32);
+		report_event("tetris", "lines", self.lines);
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		match action {
+			Action::Pause => self.paused = !self.paused,
+			_ if self.paused || self.finished => {},
+			Action::MoveLeft => {self.try_move(-1, 0);},
+			Action::MoveRight => {self.try_move(1, 0);},
+			Action::MoveDown if self.try_move(0, 1) => {
+				self.score += 1;
+				self.gravity_count = 0;
+			},
+			Action::RotateRight => self.rotate(true),
+			Action::RotateLeft => self.rotate(false),
+			Action::Drop => self.hard_drop(),
+			Action::Hold => self.hold(),
+			_ => {}
+		}
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished {return};
+		self.gravity_count += 1;
+		if self.gravity_count >= gravity_ticks(self.level) {
+			self.gravity_count = 0;
+			self.try_move(0, 1);
+		}
+		if !self.grounded() {
+			self.lock_count = 0;
+			return;
+		}
+		self.lock_count += 1;
+		if self.lock_count >= LOCK_TICKS {
+			self.lock();
+		}
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn draw_cell(state: &GameState, (x, y): (i8, i8), glyph: &str, col: &ThemeColor) {
+	if (y as usize) < HIDDEN {return};
+	let theme = current_theme();
+	let coords = (
+		state.board_rect.x + x as u16 * CELL_WIDTH,
+		state.board_rect.y + y as u16 - HIDDEN as u16
+	);
+	print_clipped(&repeat_str(glyph, CELL_WIDTH), coords, Some(state.board_rect), Some(&theme.fill), Some(col));
+}
+
+fn draw_board(state: &GameState) {
+	let glyphs = current_theme().glyphs;
+	for (y, row) in state.board.iter().enumerate() {
+		for (x, cell) in row.iter().enumerate() {
+			if let Some(kind) = cell {
+				draw_cell(state, (x as i8, y as i8), glyphs.block, &kind.color());
+			}
+		}
+	}
+	if state.finished {return};
+	let col = state.piece.kind.color();
+	for cell in state.ghost().cells() {
+		draw_cell(state, cell, glyphs.shade, &col);
+	}
+	for cell in state.piece.cells() {
+		draw_cell(state, cell, glyphs.block, &col);
+	}
+}
+
+// A piece in its spawn orientation, centred in a space two rows high
+fn draw_preview(kind: Kind, rect: Rect) {
+	let theme = current_theme();
+	let cells = kind.cells();
+	let top = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+	let width = cells.iter().map(|(x, _)| *x).max().unwrap_or(0) as u16 + 1;
+	let left = rect.x + rect.width.saturating_sub(width * CELL_WIDTH) / 2;
+	for (x, y) in cells {
+		let coords = (left + x as u16 * CELL_WIDTH, rect.y + (y - top) as u16);
+		print_clipped(&repeat_str(theme.glyphs.block, CELL_WIDTH), coords, Some(rect), Some(&theme.fill), Some(&kind.color()));
+	}
+}
+
+fn draw_panel(title: &str, rect: Rect, kinds: &[Kind]) {
+	let box_print = BoxPrint::in_rect(rect);
+	box_print.print();
+	let inner = box_print.inner();
+	TextPrint::in_rect(title, inner.row(0), Align::Center).set_max_width(inner.width).print();
+	for (i, kind) in kinds.iter().enumerate() {
+		let piece_rect = Rect::new((inner.x, inner.y + 1 + i as u16 * 3), (inner.width, 2)).intersection(inner);
+		draw_preview(*kind, piece_rect);
+	}
+}
+
+fn write_game_text(state: &GameState) {
+	let progress = format!("score: {}, level {}, {} lines", state.score, state.level, state.lines);
+	let text = if state.finished {
+		format!("Game over, {}, press any key to continue", progress)
+	} else if state.paused {
+		format!("Paused, {}", progress)
+	} else {
+		format!("Level {}, {} lines, score: {}", state.level, state.lines, state.score)
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(state.board_rect.outer(Padding::uniform(1))).print();
+	draw_board(state);
+	draw_panel("Hold", state.hold_rect, state.hold.as_slice());
+	let next = state.queue.iter().take(NEXT_SHOWN).copied().collect::<Vec<Kind>>();
+	draw_panel("Next", state.next_rect, &next);
+	write_game_text(state);
+	if state.finished {
+		print_splash("Game over", &format!("Score: {}", state.score));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+// The board in the middle with the held piece to its left and the queue to its right, status line underneath
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let board_size = (WIDTH as u16 * CELL_WIDTH + 2, (HEIGHT - HIDDEN) as u16 + 2);
+	let frame = Rect::screen().centered((board_size.0 + 2 * (PANEL_WIDTH + 1), board_size.1 + 1));
+	let [play_rect, status_rect] = Layout::vertical(&[Constraint::Min(0), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	let [hold_column, board_rect, next_column] = Layout::horizontal(&[
+		Constraint::Fixed(PANEL_WIDTH), Constraint::Fixed(board_size.0), Constraint::Fixed(PANEL_WIDTH)
+	]).spacing(1).split(play_rect)[..] else {
+		unreachable!()
+	};
+	let hold_rect = Rect::new(hold_column.coords(), (PANEL_WIDTH, 5)).intersection(hold_column);
+	let next_rect = Rect::new(next_column.coords(), (PANEL_WIDTH, NEXT_SHOWN as u16 * 3 + 2)).intersection(next_column);
+	GameState::new(board_rect.inner(Padding::uniform(1)), hold_rect, next_rect, status_rect, setup.seed)
+}
+
+pub struct Tetris {
+	setup: GameSetup
+}
+
+impl Game for Tetris {
+	fn run(&self) -> GameReturn {
+		let bindings = default_bindings().load_or_default();
+		let state = run_simulation(get_initial_state(&self.setup), FRAME_TIME, apply_bindings(bindings));
+		GameReturn {
+			score: Score::SinglePlayer(state.score as f32),
+			win_state: WinState::Lose,
+			records: vec![("Most lines", state.lines), ("Highest level", state.level)],
+			times: vec![]
+		}
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "tetris",
+		name: "Tetris",
+		description: "Fit the falling pieces together to clear lines before they stack to the top",
+		min_players: 1,
+		max_players: 1,
+		options: &[],
+		version: "1.0.0",
+		create: |setup| Box::new(Tetris { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "four_lines",
+				name: "Four at once",
+				description: "Clear four lines with one piece",
+				event: "cleared",
+				goal: 4
+			},
+			Achievement {
+				id: "lines_100",
+				name: "Line worker",
+				description: "Clear 100 lines in one game",
+				event: "lines",
+				goal: 100
+			}
+		],
+		// There are no settings, everyone gets the same pieces from the seed
+		daily: Some(|_, _| {})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::Harness, snapshot::assert_snapshot};
+
+	fn new_state(seed: u64) -> GameState {
+		let board_rect = Rect::new((15, 2), (20, 20));
+		let hold_rect = Rect::new((1, 1), (12, 5));
+		let next_rect = Rect::new((37, 1), (12, 11));
+		GameState::new(board_rect, hold_rect, next_rect, Rect::new((1, 23), (48, 1)), seed)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		Harness::new(state, (49, 23))
+	}
+
+	// Fills the bottom rows apart from the given columns
+	fn fill_rows(state: &mut GameState, rows: usize, gap: &[usize]) {
+		for y in HEIGHT - rows..HEIGHT {
+			for x in 0..WIDTH {
+				state.board[y][x] = if gap.contains(&x) {None} else {Some(Kind::O)};
+			}
+		}
+	}
+
+	#[test]
+	fn pieces_come_in_bags_of_seven() {
+		let mut state = new_state(5);
+		let mut kinds = vec![state.piece.kind];
+		kinds.extend((0..13).map(|_| state.take_next()));
+		for bag in kinds.chunks(7) {
+			for kind in Kind::all() {
+				assert_eq!(bag.iter().filter(|bagged| **bagged == kind).count(), 1);
+			}
+		}
+		assert_eq!(new_state(5).queue, new_state(5).queue);
+	}
+
+	#[test]
+	fn rotates_clockwise_and_back() {
+		let mut state = new_state(1);
+		state.piece = Piece { kind: Kind::T, x: 3, y: 5, rotation: 0 };
+		state.apply(Action::RotateRight);
+		let mut cells = state.piece.cells();
+		cells.sort();
+		assert_eq!(cells, [(4, 5), (4, 6), (4, 7), (5, 6)]);
+		state.apply(Action::RotateLeft);
+		assert_eq!(state.piece, Piece { kind: Kind::T, x: 3, y: 5, rotation: 0 });
+	}
+
+	#[test]
+	fn kicks_off_the_wall() {
+		let mut state = new_state(1);
+		// Pointing left against the right wall, turning back would poke through it without a kick
+		state.piece = Piece { kind: Kind::T, x: 8, y: 5, rotation: 3 };
+		assert!(fits(&state.board, &state.piece));
+		state.apply(Action::RotateRight);
+		assert_eq!(state.piece.rotation, 0);
+		assert_eq!(state.piece.x, 7);
+		// The I piece stood up against the left wall
+		state.piece = Piece { kind: Kind::I, x: -1, y: 5, rotation: 1 };
+		assert!(fits(&state.board, &state.piece));
+		state.apply(Action::RotateRight);
+		assert_eq!(state.piece.rotation, 2);
+		assert!(state.piece.cells().iter().all(|(x, _)| *x >= 0));
+	}
+
+	#[test]
+	fn hard_drop_clears_lines() {
+		let mut state = new_state(1);
+		fill_rows(&mut state, 4, &[9]);
+		state.piece = Piece { kind: Kind::I, x: 7, y: 5, rotation: 1 };
+		state.apply(Action::Drop);
+		assert_eq!(state.lines, 4);
+		assert!(state.board.iter().all(|row| row.iter().all(|cell| cell.is_none())));
+		// Two points a row dropped, then a tetris at level one
+		assert_eq!(state.score, 2 * 13 + 800);
+	}
+
+	#[test]
+	fn levels_speed_up_gravity() {
+		let mut state = new_state(1);
+		state.lines = 9;
+		fill_rows(&mut state, 1, &[0]);
+		state.piece = Piece { kind: Kind::I, x: -2, y: 5, rotation: 1 };
+		state.apply(Action::Drop);
+		assert_eq!(state.level, 2);
+		assert_eq!(gravity_ticks(1), 50);
+		assert!(gravity_ticks(2) < gravity_ticks(1));
+		assert_eq!(gravity_ticks(20), 1);
+	}
+
+	#[test]
+	fn holds_once_per_piece() {
+		let mut state = new_state(3);
+		let first = state.piece.kind;
+		let second = state.queue[0];
+		state.apply(Action::Hold);
+		assert_eq!(state.hold, Some(first));
+		assert_eq!(state.piece.kind, second);
+		state.apply(Action::Hold);
+		assert_eq!(state.piece.kind, second);
+		state.apply(Action::Drop);
+		state.apply(Action::Hold);
+		assert_eq!(state.piece.kind, first);
+	}
+
+	#[test]
+	fn locks_after_resting() {
+		let mut game = harness(new_state(2));
+		game.press(Action::Drop);
+		let kind = game.state.piece.kind;
+		game.run(30);
+		assert_eq!(game.state.piece.kind, kind);
+		game.press(Action::MoveDown);
+		while !game.state.grounded() {
+			game.press(Action::MoveDown);
+		}
+		game.run(LOCK_TICKS);
+		assert_eq!(game.state.board.iter().flatten().filter(|cell| cell.is_some()).count(), 8);
+	}
+
+	#[test]
+	fn ends_when_the_stack_reaches_the_top() {
+		let mut game = harness(new_state(4));
+		assert!(game.run_until_finished(10_000));
+		assert!(game.state.lines == 0 && game.state.score == 0);
+	}
+
+	#[test]
+	fn snapshot_after_a_few_pieces() {
+		let mut game = harness(new_state(3));
+		game.press(Action::MoveLeft).press(Action::MoveLeft).press(Action::Drop);
+		game.press(Action::RotateRight).press(Action::MoveRight).press(Action::MoveRight).press(Action::Drop);
+		game.press(Action::Hold);
+		game.run(60);
+		assert_snapshot("tetris_pieces", &game.frame());
+	}
+}
@@ -0,0 +1,457 @@
+use rand::prelude::*;
+
+use crate::common::{
+	achievements::*,
+	config::*,
+	game::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::{current_theme, ThemeColor}, toast::draw_toast, util::*}
+};
+
+static GOAL: u32 = 2048;
+static TILE_WIDTH: u16 = 7;
+// Tiles are squashed to a single row when the board wouldn't fit on the screen otherwise
+static TILE_HEIGHT: u16 = 3;
+
+// The theme's colours go up with the tile's power of two
+fn tile_colors(value: u32) -> (ThemeColor, ThemeColor) {
+	let colors = current_theme().entities;
+	let index = if value == 0 {0} else {std::cmp::min(value.ilog2() as usize, colors.tiles.len() - 1)};
+	let text = colors.tiles_text[if value <= 4 {0} else {1}];
+	(colors.tiles[index], text)
+}
+
+struct GameState {
+	size: usize,
+	// Row by row, 0 for an empty space
+	tiles: Vec<u32>,
+	score: u32,
+	// The tiles, score and whether the goal was reached from before the last move
+	undo: Option<(Vec<u32>, u32, bool)>,
+	reached_goal: bool,
+	// Waiting for the player to choose whether to keep going after reaching the goal
+	asking: bool,
+	finished: bool,
+	board_rect: Rect,
+	tile_height: u16,
+	status_rect: Rect,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("2048", &[
+		(Action::MoveUp, &["w", "up"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Undo, &["u", "backspace"]),
+		(Action::Confirm, &["enter"]),
+		(Action::Back, &["escape"])
+	])
+}
+
+// Slides one line towards its start, merging each pair of equal tiles once. Returns the points scored
+fn slide_line(line: &mut [u32]) -> u32 {
+	let tiles = line.iter().copied().filter(|tile| *tile != 0).collect::<Vec<u32>>();
+	let mut slid = vec![];
+	let mut points = 0;
+	let mut i = 0;
+	while i < tiles.len() {
+		if tiles.get(i + 1) == Some(&tiles[i]) {
+			slid.push(tiles[i] * 2);
+			points += tiles[i] * 2;
+			i += 2;
+		} else {
+			slid.push(tiles[i]);
+			i += 1;
+		}
+	}
+	slid.resize(line.len(), 0);
+	line.copy_from_slice(&slid);
+	points
+}
+
+// Indexes of the tiles in a row or column, starting from the side they're moving towards
+fn line_indexes(size: usize, action: Action, line: usize) -> Vec<usize> {
+	match action {
+		Action::MoveLeft => (0..size).map(|x| line * size + x).collect(),
+		Action::MoveRight => (0..size).rev().map(|x| line * size + x).collect(),
+		Action::MoveUp => (0..size).map(|y| y * size + line).collect(),
+		_ => (0..size).rev().map(|y| y * size + line).collect()
+	}
+}
+
+fn can_move(tiles: &[u32], size: usize) -> bool {
+	(0..tiles.len()).any(|i| {
+		let (x, y) = (i % size, i / size);
+		tiles[i] == 0 || (x + 1 < size && tiles[i] == tiles[i + 1]) || (y + 1 < size && tiles[i] == tiles[i + size])
+	})
+}
+
+impl GameState {
+	// The board is the area inside the border, new tiles are placed using the seed
+	fn new(size: usize, board_rect: Rect, tile_height: u16, status_rect: Rect, seed: u64) -> GameState {
+		let size = size.clamp(MIN_TILES_SIZE as usize, MAX_TILES_SIZE as usize);
+		let mut state = GameState {
+			size,
+			tiles: vec![0; size * size],
+			score: 0,
+			undo: None,
+			reached_goal: false,
+			asking: false,
+			finished: false,
+			board_rect,
+			tile_height,
+			status_rect,
+			rng: StdRng::seed_from_u64(seed)
+		};
+		state.add_tile();
+		state.add_tile();
+		state
+	}
+	// A 2, or now and then a 4, in an empty space
+	fn add_tile(&mut self) {
+		let empty = (0..self.tiles.len()).filter(|i| self.tiles[*i] == 0).collect::<Vec<usize>>();
+		let Some(index) = empty.choose(&mut self.rng) else {return};
+		self.tiles[*index] = if self.rng.gen_bool(0.1) {4} else {2};
+	}
+	fn slide(&mut self, action: Action) {
+		let before = self.tiles.clone();
+		let mut points = 0;
+		for line in 0..self.size {
+			let indexes = line_indexes(self.size, action, line);
+			let mut tiles = indexes.iter().map(|i| self.tiles[*i]).collect::<Vec<u32>>();
+			points += slide_line(&mut tiles);
+			for (i, tile) in indexes.into_iter().zip(tiles) {
+				self.tiles[i] = tile;
+			}
+		}
+		// Only moves that change something count, and bring a new tile
+		if self.tiles == before {return};
+		self.undo = Some((before, self.score, self.reached_goal));
+		self.score += points;
+		self.add_tile();
+		let highest = self.highest();
+		report_event("2048", "tile", highest);
+		if !self.reached_goal && highest >= GOAL {
+			self.reached_goal = true;
+			self.asking = true;
+		}
+		if !can_move(&self.tiles, self.size) {
+			self.finished = true;
+		}
+	}
+	fn highest(&self) -> u32 {
+		self.tiles.iter().copied().max().unwrap_or(0)
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		match action {
+			_ if self.finished => {},
+			Action::Back => self.finished = true,
+			Action::Confirm if self.asking => self.asking = false,
+			_ if self.asking => {},
+			Action::Undo => if let Some((tiles, score, reached_goal)) = self.undo.take() {
+				self.tiles = tiles;
+				self.score = score;
+				self.reached_goal = reached_goal;
+			},
+			Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight => self.slide(action),
+			_ => {}
+		}
+	}
+	// Nothing happens without the player
+	fn tick(&mut self) {}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn draw_tiles(state: &GameState) {
+	for (i, value) in state.tiles.iter().enumerate() {
+		let (x, y) = ((i % state.size) as u16, (i / state.size) as u16);
+		let coords = (
+			state.board_rect.x + x * (TILE_WIDTH + 1),
+			state.board_rect.y + y * (state.tile_height + 1)
+		);
+		let rect = Rect::new(coords, (TILE_WIDTH, state.tile_height)).intersection(state.board_rect);
+		let (fill, text) = tile_colors(*value);
+		BoxPrint::in_rect(rect)
+			.set_border(false)
+			.set_fill(&fill)
+			.print();
+		if *value == 0 {continue};
+		let label = value.to_string();
+		let row = rect.row(rect.height / 2);
+		TextPrint::in_rect(&label, row, Align::Center)
+			.color_bg(&fill)
+			.color_fg(&text)
+			.add_prefix(&termion::style::Bold)
+			.print();
+	}
+}
+
+fn write_game_text(state: &GameState) {
+	let text = if state.finished {
+		format!("Game over, score: {}, press any key to continue", state.score)
+	} else if state.asking {
+		format!("You made {}! Enter to keep going, escape to stop", GOAL)
+	} else {
+		format!("Score: {}", state.score)
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(state.board_rect.outer(Padding::uniform(2)))
+		.print();
+	draw_tiles(state);
+	write_game_text(state);
+	if state.finished {
+		print_splash("Game over", &format!("Score: {}", state.score));
+	} else if state.asking {
+		print_splash(&GOAL.to_string(), &format!("Score: {}", state.score));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let size = setup.config.tiles.board_size();
+	let board_size = |tile_height: u16| (size * (TILE_WIDTH + 1) - 1, size * (tile_height + 1) - 1);
+	let screen = Rect::screen();
+	let tile_height = if board_size(TILE_HEIGHT).1 + 5 <= screen.height {TILE_HEIGHT} else {1};
+	let (width, height) = board_size(tile_height);
+	// Board with its border and a space around the tiles, plus a status line underneath
+	let frame = screen.centered((width + 4, height + 5));
+	let [board_rect, status_rect] = Layout::vertical(&[Constraint::Min(5), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	GameState::new(size as usize, board_rect.inner(Padding::uniform(2)), tile_height, status_rect, setup.seed)
+}
+
+pub struct Tiles {
+	setup: GameSetup
+}
+
+impl Game for Tiles {
+	fn run(&self) -> GameReturn {
+		let mut state = get_initial_state(&self.setup);
+		let bindings = default_bindings().load_or_default();
+		while !state.finished {
+			write_screen(&state);
+			let Some(key) = read_key() else {break};
+			if let Some(action) = bindings.action_for(key) {
+				state.apply(action);
+			}
+		}
+		// Stopping on purpose goes straight back, running out of moves shows the board first
+		if !can_move(&state.tiles, state.size) {
+			write_screen(&state);
+			read_key();
+		}
+		GameReturn {
+			score: Score::SinglePlayer(state.score as f32),
+			win_state: if state.reached_goal {WinState::Win} else {WinState::Lose},
+			records: vec![("Highest tile", state.highest())],
+			times: vec![]
+		}
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "2048",
+		name: "2048",
+		description: "Slide the tiles to merge matching numbers and reach 2048",
+		min_players: 1,
+		max_players: 1,
+		options: &[GameOption::TilesGridSize],
+		version: "1.0.0",
+		create: |setup| Box::new(Tiles { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "tile_512",
+				name: "Getting there",
+				description: "Make a 512 tile",
+				event: "tile",
+				goal: 512
+			},
+			Achievement {
+				id: "tile_2048",
+				name: "2048",
+				description: "Make a 2048 tile",
+				event: "tile",
+				goal: 2048
+			}
+		],
+		daily: Some(|config, _| {
+			config.tiles = TilesConfig::default();
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::Harness, snapshot::assert_snapshot};
+
+	fn new_state(seed: u64) -> GameState {
+		GameState::new(4, Rect::new((4, 3), (31, 15)), TILE_HEIGHT, Rect::new((2, 21), (35, 1)), seed)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		Harness::new(state, (38, 21))
+	}
+
+	fn slid(mut line: Vec<u32>) -> (Vec<u32>, u32) {
+		let points = slide_line(&mut line);
+		(line, points)
+	}
+
+	#[test]
+	fn merges_each_tile_once() {
+		assert_eq!(slid(vec![2, 2, 2, 2]), (vec![4, 4, 0, 0], 8));
+		assert_eq!(slid(vec![2, 2, 4, 0]), (vec![4, 4, 0, 0], 4));
+		assert_eq!(slid(vec![4, 0, 4, 8]), (vec![8, 8, 0, 0], 8));
+		assert_eq!(slid(vec![2, 2, 2, 0]), (vec![4, 2, 0, 0], 4));
+		assert_eq!(slid(vec![2, 4, 8, 16]), (vec![2, 4, 8, 16], 0));
+	}
+
+	#[test]
+	fn slides_in_each_direction() {
+		let mut state = new_state(1);
+		state.tiles = vec![
+			2, 0, 2, 0,
+			0, 4, 0, 0,
+			0, 0, 0, 0,
+			0, 4, 0, 8
+		];
+		state.apply(Action::MoveRight);
+		assert_eq!(state.score, 4);
+		assert_eq!(state.tiles[3], 4);
+		assert_eq!(state.tiles[7], 4);
+		assert_eq!(state.tiles[15], 8);
+		assert_eq!(state.tiles.iter().filter(|tile| **tile != 0).count(), 5);
+
+		state.tiles = vec![
+			2, 0, 0, 0,
+			2, 0, 0, 0,
+			4, 0, 0, 0,
+			4, 0, 0, 0
+		];
+		state.apply(Action::MoveDown);
+		assert_eq!(state.tiles[8..].iter().step_by(4).copied().collect::<Vec<u32>>(), [4, 8]);
+		assert_eq!(state.score, 16);
+	}
+
+	#[test]
+	fn moves_that_change_nothing_add_no_tile() {
+		let mut state = new_state(1);
+		state.tiles = vec![2, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+		state.apply(Action::MoveLeft);
+		assert_eq!(state.tiles[..2], [2, 4]);
+		assert_eq!(state.tiles.iter().filter(|tile| **tile != 0).count(), 2);
+		assert!(state.undo.is_none());
+	}
+
+	#[test]
+	fn undoes_the_last_move() {
+		let mut state = new_state(7);
+		let start = state.tiles.clone();
+		for action in [Action::MoveLeft, Action::MoveUp, Action::MoveRight, Action::MoveDown] {
+			state.apply(action);
+			if state.undo.is_some() {break};
+		}
+		let (before, _, _) = state.undo.clone().unwrap();
+		state.apply(Action::Undo);
+		assert_eq!(state.tiles, before);
+		assert_eq!(state.score, 0);
+		assert!(state.undo.is_none());
+		assert_eq!(state.tiles, start);
+	}
+
+	#[test]
+	fn asks_to_keep_going_at_2048() {
+		let mut state = new_state(1);
+		state.tiles = vec![1024, 1024, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+		state.apply(Action::MoveLeft);
+		assert!(state.reached_goal && state.asking);
+		state.apply(Action::MoveRight);
+		assert_eq!(state.tiles[0], 2048);
+		state.apply(Action::Confirm);
+		state.apply(Action::MoveRight);
+		assert_eq!(state.tiles[3], 2048);
+		state.apply(Action::Back);
+		assert!(state.finished);
+	}
+
+	#[test]
+	fn undoing_past_2048_asks_again() {
+		let mut state = new_state(1);
+		state.tiles = vec![1024, 1024, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+		state.apply(Action::MoveLeft);
+		state.apply(Action::Confirm);
+		state.apply(Action::Undo);
+		assert_eq!(state.tiles[..2], [1024, 1024]);
+		assert!(!state.reached_goal);
+		state.apply(Action::MoveLeft);
+		assert!(state.reached_goal && state.asking);
+	}
+
+	#[test]
+	fn board_size_is_limited() {
+		let mut config = TilesConfig { size: 40 };
+		assert_eq!(config.board_size(), MAX_TILES_SIZE);
+		config.size = 0;
+		assert_eq!(config.board_size(), MIN_TILES_SIZE);
+		let state = GameState::new(40, Rect::new((1, 1), (10, 10)), 1, Rect::new((1, 12), (10, 1)), 1);
+		assert_eq!(state.size, MAX_TILES_SIZE as usize);
+	}
+
+	#[test]
+	fn ends_when_nothing_can_move() {
+		let mut state = new_state(1);
+		state.tiles = vec![
+			2, 4, 2, 4,
+			4, 2, 4, 2,
+			2, 4, 2, 4,
+			4, 2, 4, 0
+		];
+		state.apply(Action::MoveDown);
+		assert!(!state.finished);
+		// The new tile, a 2 or a 4, can't join anything around it
+		state.tiles = vec![
+			2, 4, 2, 4,
+			4, 2, 4, 2,
+			2, 4, 2, 8,
+			32, 64, 8, 8
+		];
+		state.apply(Action::MoveLeft);
+		assert_eq!(state.tiles[12..15], [32, 64, 16]);
+		assert!(matches!(state.tiles[15], 2 | 4));
+		assert!(state.finished);
+		// No undoing once the game is over
+		let over = state.tiles.clone();
+		state.apply(Action::Undo);
+		assert_eq!(state.tiles, over);
+	}
+
+	#[test]
+	fn snapshot_after_a_few_moves() {
+		let mut game = harness(new_state(3));
+		game.press(Action::MoveLeft).press(Action::MoveUp).press(Action::MoveRight).press(Action::MoveUp);
+		assert_snapshot("tiles_moves", &game.frame());
+	}
+}
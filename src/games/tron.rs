@@ -0,0 +1,604 @@
+use std::{collections::VecDeque, time::Duration};
+
+use rand::prelude::*;
+
+use crate::common::{
+	achievements::*,
+	config::*,
+	game::*,
+	game_loop::*,
+	headless::Simulation,
+	input::*,
+	registry::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, text::TextPrint, theme::*, toast::draw_toast, util::*}
+};
+
+static BOARD_SIZE: (u16, u16) = (60, 18);
+// How long the end of a round stays up before the next
+static ROUND_PAUSE_TICKS: u32 = 25;
+// Chance of the easy computer turning for no reason
+static EASY_TURN_CHANCE: f64 = 0.05;
+static FRAME_TIME: Duration = Duration::from_millis(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+	Up,
+	Down,
+	Left,
+	Right
+}
+
+impl Direction {
+	fn all() -> [Direction; 4] {
+		[Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+	}
+	fn from_action(action: Action) -> Option<Direction> {
+		match action {
+			Action::MoveUp => Some(Direction::Up),
+			Action::MoveDown => Some(Direction::Down),
+			Action::MoveLeft => Some(Direction::Left),
+			Action::MoveRight => Some(Direction::Right),
+			_ => None
+		}
+	}
+	fn opposite(&self) -> Direction {
+		match self {
+			Direction::Up => Direction::Down,
+			Direction::Down => Direction::Up,
+			Direction::Left => Direction::Right,
+			Direction::Right => Direction::Left
+		}
+	}
+	// The next cell this way, None off the edge of the board
+	fn step(&self, (x, y): (u16, u16), bounds: (u16, u16)) -> Option<(u16, u16)> {
+		let next = match self {
+			Direction::Up => (x, y.checked_sub(1)?),
+			Direction::Down => (x, y + 1),
+			Direction::Left => (x.checked_sub(1)?, y),
+			Direction::Right => (x + 1, y)
+		};
+		if next.0 < bounds.0 && next.1 < bounds.1 {Some(next)} else {None}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RoundResult {
+	Won(usize),
+	Draw
+}
+
+struct Cycle {
+	pos: (u16, u16),
+	direction: Direction,
+	// Turns waiting for the next moves, so two quick presses aren't lost
+	turns: VecDeque<Direction>,
+	// Steered by the computer at this difficulty
+	computer: Option<Difficulty>,
+	wins: u16,
+	// Cells covered this round
+	trail: u32
+}
+
+impl Cycle {
+	fn new(computer: Option<Difficulty>) -> Cycle {
+		Cycle {
+			pos: (0, 0),
+			direction: Direction::Right,
+			turns: VecDeque::new(),
+			computer,
+			wins: 0,
+			trail: 0
+		}
+	}
+}
+
+struct GameState {
+	bounds: (u16, u16),
+	offset: (u16, u16),
+	status_rect: Rect,
+	// Whose trail covers each cell by row then column
+	grid: Vec<Vec<Option<usize>>>,
+	cycles: [Cycle; 2],
+	names: [&'static str; 2],
+	// The match is best of this many
+	rounds: u16,
+	played: u16,
+	last_result: Option<RoundResult>,
+	// Where the cycles crashed last round
+	crashes: Vec<(u16, u16)>,
+	// Counts down while the last round's result is shown
+	pause_ticks: u32,
+	longest_trail: u32,
+	finished: bool,
+	paused: bool,
+	rng: StdRng
+}
+
+pub fn default_bindings() -> Bindings {
+	Bindings::new("tron", &[
+		(Action::MoveUp, &["w", "up"]),
+		(Action::MoveDown, &["s", "down"]),
+		(Action::MoveLeft, &["a", "left"]),
+		(Action::MoveRight, &["d", "right"]),
+		(Action::Pause, &["p"])
+	])
+}
+
+// For the second cycle when two share the keyboard, these take priority over the tron bindings
+pub fn second_player_bindings() -> Bindings {
+	Bindings::new("tron_second", &[
+		(Action::MoveUp, &["up"]),
+		(Action::MoveDown, &["down"]),
+		(Action::MoveLeft, &["left"]),
+		(Action::MoveRight, &["right"])
+	])
+}
+
+fn board_rect(state: &GameState) -> Rect {
+	Rect::new(state.offset, state.bounds)
+}
+
+// Steps to each cell that can be reached from the start without crossing a trail, None where
+// it can't. The start counts even if it's taken, so this works from a cycle's head
+fn distances(grid: &[Vec<Option<usize>>], start: (u16, u16), bounds: (u16, u16)) -> Vec<Vec<Option<u32>>> {
+	let mut distances = vec![vec![None; bounds.0 as usize]; bounds.1 as usize];
+	distances[start.1 as usize][start.0 as usize] = Some(0);
+	let mut queue = VecDeque::from([(start, 0)]);
+	while let Some((cell, distance)) = queue.pop_front() {
+		for direction in Direction::all() {
+			let Some((x, y)) = direction.step(cell, bounds) else {continue};
+			let (row, column) = (y as usize, x as usize);
+			if grid[row][column].is_some() || distances[row][column].is_some() {continue};
+			distances[row][column] = Some(distance + 1);
+			queue.push_back(((x, y), distance + 1));
+		}
+	}
+	distances
+}
+
+impl GameState {
+	// The board is the area inside the border. The second cycle is the computer's unless
+	// `second` is None, the easy computer's mistakes come from the seed
+	fn new(board: Rect, status_rect: Rect, rounds: u16, second: Option<Difficulty>, seed: u64) -> GameState {
+		let mut state = GameState {
+			bounds: BOARD_SIZE,
+			offset: board.coords(),
+			status_rect,
+			grid: vec![],
+			cycles: [Cycle::new(None), Cycle::new(second)],
+			names: if second.is_some() {["You", "Computer"]} else {["Blue", "Orange"]},
+			rounds: std::cmp::max(rounds, 1),
+			played: 0,
+			last_result: None,
+			crashes: vec![],
+			pause_ticks: 0,
+			longest_trail: 0,
+			finished: false,
+			paused: false,
+			rng: StdRng::seed_from_u64(seed)
+		};
+		state.start_round();
+		state
+	}
+	// Facing each other from either side of the board
+	fn start_round(&mut self) {
+		self.grid = vec![vec![None; self.bounds.0 as usize]; self.bounds.1 as usize];
+		let y = self.bounds.1 / 2;
+		let starts = [((self.bounds.0 / 4, y), Direction::Right), ((self.bounds.0 - 1 - self.bounds.0 / 4, y), Direction::Left)];
+		for (index, (pos, direction)) in starts.into_iter().enumerate() {
+			let cycle = &mut self.cycles[index];
+			(cycle.pos, cycle.direction, cycle.trail) = (pos, direction, 1);
+			cycle.turns.clear();
+			self.grid[pos.1 as usize][pos.0 as usize] = Some(index);
+		}
+		self.crashes.clear();
+	}
+	fn is_free(&self, (x, y): (u16, u16)) -> bool {
+		self.grid[y as usize][x as usize].is_none()
+	}
+	fn apply_to(&mut self, index: usize, action: Action) {
+		match action {
+			Action::Pause => self.paused = !self.paused,
+			_ if self.paused || self.finished => {},
+			action => if let Some(direction) = Direction::from_action(action) {
+				self.cycles[index].turns.push_back(direction);
+			}
+		}
+	}
+	// Takes the next queued turn that doesn't double back
+	fn take_turn(&mut self, index: usize) {
+		let cycle = &mut self.cycles[index];
+		while let Some(direction) = cycle.turns.pop_front() {
+			if direction != cycle.direction && direction != cycle.direction.opposite() {
+				cycle.direction = direction;
+				return;
+			}
+		}
+	}
+	// Heads for the most room, and on hard for the cells it can get to before the other cycle.
+	// Ties keep away from the other head, then go straight on
+	fn computer_direction(&mut self, index: usize, difficulty: Difficulty) -> Direction {
+		let (cycle, other) = (&self.cycles[index], &self.cycles[1 - index]);
+		let straight = cycle.direction;
+		let safe = Direction::all().into_iter()
+			.filter(|direction| *direction != straight.opposite())
+			.filter_map(|direction| direction.step(cycle.pos, self.bounds).filter(|next| self.is_free(*next)).map(|next| (direction, next)))
+			.collect::<Vec<(Direction, (u16, u16))>>();
+		if safe.is_empty() {return straight};
+		if difficulty == Difficulty::Easy {
+			let keep_going = safe.iter().any(|(direction, _)| *direction == straight);
+			return if keep_going && !self.rng.gen_bool(EASY_TURN_CHANCE) {straight} else {safe.choose(&mut self.rng).unwrap().0};
+		}
+		let theirs = distances(&self.grid, other.pos, self.bounds);
+		let (direction, _) = safe.iter().max_by_key(|(direction, next)| {
+			let mine = distances(&self.grid, *next, self.bounds);
+			let room = mine.iter().flatten().flatten().count();
+			let territory = if difficulty == Difficulty::Hard {
+				mine.iter().flatten().zip(theirs.iter().flatten()).filter(|(mine, theirs)| match (mine, theirs) {
+					(Some(mine), Some(theirs)) => mine < theirs,
+					(Some(_), None) => true,
+					_ => false
+				}).count()
+			} else {0};
+			let near_head = next.0.abs_diff(other.pos.0) + next.1.abs_diff(other.pos.1) == 1;
+			(room, territory, !near_head, *direction == straight)
+		}).unwrap();
+		*direction
+	}
+	fn move_cycles(&mut self) {
+		for index in 0..2 {
+			match self.cycles[index].computer {
+				Some(difficulty) => self.cycles[index].direction = self.computer_direction(index, difficulty),
+				None => self.take_turn(index)
+			}
+		}
+		let next = self.cycles.each_ref().map(|cycle| cycle.direction.step(cycle.pos, self.bounds));
+		let mut crashed = next.map(|next| !next.is_some_and(|next| self.is_free(next)));
+		// Both into the same cell is a head on crash
+		if next[0].is_some() && next[0] == next[1] {
+			crashed = [true, true];
+		}
+		for index in 0..2 {
+			match next[index] {
+				Some(pos) if !crashed[index] => {
+					self.grid[pos.1 as usize][pos.0 as usize] = Some(index);
+					self.cycles[index].pos = pos;
+					self.cycles[index].trail += 1;
+				},
+				_ if crashed[index] => self.crashes.push(next[index].unwrap_or(self.cycles[index].pos)),
+				_ => {}
+			}
+		}
+		match crashed {
+			[true, true] => self.end_round(RoundResult::Draw),
+			[true, false] => self.end_round(RoundResult::Won(1)),
+			[false, true] => self.end_round(RoundResult::Won(0)),
+			_ => {}
+		}
+	}
+	fn end_round(&mut self, result: RoundResult) {
+		if let RoundResult::Won(index) = result {
+			self.cycles[index].wins += 1;
+		}
+		self.played += 1;
+		self.last_result = Some(result);
+		self.longest_trail = std::cmp::max(self.longest_trail, self.cycles[0].trail);
+		let needed = self.rounds / 2 + 1;
+		if self.played >= self.rounds || self.cycles.iter().any(|cycle| cycle.wins >= needed) {
+			self.finished = true;
+			self.report_result();
+		} else {
+			self.pause_ticks = ROUND_PAUSE_TICKS;
+		}
+	}
+	// The achievements are all for beating the computer
+	fn report_result(&self) {
+		let Some(difficulty) = self.cycles[1].computer else {return};
+		if self.cycles[0].wins <= self.cycles[1].wins {return};
+		report_event("tron", "win", 1);
+		if difficulty == Difficulty::Hard {
+			report_event("tron", "hard_win", 1);
+		}
+	}
+	// Who's ahead on rounds, None when level
+	fn leader(&self) -> Option<usize> {
+		match self.cycles[0].wins.cmp(&self.cycles[1].wins) {
+			std::cmp::Ordering::Greater => Some(0),
+			std::cmp::Ordering::Less => Some(1),
+			std::cmp::Ordering::Equal => None
+		}
+	}
+}
+
+impl Simulation for GameState {
+	fn apply(&mut self, action: Action) {
+		self.apply_to(0, action);
+	}
+	fn tick(&mut self) {
+		if self.paused || self.finished {return};
+		if self.pause_ticks > 0 {
+			self.pause_ticks -= 1;
+			if self.pause_ticks == 0 {
+				self.start_round();
+			}
+			return;
+		}
+		self.move_cycles();
+	}
+	fn draw(&self) {
+		write_screen(self);
+	}
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn draw_trails(state: &GameState) {
+	let theme = current_theme();
+	for (y, row) in state.grid.iter().enumerate() {
+		for (x, owner) in row.iter().enumerate() {
+			let Some(owner) = owner else {continue};
+			let coords = (state.offset.0 + x as u16, state.offset.1 + y as u16);
+			print_clipped(theme.glyphs.block, coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.tron_trails[*owner]));
+		}
+	}
+	for (index, cycle) in state.cycles.iter().enumerate() {
+		let coords = (state.offset.0 + cycle.pos.0, state.offset.1 + cycle.pos.1);
+		print_clipped(theme.glyphs.block, coords, Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.tron_heads[index]));
+	}
+	for (x, y) in &state.crashes {
+		print_clipped("X", (state.offset.0 + x, state.offset.1 + y), Some(board_rect(state)), Some(&theme.fill), Some(&theme.entities.tron_crash));
+	}
+}
+
+fn get_win_text(state: &GameState) -> String {
+	match state.leader() {
+		None => "Draw".to_owned(),
+		Some(0) if state.cycles[1].computer.is_some() => "You win".to_owned(),
+		Some(index) => format!("{} wins", state.names[index])
+	}
+}
+
+fn write_game_text(state: &GameState) {
+	let score = format!("{} {} - {} {}", state.names[0], state.cycles[0].wins, state.cycles[1].wins, state.names[1]);
+	let text = if state.finished {
+		format!("{}, {}, press any key to continue", get_win_text(state), score)
+	} else if state.paused {
+		format!("Paused, {}", score)
+	} else if state.pause_ticks > 0 {
+		match state.last_result {
+			Some(RoundResult::Won(index)) => format!("{} takes the round, {}", state.names[index], score),
+			_ => format!("Round drawn, {}", score)
+		}
+	} else {
+		format!("{}, best of {}", score, state.rounds)
+	};
+	TextPrint::in_rect(&text, state.status_rect, Align::Center)
+		.set_max_width(state.status_rect.width)
+		.print();
+}
+
+fn write_screen(state: &GameState) {
+	clear_screen();
+	BoxPrint::in_rect(board_rect(state).outer(Padding::uniform(1))).print();
+	draw_trails(state);
+	write_game_text(state);
+	if state.finished {
+		print_splash(&get_win_text(state), &format!("{} - {}", state.cycles[0].wins, state.cycles[1].wins));
+	}
+	draw_toast();
+	cursor_to_end();
+}
+
+fn get_initial_state(setup: &GameSetup) -> GameState {
+	let config = &setup.config;
+	// Board with its border, plus a status line underneath
+	let frame = Rect::screen().centered((BOARD_SIZE.0 + 2, BOARD_SIZE.1 + 3));
+	let [board_rect, status_rect] = Layout::vertical(&[Constraint::Min(3), Constraint::Fixed(1)]).split(frame)[..] else {
+		unreachable!()
+	};
+	let second = if config.tron.two_players {None} else {Some(config.difficulty)};
+	GameState::new(board_rect.inner(Padding::uniform(1)), status_rect, config.tron.rounds, second, setup.seed)
+}
+
+fn get_game_return(state: &GameState) -> GameReturn {
+	let (wins, opponent_wins) = (state.cycles[0].wins, state.cycles[1].wins);
+	GameReturn {
+		score: if state.cycles[1].computer.is_some() {
+			Score::TwoPlayer(wins as f32, opponent_wins as f32)
+		} else {
+			Score::Versus(wins as f32, opponent_wins as f32)
+		},
+		win_state: match state.leader() {
+			Some(0) => WinState::Win,
+			Some(_) => WinState::Lose,
+			None => WinState::Draw
+		},
+		records: vec![("Longest trail", state.longest_trail)],
+		times: vec![]
+	}
+}
+
+pub struct Tron {
+	setup: GameSetup
+}
+
+impl Game for Tron {
+	fn run(&self) -> GameReturn {
+		let bindings = default_bindings().load_or_default();
+		let second_bindings = second_player_bindings().load_or_default();
+		let state = run_simulation(get_initial_state(&self.setup), FRAME_TIME, move |state: &mut GameState, key| {
+			let second_action = second_bindings.action_for(key).filter(|_| state.cycles[1].computer.is_none());
+			let action = match second_action {
+				Some(action) => Some((1, action)),
+				None => bindings.action_for(key).map(|action| (0, action))
+			};
+			if let Some((index, action)) = action {
+				state.apply_to(index, action);
+			}
+		});
+		get_game_return(&state)
+	}
+}
+
+pub fn get_game_entry() -> GameEntry {
+	GameEntry {
+		id: "tron",
+		name: "Tron",
+		description: "Outlast the other light cycle without hitting a trail",
+		min_players: 1,
+		max_players: 2,
+		options: &[GameOption::Difficulty, GameOption::TronRounds, GameOption::TronOpponent],
+		version: "1.0.0",
+		create: |setup| Box::new(Tron { setup }),
+		default_bindings,
+		achievements: &[
+			Achievement {
+				id: "win",
+				name: "End of line",
+				description: "Win a match against the computer",
+				event: "win",
+				goal: 1
+			},
+			Achievement {
+				id: "hard_win",
+				name: "User wins",
+				description: "Win a match against the computer on hard",
+				event: "hard_win",
+				goal: 1
+			}
+		],
+		daily: Some(|config, day| {
+			config.tron = TronConfig { rounds: 3, two_players: false };
+			config.difficulty = Difficulty::all()[day as usize % 3];
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{headless::*, snapshot::assert_snapshot};
+
+	fn new_state(rounds: u16, second: Option<Difficulty>) -> GameState {
+		let (board, status_rect) = board_rects(BOARD_SIZE);
+		GameState::new(board, status_rect, rounds, second, 1)
+	}
+
+	fn harness(state: GameState) -> Harness<GameState> {
+		Harness::for_board(state, BOARD_SIZE)
+	}
+
+	#[test]
+	fn cycles_leave_trails() {
+		let mut game = harness(new_state(3, None));
+		game.press(Action::MoveUp).run(3);
+		assert_eq!(game.state.cycles[0].pos, (15, 6));
+		assert_eq!(game.state.cycles[1].pos, (41, 9));
+		assert_eq!(game.state.grid[8][15], Some(0));
+		assert_eq!(game.state.grid[9][42], Some(1));
+		assert_eq!(game.state.cycles[0].trail, 4);
+		// Doubling back is ignored
+		game.press(Action::MoveDown).step();
+		assert_eq!(game.state.cycles[0].pos, (15, 5));
+	}
+
+	#[test]
+	fn quick_turns_are_queued() {
+		let mut game = harness(new_state(3, None));
+		game.press(Action::MoveUp).press(Action::MoveLeft).run(2);
+		assert_eq!(game.state.cycles[0].pos, (14, 8));
+		assert_eq!(game.state.cycles[0].direction, Direction::Left);
+	}
+
+	#[test]
+	fn hitting_a_trail_loses_the_round() {
+		let mut game = harness(new_state(3, None));
+		// Round in a tight square back to where it started
+		for action in [Action::MoveDown, Action::MoveLeft, Action::MoveUp, Action::MoveRight] {
+			game.state.apply_to(1, action);
+			game.step();
+		}
+		assert_eq!(game.state.last_result, Some(RoundResult::Won(0)));
+		assert_eq!(game.state.cycles[0].wins, 1);
+		assert_eq!(game.state.crashes, vec![(44, 9)]);
+		// The board's cleared for the next round
+		game.run(ROUND_PAUSE_TICKS);
+		assert_eq!(game.state.cycles[1].pos, (44, 9));
+		assert_eq!(game.state.grid[10][43], None);
+		assert!(game.state.crashes.is_empty());
+	}
+
+	#[test]
+	fn head_on_is_a_draw() {
+		let mut game = harness(new_state(1, None));
+		assert!(game.run_until_finished(20));
+		assert_eq!(game.state.last_result, Some(RoundResult::Draw));
+		assert_eq!(game.state.crashes.len(), 2);
+		let game_return = get_game_return(&game.state);
+		assert!(matches!(game_return.win_state, WinState::Draw));
+		assert!(matches!(game_return.score, Score::Versus(0.0, 0.0)));
+		assert_eq!(game_return.score.player_score(), None);
+	}
+
+	#[test]
+	fn walls_end_the_round() {
+		let mut game = harness(new_state(1, None));
+		game.press(Action::MoveUp);
+		game.state.apply_to(1, Action::MoveDown);
+		// The bottom wall's closer, the first cycle only just makes it to the top
+		assert!(game.run_until_finished(20));
+		assert_eq!(game.state.last_result, Some(RoundResult::Won(0)));
+		assert_eq!(game.state.crashes, vec![(44, 17)]);
+		assert_eq!(game.state.cycles[0].pos, (15, 0));
+	}
+
+	#[test]
+	fn match_ends_once_decided() {
+		let mut state = new_state(5, None);
+		state.cycles[1].wins = 2;
+		state.played = 3;
+		state.end_round(RoundResult::Won(1));
+		assert!(state.finished);
+		assert!(matches!(get_game_return(&state).win_state, WinState::Lose));
+		assert_eq!(get_win_text(&state), "Orange wins");
+		let mut state = new_state(5, None);
+		state.end_round(RoundResult::Won(0));
+		assert!(!state.finished);
+		assert_eq!(state.pause_ticks, ROUND_PAUSE_TICKS);
+	}
+
+	#[test]
+	fn computer_heads_for_the_most_room() {
+		// A wall across the board with a gap to the bigger side below
+		let mut state = new_state(3, Some(Difficulty::Normal));
+		for x in 0..BOARD_SIZE.0 {
+			state.grid[5][x as usize] = Some(0);
+		}
+		state.grid[5][31] = None;
+		state.cycles[1].pos = (31, 4);
+		state.cycles[1].direction = Direction::Left;
+		state.grid[4][31] = Some(1);
+		assert_eq!(state.computer_direction(1, Difficulty::Normal), Direction::Down);
+		assert_eq!(state.computer_direction(1, Difficulty::Hard), Direction::Down);
+	}
+
+	#[test]
+	fn computer_survives_on_its_own() {
+		for difficulty in [Difficulty::Normal, Difficulty::Hard] {
+			let mut game = harness(new_state(1, Some(difficulty)));
+			// The player keeps going into the wall
+			assert!(game.run_until_finished(100));
+			assert_eq!(game.state.last_result, Some(RoundResult::Won(1)));
+		}
+	}
+
+	#[test]
+	fn snapshot_round() {
+		let mut game = harness(new_state(3, None));
+		game.run_script(&[(0, Action::MoveUp), (4, Action::MoveRight)]);
+		game.state.apply_to(1, Action::MoveDown);
+		game.run(3);
+		assert_snapshot("tron_round", &game.frame());
+	}
+}
@@ -0,0 +1,15 @@
+//! Building blocks for games that run inside the terminal.
+//!
+//! A game implements [`Game`] and is described to the launcher by a
+//! [`GameEntry`](common::registry::GameEntry). Drawing is done with the widgets in
+//! [`common::screen`], input comes from [`common::input`] and
+//! [`common::game_loop`] steps the game at a fixed rate.
+//!
+//! See the `examples` directory for a complete game built on this crate.
+
+pub mod common;
+pub mod games;
+pub mod screens;
+
+pub use common::game::{Game, GameReturn, Score, WinState};
+pub use common::registry::{GameEntry, GameOption, Registry, RegistryError};
@@ -1,68 +1,180 @@
-use common::{game::{Game, GameInstance}, screen::menu::*};
-use clap::Parser;
-use games::*;
+use std::path::PathBuf;
 
-mod games;
-mod common;
+use clap::{Parser, Subcommand};
+use terminal_games::{
+	common::{config::*, game::GameSetup, leaderboard::*, screen::theme::*, spectate::*},
+	games::{self, OnlinePong, Role},
+	screens::{check_bindings, print_game_list, run_launcher, run_leaderboard_server, run_single_game, run_server},
+	Registry
+};
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Let people play over telnet, each connection gets its own menu and games
+	Serve {
+		/// Port to listen on
+		#[arg(long, default_value_t = 2323)]
+		port: u16
+	},
+	/// Keep a leaderboard that players share by setting --leaderboard to this server's address
+	LeaderboardServer {
+		/// Port to listen on
+		#[arg(long, default_value_t = 2324)]
+		port: u16,
+		/// File to keep the scores in, leaderboard-server.toml in the data directory by default
+		#[arg(long)]
+		file: Option<PathBuf>
+	}
+}
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Run games within the terminal", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-	game: Option<String>
+	#[command(subcommand)]
+	command: Option<Command>,
+	game: Option<String>,
+	/// List the available games and exit
+	#[arg(long)]
+	list: bool,
+	/// Colour theme to draw with
+	#[arg(long)]
+	theme: Option<String>,
+	/// Only draw plain ASCII characters, for terminals without unicode support
+	#[arg(long)]
+	ascii: bool,
+	/// Difficulty of computer opponents
+	#[arg(long, value_enum)]
+	difficulty: Option<Difficulty>,
+	/// Name to record scores under
+	#[arg(long)]
+	player: Option<String>,
+	/// Address of a leaderboard server to share high scores through
+	#[arg(long, value_name = "ADDR")]
+	leaderboard: Option<String>,
+	/// Host an online game of pong, listening on the given address
+	#[arg(long, value_name = "ADDR", conflicts_with = "join")]
+	host: Option<String>,
+	/// Join an online game of pong hosted at the given address
+	#[arg(long, value_name = "ADDR")]
+	join: Option<String>,
+	/// Let others watch over a TCP address or unix socket path, with telnet or nc
+	#[arg(long, value_name = "ADDR")]
+	spectate: Option<String>
+}
+
+// Settings from the command line take priority over the config file
+fn get_overrides(cli: &Args) -> Overrides {
+	if let Some(theme_name) = &cli.theme {
+		if Theme::by_name(theme_name).is_none() {
+			let names = Theme::all().iter().map(|theme| theme.name).collect::<Vec<&str>>();
+			println!("Couldn't find theme, available themes: {}", names.join(", "));
+			std::process::exit(1);
+		}
+	}
+	Overrides {
+		theme: cli.theme.clone(),
+		ascii: cli.ascii,
+		difficulty: cli.difficulty,
+		player_name: cli.player.clone(),
+		leaderboard_server: cli.leaderboard.clone()
+	}
+}
+
+fn get_config(overrides: &Overrides) -> Config {
+	let mut config = match Config::load() {
+		Ok(config) => config,
+		Err(err) => {
+			println!("Couldn't load config, {}", err);
+			std::process::exit(1);
+		}
+	};
+	overrides.apply(&mut config);
+	config
 }
 
-fn select_game(games: &[GameInstance]) -> String {
-	let menu_items = games.iter().map(|(_, label)| {
-		MenuItem::new(&label, &label)
-	}).collect::<Vec<MenuItem>>();
-	let selected_name = draw_menu(&menu_items, "Select a game");
-	selected_name
+fn run_leaderboard(registry: &Registry, port: u16, file: Option<PathBuf>) {
+	let Some(path) = file.or_else(get_leaderboard_server_path) else {
+		println!("Couldn't find a data directory for the scores, pass --file");
+		std::process::exit(1);
+	};
+	let scores = match load_toml(path.clone()) {
+		Ok(scores) => scores,
+		Err(err) => {
+			println!("Couldn't load scores, {}", err);
+			std::process::exit(1);
+		}
+	};
+	if let Err(err) = run_leaderboard_server(registry, port, path, scores) {
+		println!("Couldn't start the leaderboard, {}", err);
+		std::process::exit(1);
+	}
 }
 
 fn main() {
 	let cli = Args::parse();
-	let games = [
-		snake::get_game_instance(),
-		pong::get_game_instance(),
-		quit::get_game_instance()
-	];
+	let mut registry = Registry::new();
+	games::register_all(&mut registry).expect("couldn't register games");
+	if cli.list {
+		print_game_list(&registry);
+		return;
+	}
+	if let Some(Command::LeaderboardServer { port, file }) = &cli.command {
+		run_leaderboard(&registry, *port, file.clone());
+		return;
+	}
+
+	// Telnet clients can't say which colours they support, nearly all manage 256
+	set_color_depth(match cli.command {
+		Some(Command::Serve {..}) => ColorDepth::Ansi256,
+		_ => ColorDepth::detect()
+	});
+	let overrides = get_overrides(&cli);
+	apply_config(get_config(&overrides));
+	set_overrides(overrides);
+	if let Err(err) = check_bindings(&registry) {
+		println!("Couldn't load key bindings, {}", err);
+		std::process::exit(1);
+	}
 
-	'outer: loop {
-		let selected_game_name: String;
-	
-		if let Some(game_name) = &cli.game {
-			selected_game_name = game_name.to_owned();
-		} else {
-			selected_game_name = select_game(&games);
+	if let Some(Command::Serve { port }) = cli.command {
+		if let Err(err) = run_server(&registry, port) {
+			println!("Couldn't start the server, {}", err);
+			std::process::exit(1);
 		}
+		return;
+	}
 
-		let mut found_game: Option<&Box<dyn Game>> = None;
-		for game_instance in &games {
-			let game = &game_instance.0;
-			let name = game_instance.1;
-			if name == selected_game_name {
-				found_game = Some(&game);
+	if let Some(addr) = &cli.spectate {
+		match Spectators::start(addr) {
+			Ok(spectators) => set_spectators(spectators),
+			Err(err) => {
+				println!("Couldn't let spectators watch on {}, {}", addr, err);
+				std::process::exit(1);
 			}
 		}
-		
-		if let Some(game) = found_game {
-			let menu_items: Vec<MenuItem> = vec![
-				MenuItem::new("Play again", "again"),
-				MenuItem::new("Play a different game", "different_game"),
-				MenuItem::new("Quit", "quit")
-			];
-			'inner: loop {
-				let game_return = game.run();
-				let response = draw_menu(&menu_items, &game_return.get_end_text());
-				match response.as_str() {
-					"again" => {},
-					"different_game" => break 'inner,
-					_ => break 'outer,
-				}
-			}
-		} else {
-			println!("Couldn't find game");
+	}
+
+	let first_game = cli.game.as_ref().map(|game_name| match registry.find(game_name) {
+		Some(entry) => entry,
+		None => {
+			println!("Couldn't find game, run with --list to see the available games");
 			std::process::exit(1);
 		}
+	});
+	let role = match (&cli.host, &cli.join) {
+		(Some(addr), _) => Some(Role::Host(addr.to_owned())),
+		(_, Some(addr)) => Some(Role::Join(addr.to_owned())),
+		_ => None
+	};
+	match role {
+		Some(role) => {
+			if first_game.is_none_or(|entry| entry.id != "pong") {
+				println!("Online play is only available for pong");
+				std::process::exit(1);
+			}
+			run_single_game(&registry, "pong", || Box::new(OnlinePong::new(role.clone(), GameSetup::new())));
+		},
+		None => run_launcher(&registry, first_game)
 	}
 }
@@ -0,0 +1,48 @@
+use crate::common::{achievements::*, registry::*, screen::menu::*};
+
+fn show_message(message: &str) {
+	draw_menu(&[MenuItem::new("Ok", "ok")], message);
+}
+
+fn achievement_label(progress: &AchievementProgress, game: &GameEntry, achievement: &Achievement) -> String {
+	if progress.is_unlocked(game.id, achievement) {
+		format!("[x] {}: {}", game.name, achievement.name)
+	} else if achievement.goal > 1 {
+		format!("[ ] {}: {} ({}/{})", game.name, achievement.name, progress.progress(game.id, achievement), achievement.goal)
+	} else {
+		format!("[ ] {}: {}", game.name, achievement.name)
+	}
+}
+
+pub fn run_achievements(registry: &Registry) {
+	let progress = match current_progress() {
+		Ok(progress) => progress,
+		Err(err) => {
+			show_message(&format!("Couldn't load achievements, {}", err));
+			return;
+		}
+	};
+	let achievements = registry.games().iter().flat_map(|game| {
+		game.achievements.iter().map(move |achievement| (game, achievement))
+	}).collect::<Vec<(&GameEntry, &Achievement)>>();
+	let labels = achievements.iter().map(|(game, achievement)| {
+		achievement_label(&progress, game, achievement)
+	}).collect::<Vec<String>>();
+	let values = (0..achievements.len()).map(|i| i.to_string()).collect::<Vec<String>>();
+	let unlocked = achievements.iter().filter(|(game, achievement)| progress.is_unlocked(game.id, achievement)).count();
+	let title = format!("Achievements {}/{}", unlocked, achievements.len());
+
+	let mut selected_index = 0;
+	loop {
+		let mut menu_items = labels.iter().zip(&values).map(|(label, value)| {
+			MenuItem::new(label, value)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Back", "back"));
+		let response = draw_menu_at(&menu_items, &title, selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		match response.parse::<usize>().ok().and_then(|i| achievements.get(i)) {
+			Some((_, achievement)) => show_message(achievement.description),
+			None => return
+		}
+	}
+}
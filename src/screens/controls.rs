@@ -0,0 +1,56 @@
+use crate::common::{config::current_config, input::*, registry::Registry, screen::menu::*};
+use crate::games::{pong::right_player_bindings, tron::second_player_bindings};
+
+pub fn get_all_bindings(registry: &Registry) -> Vec<Bindings> {
+	let mut all_bindings = registry.games().iter().map(|entry| {
+		(entry.default_bindings)()
+	}).collect::<Vec<Bindings>>();
+	// The second player's keys in hot-seat Pong and Tron
+	all_bindings.push(right_player_bindings());
+	all_bindings.push(second_player_bindings());
+	all_bindings.push(menu_bindings());
+	all_bindings
+}
+
+// Run at startup so mistakes in the config are reported rather than silently ignored
+pub fn check_bindings(registry: &Registry) -> Result<(), BindingError> {
+	let all_bindings = get_all_bindings(registry);
+	for game in current_config().key_bindings.keys() {
+		if !all_bindings.iter().any(|bindings| bindings.game == *game) {
+			return Err(BindingError::UnknownGame(game.to_owned()));
+		}
+	}
+	for bindings in all_bindings {
+		bindings.load()?;
+	}
+	Ok(())
+}
+
+fn show_bindings(bindings: &Bindings) {
+	let labels = bindings.actions.iter().map(|(action, _)| {
+		format!("{}: {}", action.label(), bindings.describe_keys(*action))
+	}).collect::<Vec<String>>();
+	let mut menu_items = labels.iter().map(|label| {
+		MenuItem::new(label, "back")
+	}).collect::<Vec<MenuItem>>();
+	menu_items.push(MenuItem::new("Back", "back"));
+	let title = format!("{} controls", bindings.game);
+	draw_menu(&menu_items, &title);
+}
+
+pub fn run_controls(registry: &Registry) {
+	let all_bindings = get_all_bindings(registry).iter().map(|bindings| bindings.load_or_default()).collect::<Vec<Bindings>>();
+	let mut selected_index = 0;
+	loop {
+		let mut menu_items = all_bindings.iter().map(|bindings| {
+			MenuItem::new(&bindings.game, &bindings.game)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Back", "back"));
+		let response = draw_menu_at(&menu_items, "Controls", selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		match all_bindings.iter().find(|bindings| bindings.game == response) {
+			Some(bindings) => show_bindings(bindings),
+			None => return
+		}
+	}
+}
@@ -0,0 +1,92 @@
+use crate::common::{config::current_config, daily::*, leaderboard::*, registry::*, screen::{menu::*, toast::show_toast}, terminal::is_served};
+
+use super::{highscores::submit_high_score, launcher::{play_game, show_message}};
+
+static MAX_NAME_LENGTH: usize = 16;
+
+// The shared leaderboard's table for the day if there is one, otherwise this data directory's
+fn show_leaderboard(entry: &GameEntry, results: &DailyResults, date: Date, player: &str) {
+	let server = current_config().leaderboard_server;
+	let local = || results.scores(date, entry.id).iter().map(|score| (score.player.clone(), score.score)).collect::<Vec<(String, f32)>>();
+	let (scores, source) = if server.is_empty() {
+		(local(), "local")
+	} else {
+		match fetch_top(&server, &daily_table(entry.id, date), LOCAL_LIMIT) {
+			Ok(scores) => (scores.into_iter().map(|score| (score.player, score.score)).collect(), "shared"),
+			Err(_) => (local(), "local, leaderboard unreachable")
+		}
+	};
+	let labels = scores.iter().enumerate().map(|(i, (name, score))| {
+		let you = if name == player {" (you)"} else {""};
+		format!("{}. {} {}{}", i + 1, name, score, you)
+	}).collect::<Vec<String>>();
+	let mut menu_items = labels.iter().map(|label| MenuItem::new(label, "back")).collect::<Vec<MenuItem>>();
+	menu_items.push(MenuItem::new("Back", "back"));
+	draw_menu(&menu_items, &format!("{} daily {} ({})", entry.name, date, source));
+}
+
+// Everyone on a served terminal shares the settings, so they each give a name to play under
+fn daily_player() -> Option<String> {
+	if !is_served() {
+		return Some(current_config().player_name);
+	}
+	let name = draw_text_input("Your name for the daily challenge", "", MAX_NAME_LENGTH)?;
+	let name = name.trim();
+	if name.is_empty() {None} else {Some(name.to_owned())}
+}
+
+// One scored attempt per game for each player each day, after that the game shows its leaderboard
+pub fn run_daily(registry: &Registry) {
+	let date = Date::today();
+	let Some(player) = daily_player() else {return};
+	let games = registry.games().iter().filter(|entry| entry.daily.is_some()).collect::<Vec<&GameEntry>>();
+	let mut selected_index = 0;
+	loop {
+		let results = match DailyResults::load() {
+			Ok(results) => results,
+			Err(err) => {
+				show_message(&format!("Couldn't load daily results, {}", err));
+				return;
+			}
+		};
+		let labels = games.iter().map(|entry| {
+			if results.has_played(date, entry.id, &player) {
+				format!("{} (played, see leaderboard)", entry.name)
+			} else {
+				entry.name.to_owned()
+			}
+		}).collect::<Vec<String>>();
+		let mut menu_items = games.iter().zip(&labels).map(|(entry, label)| {
+			MenuItem::new(label, entry.id)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Back", ":back"));
+		let response = draw_menu_at(&menu_items, &format!("Daily challenge {}", date), selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		let Some(entry) = games.iter().find(|entry| entry.id == response) else {return};
+
+		if results.has_played(date, entry.id, &player) {
+			show_leaderboard(entry, &results, date, &player);
+			continue;
+		}
+		let Some(mut setup) = daily_setup(entry, date) else {continue};
+		setup.config.player_name = player.clone();
+		let game_return = play_game(entry.id, (entry.create)(setup.clone()).as_ref());
+		submit_high_score(entry, &setup, &game_return);
+		let score = game_return.score.player_score().unwrap_or(0.0);
+		// Reloaded in case another copy of the game saved results meanwhile
+		let mut results = DailyResults::load().unwrap_or(results);
+		let counted = results.add(date, entry.id, &player, score);
+		if let Err(err) = results.save() {
+			show_message(&format!("Couldn't save daily results, {}", err));
+		}
+		let server = current_config().leaderboard_server;
+		if counted && !server.is_empty() {
+			// Sent before the leaderboard is fetched, so the new score is on it
+			let sent = ScoreEntry::new(entry, &setup, &game_return).map(|score| submit_score(&server, &daily_table(entry.id, date), &score));
+			if let Some(Err(err)) = sent {
+				show_toast("Shared leaderboard", &format!("Couldn't send your daily score, {}", err));
+			}
+		}
+		show_leaderboard(entry, &results, date, &player);
+	}
+}
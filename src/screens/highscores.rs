@@ -0,0 +1,69 @@
+use crate::common::{config::current_config, game::*, leaderboard::*, registry::*, screen::{menu::*, toast::show_toast}, terminal::spawn_in_session};
+
+use super::launcher::show_message;
+
+// The leaderboard can be slow to answer, so its reply is shown as a toast once it comes
+fn send_to_leaderboard(server: String, game_id: &'static str, score: ScoreEntry) {
+	spawn_in_session(move || match submit_score(&server, game_id, &score) {
+		Ok(Some(place)) => show_toast("Shared leaderboard", &format!("Your score is number {}", place)),
+		Ok(None) => {},
+		Err(err) => show_toast("Shared leaderboard", &format!("Couldn't send your score, {}", err))
+	});
+}
+
+// Kept locally, and sent to the shared leaderboard if there is one. The local table is what's
+// shown when the leaderboard can't be reached, so a failed send is left at that
+pub fn submit_high_score(entry: &GameEntry, setup: &GameSetup, game_return: &GameReturn) {
+	let Some(score) = ScoreEntry::new(entry, setup, game_return) else {return};
+	let result = HighScores::load().and_then(|mut scores| {
+		scores.add(entry.id, score.clone(), LOCAL_LIMIT);
+		scores.save()
+	});
+	if let Err(err) = result {
+		show_message(&format!("Couldn't save high scores, {}", err));
+	}
+	let server = current_config().leaderboard_server;
+	if !server.is_empty() {
+		send_to_leaderboard(server, entry.id, score);
+	}
+}
+
+// The shared leaderboard's table if it can be reached, otherwise the local one
+fn show_high_scores(entry: &GameEntry) {
+	let server = current_config().leaderboard_server;
+	let local = || HighScores::load().map(|scores| scores.top(entry.id, LOCAL_LIMIT)).unwrap_or_default();
+	let (scores, source) = if server.is_empty() {
+		(local(), "local")
+	} else {
+		match fetch_top(&server, entry.id, LOCAL_LIMIT) {
+			Ok(scores) => (scores, "shared"),
+			Err(_) => (local(), "local, leaderboard unreachable")
+		}
+	};
+	let labels = scores.iter().enumerate().map(|(i, score)| {
+		let settings = if score.settings.is_empty() {String::new()} else {format!(" ({})", score.settings)};
+		format!("{}. {} {}{}", i + 1, score.player, score.score, settings)
+	}).collect::<Vec<String>>();
+	let mut menu_items = labels.iter().map(|label| MenuItem::new(label, "back")).collect::<Vec<MenuItem>>();
+	if menu_items.is_empty() {
+		menu_items.push(MenuItem::new("No scores yet", "back"));
+	}
+	menu_items.push(MenuItem::new("Back", "back"));
+	draw_menu(&menu_items, &format!("{} high scores ({})", entry.name, source));
+}
+
+pub fn run_high_scores(registry: &Registry) {
+	let mut selected_index = 0;
+	loop {
+		let mut menu_items = registry.games().iter().map(|entry| {
+			MenuItem::new(entry.name, entry.id)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Back", ":back"));
+		let response = draw_menu_at(&menu_items, "High scores", selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		match registry.find(&response) {
+			Some(entry) => show_high_scores(entry),
+			None => return
+		}
+	}
+}
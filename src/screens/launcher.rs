@@ -0,0 +1,158 @@
+use std::time::Instant;
+
+use crate::common::{achievements::*, game::*, registry::*, screen::menu::*, stats::record_game, terminal::is_served};
+
+use super::{achievements::run_achievements, controls::run_controls, daily::run_daily, highscores::*, settings::run_settings, stats::run_stats, tournament::run_tournament};
+
+pub fn show_message(message: &str) {
+	draw_menu(&[MenuItem::new("Ok", "ok")], message);
+}
+
+enum MenuChoice<'r> {
+	Game(&'r GameEntry),
+	Daily,
+	Tournament,
+	HighScores,
+	Stats,
+	Achievements,
+	Controls,
+	Settings,
+	Quit
+}
+
+fn select_game(registry: &Registry) -> MenuChoice<'_> {
+	let mut menu_items = registry.games().iter().map(|entry| {
+		MenuItem::new(entry.name, entry.id)
+	}).collect::<Vec<MenuItem>>();
+	// Menu actions are prefixed so they can't clash with a game id
+	menu_items.push(MenuItem::new("Daily challenge", ":daily"));
+	menu_items.push(MenuItem::new("Tournament", ":tournament"));
+	menu_items.push(MenuItem::new("High scores", ":highscores"));
+	menu_items.push(MenuItem::new("Stats", ":stats"));
+	menu_items.push(MenuItem::new("Achievements", ":achievements"));
+	if !is_served() {
+		menu_items.push(MenuItem::new("Controls", ":controls"));
+		menu_items.push(MenuItem::new("Settings", ":settings"));
+	}
+	menu_items.push(MenuItem::new("Quit", ":quit"));
+	let selected = draw_menu_with_banner(&menu_items, "Select a game", Some("Terminal games"));
+	match selected.as_str() {
+		":daily" => MenuChoice::Daily,
+		":tournament" => MenuChoice::Tournament,
+		":highscores" => MenuChoice::HighScores,
+		":stats" => MenuChoice::Stats,
+		":achievements" => MenuChoice::Achievements,
+		":controls" => MenuChoice::Controls,
+		":settings" => MenuChoice::Settings,
+		":quit" => MenuChoice::Quit,
+		id => match registry.find(id) {
+			Some(entry) => MenuChoice::Game(entry),
+			None => MenuChoice::Quit
+		}
+	}
+}
+
+pub fn print_game_list(registry: &Registry) {
+	for entry in registry.games() {
+		let options = entry.options.iter().map(|option| option.name()).collect::<Vec<&str>>();
+		println!("{} ({}) v{}", entry.name, entry.id, entry.version);
+		println!("  {}", entry.description);
+		println!("  Players: {}", entry.players_text());
+		if !options.is_empty() {
+			println!("  Options: {}", options.join(", "));
+		}
+	}
+}
+
+// Runs one game, adding the result to the stats and saving any achievements
+pub fn play_game(game_id: &str, game: &dyn Game) -> GameReturn {
+	let started = Instant::now();
+	let game_return = game.run();
+	if let Err(err) = record_game(game_id, &game_return, started.elapsed()) {
+		show_message(&format!("Couldn't save stats, {}", err));
+	}
+	if let Err(err) = save_progress() {
+		show_message(&format!("Couldn't save achievements, {}", err));
+	}
+	game_return
+}
+
+fn load_achievements(registry: &Registry) {
+	match AchievementProgress::load() {
+		Ok(progress) => start_tracking(registry, progress),
+		Err(err) => show_message(&format!("Couldn't load achievements, they won't be unlocked this time, {}", err))
+	}
+}
+
+// Plays one game repeatedly without the main menu, for games set up from the command line
+pub fn run_single_game<F>(registry: &Registry, game_id: &str, create: F) where F: Fn() -> Box<dyn Game> {
+	load_achievements(registry);
+	let menu_items: Vec<MenuItem> = vec![
+		MenuItem::new("Play again", "again"),
+		MenuItem::new("Quit", "quit")
+	];
+	loop {
+		let game_return = play_game(game_id, create().as_ref());
+		if draw_menu(&menu_items, &game_return.get_end_text()) != "again" {break};
+	}
+}
+
+// Main menu loop, starting straight into the first game if one is given
+pub fn run_launcher(registry: &Registry, first_game: Option<&GameEntry>) {
+	load_achievements(registry);
+	let mut requested_game = first_game;
+	loop {
+		let entry = match requested_game.take() {
+			Some(entry) => entry,
+			None => match select_game(registry) {
+				MenuChoice::Game(entry) => entry,
+				MenuChoice::Daily => {
+					run_daily(registry);
+					continue;
+				},
+				MenuChoice::Tournament => {
+					run_tournament();
+					continue;
+				},
+				MenuChoice::HighScores => {
+					run_high_scores(registry);
+					continue;
+				},
+				MenuChoice::Stats => {
+					run_stats(registry);
+					continue;
+				},
+				MenuChoice::Achievements => {
+					run_achievements(registry);
+					continue;
+				},
+				MenuChoice::Controls => {
+					run_controls(registry);
+					continue;
+				},
+				MenuChoice::Settings => {
+					run_settings();
+					continue;
+				},
+				MenuChoice::Quit => break
+			}
+		};
+
+		let menu_items: Vec<MenuItem> = vec![
+			MenuItem::new("Play again", "again"),
+			MenuItem::new("Play a different game", "different_game"),
+			MenuItem::new("Quit", "quit")
+		];
+		'inner: loop {
+			let setup = GameSetup::new();
+			let game_return = play_game(entry.id, (entry.create)(setup.clone()).as_ref());
+			submit_high_score(entry, &setup, &game_return);
+			let response = draw_menu(&menu_items, &game_return.get_end_text());
+			match response.as_str() {
+				"again" => {},
+				"different_game" => break 'inner,
+				_ => return,
+			}
+		}
+	}
+}
@@ -0,0 +1,13 @@
+pub(crate) mod achievements;
+pub(crate) mod controls;
+pub(crate) mod daily;
+pub(crate) mod highscores;
+pub(crate) mod launcher;
+pub(crate) mod server;
+pub(crate) mod settings;
+pub(crate) mod stats;
+pub(crate) mod tournament;
+
+pub use controls::check_bindings;
+pub use launcher::{print_game_list, run_launcher, run_single_game};
+pub use server::{run_leaderboard_server, run_server};
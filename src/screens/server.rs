@@ -0,0 +1,91 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::common::{
+	leaderboard::{handle_client, turn_away, HighScores},
+	registry::Registry,
+	screen::util::clear_screen,
+	telnet::TelnetTerminal,
+	terminal::with_terminal
+};
+
+use super::launcher::run_launcher;
+
+// Players at once, later connections are turned away until someone leaves
+static MAX_SESSIONS: usize = 32;
+
+// Leaderboard clients at once, each has its own thread
+static MAX_CLIENTS: usize = 64;
+
+// Each connection gets the whole launcher to itself, on its own thread
+pub fn run_server(registry: &Registry, port: u16) -> io::Result<()> {
+	let listener = TcpListener::bind(("0.0.0.0", port))?;
+	println!("Serving games on port {}, connect with telnet", port);
+	let sessions = AtomicUsize::new(0);
+	thread::scope(|scope| {
+		for stream in listener.incoming() {
+			match stream {
+				Ok(mut stream) if sessions.load(Ordering::SeqCst) >= MAX_SESSIONS => {
+					let _ = stream.write_all(b"Too many players right now, try again later\r\n");
+				},
+				Ok(stream) => {
+					sessions.fetch_add(1, Ordering::SeqCst);
+					let sessions = &sessions;
+					scope.spawn(move || {
+						run_session(registry, stream);
+						sessions.fetch_sub(1, Ordering::SeqCst);
+					});
+				},
+				Err(err) => println!("Couldn't accept a connection, {}", err)
+			}
+		}
+	});
+	Ok(())
+}
+
+fn run_session(registry: &Registry, stream: TcpStream) {
+	let peer = stream.peer_addr().map_or("unknown".to_owned(), |addr| addr.to_string());
+	println!("{} connected", peer);
+	match TelnetTerminal::start(stream) {
+		Ok(terminal) => {
+			with_terminal(terminal.clone(), || {
+				run_launcher(registry, None);
+				clear_screen();
+			});
+			terminal.close();
+		},
+		Err(err) => println!("Couldn't start a session for {}, {}", peer, err)
+	}
+	println!("{} disconnected", peer);
+}
+
+// Keeps the shared high score table, saved to the given file after each new score
+pub fn run_leaderboard_server(registry: &Registry, port: u16, path: PathBuf, scores: HighScores) -> io::Result<()> {
+	let scores = Mutex::new(scores);
+	let listener = TcpListener::bind(("0.0.0.0", port))?;
+	println!("Leaderboard listening on port {}, saving to {}", port, path.display());
+	let clients = AtomicUsize::new(0);
+	thread::scope(|scope| {
+		for stream in listener.incoming() {
+			match stream {
+				Ok(stream) if clients.load(Ordering::SeqCst) >= MAX_CLIENTS => turn_away(stream),
+				Ok(stream) => {
+					clients.fetch_add(1, Ordering::SeqCst);
+					let (clients, scores, path) = (&clients, &scores, &path);
+					scope.spawn(move || {
+						if let Err(err) = handle_client(stream, registry, scores, path) {
+							println!("Leaderboard client error, {}", err);
+						}
+						clients.fetch_sub(1, Ordering::SeqCst);
+					});
+				},
+				Err(err) => println!("Couldn't accept a connection, {}", err)
+			}
+		}
+	});
+	Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::common::{config::*, screen::{menu::*, theme::Theme}};
+
+static SNAKE_SIZES: [(u16, u16); 4] = [(20, 8), (30, 10), (40, 15), (60, 20)];
+static SNAKE_SPEEDS: [u16; 4] = [30, 50, 80, 120];
+static PONG_POINTS: [u16; 5] = [3, 5, 10, 15, 21];
+static TILES_SIZES: [u16; 4] = [3, 4, 5, 6];
+static TRON_ROUNDS: [u16; 4] = [1, 3, 5, 7];
+static MAX_NAME_LENGTH: usize = 16;
+
+fn next_option<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+	let index = options.iter().position(|option| *option == current);
+	match index {
+		Some(i) => options[(i + 1) % options.len()],
+		None => options[0]
+	}
+}
+
+fn show_message(message: &str) {
+	draw_menu(&[MenuItem::new("Ok", "ok")], message);
+}
+
+// Width, height and mines as three numbers, anything out of range is brought into it
+fn edit_custom_minefield(minesweeper: &mut MinesweeperConfig) {
+	let (width, height, mines) = minesweeper.board();
+	let Some(text) = draw_text_input("Width, height and mines", &format!("{} {} {}", width, height, mines), MAX_NAME_LENGTH) else {return};
+	let numbers = text.split([' ', ',', 'x']).filter(|part| !part.is_empty()).map(|part| part.parse::<u16>()).collect::<Result<Vec<u16>, _>>();
+	let Ok([width, height, mines]) = numbers.as_deref() else {
+		show_message("Enter the width, height and number of mines, like 20 12 40");
+		return;
+	};
+	(minesweeper.width, minesweeper.height, minesweeper.mines) = (*width, *height, *mines);
+	(minesweeper.width, minesweeper.height, minesweeper.mines) = minesweeper.board();
+}
+
+// Edits the saved config file, CLI overrides aren't written back but stay in use
+pub fn run_settings() {
+	let mut config = match Config::load() {
+		Ok(config) => config,
+		Err(err) => {
+			show_message(&format!("Couldn't load settings, {}", err));
+			current_config()
+		}
+	};
+	let mut selected_index = 0;
+	loop {
+		let mut labels = vec![
+			format!("Theme: {}", config.theme),
+			format!("ASCII only: {}", if config.ascii {"on"} else {"off"}),
+			format!("Difficulty: {}", config.difficulty.name()),
+			format!("Player name: {}", config.player_name),
+			format!("Snake board: {}x{}", config.snake.width, config.snake.height),
+			format!("Snake speed: {}ms", config.snake.speed),
+			format!("Pong points to win: {}", config.pong.points_to_win),
+			format!("2048 board: {0}x{0}", config.tiles.board_size()),
+			format!("Minesweeper: {}", config.minesweeper.level.name())
+		];
+		let mut values = vec!["theme", "ascii", "difficulty", "player_name", "snake_size", "snake_speed", "pong_points", "tiles_size", "mines_level"];
+		if config.minesweeper.level == MinesLevel::Custom {
+			let (width, height, mines) = config.minesweeper.board();
+			labels.push(format!("Minesweeper custom board: {}x{}, {} mines", width, height, mines));
+			values.push("mines_custom");
+		}
+		labels.push(format!("Tron rounds: best of {}", config.tron.rounds));
+		labels.push(format!("Tron opponent: {}", if config.tron.two_players {"second player"} else {"computer"}));
+		values.extend(["tron_rounds", "tron_opponent"]);
+		let mut menu_items = labels.iter().zip(values).map(|(label, value)| {
+			MenuItem::new(label, value)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Save", "save"));
+		menu_items.push(MenuItem::new("Back", "back"));
+
+		let response = draw_menu_at(&menu_items, "Settings", selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		match response.as_str() {
+			"theme" => {
+				let names = Theme::all().iter().map(|theme| theme.name.to_owned()).collect::<Vec<String>>();
+				let index = names.iter().position(|name| *name == config.theme);
+				config.theme = names[index.map(|i| (i + 1) % names.len()).unwrap_or(0)].clone();
+			},
+			"ascii" => config.ascii = !config.ascii,
+			"difficulty" => config.difficulty = next_option(&Difficulty::all(), config.difficulty),
+			"player_name" => {
+				if let Some(name) = draw_text_input("Player name", &config.player_name, MAX_NAME_LENGTH) {
+					let name = name.trim();
+					if !name.is_empty() {config.player_name = name.to_owned()};
+				}
+			},
+			"snake_size" => {
+				let (width, height) = next_option(&SNAKE_SIZES, (config.snake.width, config.snake.height));
+				config.snake.width = width;
+				config.snake.height = height;
+			},
+			"snake_speed" => config.snake.speed = next_option(&SNAKE_SPEEDS, config.snake.speed),
+			"pong_points" => config.pong.points_to_win = next_option(&PONG_POINTS, config.pong.points_to_win),
+			"tiles_size" => config.tiles.size = next_option(&TILES_SIZES, config.tiles.board_size()),
+			"mines_level" => config.minesweeper.level = next_option(&MinesLevel::all(), config.minesweeper.level),
+			"mines_custom" => edit_custom_minefield(&mut config.minesweeper),
+			"tron_rounds" => config.tron.rounds = next_option(&TRON_ROUNDS, config.tron.rounds),
+			"tron_opponent" => config.tron.two_players = !config.tron.two_players,
+			"save" => {
+				match config.save() {
+					Ok(()) => {
+						// Whatever was given on the command line still holds for the rest of the run
+						current_overrides().apply(&mut config);
+						apply_config(config);
+						return;
+					},
+					Err(err) => show_message(&format!("Couldn't save settings, {}", err))
+				}
+			},
+			_ => return
+		}
+	}
+}
@@ -0,0 +1,116 @@
+use crate::common::{
+	input::read_key,
+	registry::*,
+	screen::{boxes::BoxPrint, chart::*, layout::*, menu::*, text::TextPrint, theme::current_theme, util::*},
+	stats::*
+};
+
+static LABEL_WIDTH: u16 = 15;
+static CHART_WIDTH: u16 = 20;
+
+fn stat_lines(stats: &GameStats) -> Vec<(String, String)> {
+	let mut lines = vec![
+		("Played".to_owned(), stats.played.to_string()),
+		("Best score".to_owned(), stats.best_score.map_or("-".to_owned(), |score| score.to_string())),
+		("Average score".to_owned(), stats.average_score().map_or("-".to_owned(), |score| format!("{:.1}", score))),
+		("Play time".to_owned(), format_play_time(stats.play_time))
+	];
+	for (label, value) in &stats.records {
+		lines.push((label.to_owned(), value.to_string()));
+	}
+	for (label, times) in &stats.best_times {
+		let times = times.iter().map(|time| format!("{:.1}s", time)).collect::<Vec<String>>();
+		lines.push((label.to_owned(), times.join(" ")));
+	}
+	lines.push((String::new(), String::new()));
+	let results = [("Wins", stats.wins), ("Losses", stats.losses), ("Draws", stats.draws)];
+	let most = results.iter().map(|(_, count)| *count).max().unwrap_or(0);
+	for (label, count) in results {
+		let bar = bar(count as f32, most as f32, CHART_WIDTH);
+		let value = if bar.is_empty() {count.to_string()} else {format!("{} {}", bar, count)};
+		lines.push((label.to_owned(), value));
+	}
+	lines.push((String::new(), String::new()));
+	lines.push(("Recent scores".to_owned(), sparkline(&stats.recent_scores, CHART_WIDTH)));
+	lines
+}
+
+pub fn draw_game_stats(name: &str, stats: &GameStats) {
+	clear_screen();
+	let lines = stat_lines(stats);
+	let box_print = BoxPrint::new((LABEL_WIDTH + CHART_WIDTH + 10, lines.len() as u16 + 6));
+	box_print.print();
+	let [title_rect, lines_rect, footer_rect] = Layout::vertical(&[Constraint::Fixed(2), Constraint::Min(0), Constraint::Fixed(1)])
+		.padding(Padding::symmetric(1, 0))
+		.split(box_print.inner())[..] else {return};
+
+	let theme = current_theme();
+	let title = format!("{} stats", name);
+	TextPrint::in_rect(&title, title_rect, Align::Start)
+		.set_max_width(title_rect.width)
+		.color_fg(&theme.accent)
+		.add_prefix(&termion::style::Bold)
+		.print();
+	for (i, (label, value)) in lines.iter().enumerate() {
+		let row = lines_rect.row(i as u16);
+		let [label_rect, value_rect] = Layout::horizontal(&[Constraint::Fixed(LABEL_WIDTH), Constraint::Min(0)]).split(row)[..] else {continue};
+		TextPrint::in_rect(label, label_rect, Align::Start).set_max_width(label_rect.width).print();
+		TextPrint::in_rect(value, value_rect, Align::Start).set_max_width(value_rect.width).print();
+	}
+	TextPrint::in_rect("Press any key to go back", footer_rect, Align::Start)
+		.set_max_width(footer_rect.width)
+		.print();
+	cursor_to_end();
+}
+
+pub fn run_stats(registry: &Registry) {
+	let stats = match Stats::load() {
+		Ok(stats) => stats,
+		Err(err) => {
+			draw_menu(&[MenuItem::new("Ok", "ok")], &format!("Couldn't load stats, {}", err));
+			return;
+		}
+	};
+	let labels = registry.games().iter().map(|entry| {
+		format!("{} ({} played)", entry.name, stats.game(entry.id).played)
+	}).collect::<Vec<String>>();
+	let mut selected_index = 0;
+	loop {
+		let mut menu_items = registry.games().iter().zip(&labels).map(|(entry, label)| {
+			MenuItem::new(label, entry.id)
+		}).collect::<Vec<MenuItem>>();
+		menu_items.push(MenuItem::new("Back", ":back"));
+		let response = draw_menu_at(&menu_items, "Stats", selected_index);
+		selected_index = menu_items.iter().position(|item| item.value == response).unwrap_or(0);
+		match registry.find(&response) {
+			Some(entry) => {
+				draw_game_stats(entry.name, &stats.game(entry.id));
+				read_key();
+			},
+			None => return
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::common::{game::*, screen::frame::capture, snapshot::assert_snapshot};
+
+	#[test]
+	fn snapshot_game_stats() {
+		let mut stats = GameStats::default();
+		for (score, win_state) in [(3.0, WinState::Lose), (10.0, WinState::Win), (6.0, WinState::Lose), (8.0, WinState::Draw), (10.0, WinState::Lose)] {
+			stats.record(&GameReturn {
+				score: Score::TwoPlayer(score, 10.0),
+				win_state,
+				records: vec![("Longest rally", score as u32 * 2)],
+				times: vec![]
+			}, Duration::from_secs(150));
+		}
+		let frame = capture((50, 22), || draw_game_stats("Pong", &stats));
+		assert_snapshot("stats_pong", &frame);
+	}
+}
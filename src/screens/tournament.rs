@@ -0,0 +1,276 @@
+use console::Key;
+
+use crate::common::{
+	achievements::save_progress,
+	config::*,
+	game::*,
+	input::*,
+	screen::{bigtext::print_splash, boxes::BoxPrint, layout::*, menu::*, text::TextPrint, theme::current_theme, util::*},
+	terminal::input_closed,
+	tournament::*
+};
+use crate::games::pong::{default_bindings, right_player_bindings, Control, Versus};
+
+use super::launcher::show_message;
+
+static MAX_ENTRANTS: usize = 8;
+static MAX_NAME_LENGTH: usize = 16;
+static POINTS: [u16; 3] = [3, 5, 10];
+static MATCH_WIDTH: u16 = 20;
+static MATCH_HEIGHT: u16 = 4;
+
+fn next_in<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+	let index = options.iter().position(|option| *option == current).map_or(0, |i| (i + 1) % options.len());
+	options[index]
+}
+
+fn entrant_label(entrant: &Entrant) -> String {
+	match entrant.kind {
+		EntrantKind::Human => entrant.name.clone(),
+		EntrantKind::Computer(difficulty) => format!("{} ({} computer)", entrant.name, difficulty.name())
+	}
+}
+
+fn draw_match(tournament: &Tournament, played: &Match, rect: Rect, is_next: bool) {
+	let theme = current_theme();
+	let mut box_print = BoxPrint::in_rect(rect);
+	if is_next {box_print.set_border_col(&theme.accent);}
+	box_print.print();
+	let inner = box_print.inner().inner(Padding::symmetric(1, 0));
+	let sides = [(played.players.0, played.result.map(|result| result.0)), (played.players.1, played.result.map(|result| result.1))];
+	for (i, (player, score)) in sides.into_iter().enumerate() {
+		let row = inner.row(i as u16);
+		let score_text = score.map_or(String::new(), |score| score.to_string());
+		let mut name = TextPrint::in_rect(&tournament.entrants[player].name, row, Align::Start);
+		name.set_max_width(row.width.saturating_sub(3));
+		if played.winner() == Some(player) {
+			name.color_fg(&theme.accent).add_prefix(&termion::style::Bold);
+		}
+		name.print();
+		TextPrint::in_rect(&score_text, row, Align::End).print();
+	}
+}
+
+// Each round is a column of matches, as many rounds as fit up to the one being played
+pub fn draw_bracket(tournament: &Tournament, status: &str) {
+	clear_screen();
+	let [title_rect, rounds_rect, status_rect] = Layout::vertical(&[Constraint::Fixed(2), Constraint::Min(0), Constraint::Fixed(1)])
+		.padding(Padding::uniform(1))
+		.split(Rect::screen())[..] else {return};
+	let theme = current_theme();
+	let title = format!("Tournament, {}", tournament.format.name());
+	TextPrint::in_rect(&title, title_rect, Align::Start)
+		.set_max_width(title_rect.width)
+		.color_fg(&theme.accent)
+		.add_prefix(&termion::style::Bold)
+		.print();
+
+	let next = tournament.next_match();
+	let current = next.map_or(tournament.rounds.len().saturating_sub(1), |(round, _)| round);
+	let columns = std::cmp::max(1, (rounds_rect.width + 1) / (MATCH_WIDTH + 1)) as usize;
+	let first = (current + 1).saturating_sub(columns);
+	let shown = tournament.rounds.iter().enumerate().skip(first).take(columns);
+	for (column, (round_index, round)) in shown.enumerate() {
+		let x = rounds_rect.x + column as u16 * (MATCH_WIDTH + 1);
+		let column_rect = Rect::new((x, rounds_rect.y), (MATCH_WIDTH, rounds_rect.height)).intersection(rounds_rect);
+		TextPrint::in_rect(&round.label, column_rect.row(0), Align::Start).set_max_width(column_rect.width).add_prefix(&termion::style::Bold).print();
+		let mut y = column_rect.y + 1;
+		for (match_index, played) in round.matches.iter().enumerate() {
+			if y + MATCH_HEIGHT > column_rect.bottom() {break};
+			let rect = Rect::new((x, y), (column_rect.width, MATCH_HEIGHT));
+			draw_match(tournament, played, rect, next == Some((round_index, match_index)));
+			y += MATCH_HEIGHT;
+		}
+		if let Some(bye) = round.bye {
+			let bye_text = format!("Bye: {}", tournament.entrants[bye].name);
+			if y < column_rect.bottom() {
+				TextPrint::in_rect(&bye_text, column_rect.row(y - column_rect.y), Align::Start).set_max_width(column_rect.width).print();
+			}
+		}
+	}
+	TextPrint::in_rect(status, status_rect, Align::Start).set_max_width(status_rect.width).print();
+	cursor_to_end();
+}
+
+fn is_computer(entrant: &Entrant) -> bool {
+	matches!(entrant.kind, EntrantKind::Computer(_))
+}
+
+fn control(entrant: &Entrant) -> Control {
+	match entrant.kind {
+		EntrantKind::Human => Control::Keys,
+		EntrantKind::Computer(difficulty) => Control::Computer(difficulty)
+	}
+}
+
+// The up and down keys for each side when two players share the keyboard, the right hand player's come first
+fn hot_seat_keys() -> (String, String) {
+	let left = default_bindings().load_or_default();
+	let right = right_player_bindings().load_or_default();
+	let describe = |bindings: &Bindings, taken: Option<&Bindings>| [Action::MoveUp, Action::MoveDown].iter().filter_map(|action| {
+		let key = bindings.keys_for(*action).iter().find(|key| taken.is_none_or(|taken| taken.action_for(**key).is_none()));
+		key.map(|key| key.to_string())
+	}).collect::<Vec<String>>().join("/");
+	(describe(&left, Some(&right)), describe(&right, None))
+}
+
+// A human playing the computer always has the left paddle
+fn sides(tournament: &Tournament, played: &Match) -> ((usize, usize), bool) {
+	let (left, right) = played.players;
+	let swapped = is_computer(&tournament.entrants[left]) && !is_computer(&tournament.entrants[right]);
+	(if swapped {(right, left)} else {(left, right)}, swapped)
+}
+
+fn match_status(tournament: &Tournament, played: &Match) -> String {
+	let ((left, right), _) = sides(tournament, played);
+	let (left, right) = (&tournament.entrants[left], &tournament.entrants[right]);
+	let versus = if is_computer(left) || is_computer(right) {
+		format!("{} vs {}", left.name, right.name)
+	} else {
+		let (left_keys, right_keys) = hot_seat_keys();
+		format!("{} ({}) vs {} ({})", left.name, left_keys, right.name, right_keys)
+	};
+	format!("Next: {}. Press any key to play, escape to leave", versus)
+}
+
+// The result in the match's own order, None if the game didn't finish with a score
+fn play_match(tournament: &Tournament, played: &Match, points: u16) -> Option<(u16, u16)> {
+	let ((left, right), swapped) = sides(tournament, played);
+	let (left, right) = (&tournament.entrants[left], &tournament.entrants[right]);
+	let mut setup = GameSetup::new();
+	setup.config.pong.points_to_win = points;
+	let game = Versus::new(setup, (left.name.clone(), control(left)), (right.name.clone(), control(right)));
+	let game_return = game.run();
+	if let Err(err) = save_progress() {
+		show_message(&format!("Couldn't save achievements, {}", err));
+	}
+	match_result(&game_return, swapped)
+}
+
+// Hot-seat and computer matches both end with a versus score, from the left hand player's side
+fn match_result(game_return: &GameReturn, swapped: bool) -> Option<(u16, u16)> {
+	let Score::Versus(left_score, right_score) = game_return.score else {return None};
+	let result = (left_score as u16, right_score as u16);
+	Some(if swapped {(result.1, result.0)} else {result})
+}
+
+fn show_champion(tournament: &Tournament, champion: usize) {
+	let standing = tournament.standings().into_iter().find(|standing| standing.player == champion);
+	let record = standing.map_or(String::new(), |standing| format!(", {} won {} lost", standing.wins, standing.losses));
+	clear_screen();
+	print_splash(&tournament.entrants[champion].name, &format!("Champion{}", record));
+	cursor_to_end();
+	read_key();
+}
+
+fn play_tournament(mut tournament: Tournament, points: u16) {
+	while let Some((round, index)) = tournament.next_match() {
+		let played = tournament.rounds[round].matches[index].clone();
+		draw_bracket(&tournament, &match_status(&tournament, &played));
+		if matches!(read_console_key(), Some(Key::Escape) | None) {return};
+		if let Some(result) = play_match(&tournament, &played, points) {
+			tournament.record(round, index, result);
+		}
+		if input_closed() {return};
+	}
+	draw_bracket(&tournament, "Press any key to continue");
+	if read_key().is_none() {return};
+	if let Some(champion) = tournament.champion() {
+		show_champion(&tournament, champion);
+	}
+}
+
+fn add_player(entrants: &mut Vec<Entrant>) {
+	let suggested = if entrants.iter().any(|entrant| !is_computer(entrant)) {String::new()} else {current_config().player_name};
+	let Some(name) = draw_text_input("Player name", &suggested, MAX_NAME_LENGTH) else {return};
+	let name = name.trim();
+	if name.is_empty() {return};
+	if entrants.iter().any(|entrant| entrant.name == name) {
+		show_message(&format!("There's already a player called {}", name));
+		return;
+	}
+	entrants.push(Entrant::human(name));
+}
+
+fn add_computer(entrants: &mut Vec<Entrant>) {
+	let difficulties = Difficulty::all();
+	let mut menu_items = difficulties.iter().map(|difficulty| MenuItem::new(difficulty.name(), difficulty.name())).collect::<Vec<MenuItem>>();
+	menu_items.push(MenuItem::new("Back", ":back"));
+	let response = draw_menu(&menu_items, "Computer difficulty");
+	let Some(difficulty) = difficulties.into_iter().find(|difficulty| difficulty.name() == response) else {return};
+	let number = (1..).find(|n| !entrants.iter().any(|entrant| entrant.name == format!("CPU {}", n))).unwrap_or(1);
+	entrants.push(Entrant::computer(&format!("CPU {}", number), difficulty));
+}
+
+// Sets up the players and format, then plays the matches on Pong one after another
+pub fn run_tournament() {
+	let mut entrants: Vec<Entrant> = vec![];
+	let mut format = Format::SingleElimination;
+	let mut points = 5;
+	// Kept by value, adding and removing players moves the other items
+	let mut selected = String::new();
+	loop {
+		let labels = entrants.iter().map(entrant_label).collect::<Vec<String>>();
+		let values = (0..entrants.len()).map(|i| format!("entrant:{}", i)).collect::<Vec<String>>();
+		let mut menu_items = labels.iter().zip(&values).map(|(label, value)| MenuItem::new(label, value)).collect::<Vec<MenuItem>>();
+		let format_label = format!("Format: {}", format.name());
+		let points_label = format!("Points to win: {}", points);
+		if entrants.len() < MAX_ENTRANTS {
+			menu_items.push(MenuItem::new("Add a player", ":add_player"));
+			menu_items.push(MenuItem::new("Add a computer", ":add_computer"));
+		}
+		menu_items.push(MenuItem::new(&format_label, ":format"));
+		menu_items.push(MenuItem::new(&points_label, ":points"));
+		menu_items.push(MenuItem::new("Start", ":start"));
+		menu_items.push(MenuItem::new("Back", ":back"));
+		let selected_index = menu_items.iter().position(|item| item.value == selected).unwrap_or(0);
+		let response = draw_menu_at(&menu_items, "Tournament, select a player to remove them", selected_index);
+		selected = response.clone();
+		match response.as_str() {
+			":add_player" => add_player(&mut entrants),
+			":add_computer" => add_computer(&mut entrants),
+			":format" => format = next_in(&Format::all(), format),
+			":points" => points = next_in(&POINTS, points),
+			":start" if entrants.len() < 2 => show_message("A tournament needs at least two players"),
+			":start" => {
+				play_tournament(Tournament::new(format, entrants.clone()), points);
+				if input_closed() {return};
+			},
+			value => match value.strip_prefix("entrant:").and_then(|index| index.parse::<usize>().ok()) {
+				Some(index) if index < entrants.len() => {
+					entrants.remove(index);
+				},
+				_ => return
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::common::{screen::frame::capture, snapshot::assert_snapshot};
+
+	#[test]
+	fn snapshot_bracket() {
+		let entrants = ["Ada", "Grace", "Alan", "Edsger", "Barbara"].iter().map(|name| Entrant::human(name)).collect();
+		let mut tournament = Tournament::new(Format::SingleElimination, entrants);
+		tournament.record(0, 0, (5, 3));
+		tournament.record(0, 1, (1, 5));
+		let frame = capture((64, 16), || draw_bracket(&tournament, "Next: Barbara vs Ada"));
+		assert_snapshot("tournament_bracket", &frame);
+	}
+
+	#[test]
+	fn hot_seat_results_move_the_bracket_on() {
+		let entrants = ["Ada", "Grace"].iter().map(|name| Entrant::human(name)).collect();
+		let mut tournament = Tournament::new(Format::SingleElimination, entrants);
+		assert_eq!(match_result(&GameReturn::new(Score::None, WinState::None), false), None);
+		let game_return = GameReturn::new(Score::Versus(2.0, 5.0), WinState::Lose);
+		let result = match_result(&game_return, false).unwrap();
+		tournament.record(0, 0, result);
+		assert_eq!(tournament.next_match(), None);
+		assert_eq!(tournament.champion(), Some(1));
+		assert_eq!(match_result(&game_return, true), Some((5, 2)));
+	}
+}
@@ -0,0 +1,70 @@
+use terminal_games::common::{
+	screen::{boxes::BoxPrint, frame::capture, layout::Rect, menu::*, theme::ThemeColor},
+	snapshot::assert_snapshot
+};
+
+static MENU_LABELS: [&str; 12] = [
+	"Apple", "Banana", "Cherry", "Damson", "Elderberry", "Fig",
+	"Grape", "Honeydew", "Kiwi", "Lemon", "Mango", "Nectarine"
+];
+
+fn menu_items() -> Vec<MenuItem<'static>> {
+	MENU_LABELS.iter().map(|label| MenuItem::new(label, label)).collect()
+}
+
+#[test]
+fn box_with_border() {
+	let border = ThemeColor::Rgb(200, 160, 40);
+	let frame = capture((12, 6), || {
+		BoxPrint::in_rect(Rect::new((2, 2), (8, 4)))
+			.set_border_col(&border)
+			.print();
+	});
+	assert_snapshot("box_with_border", &frame);
+}
+
+#[test]
+fn box_without_border() {
+	let fill = ThemeColor::Rgb(40, 80, 120);
+	let frame = capture((12, 6), || {
+		BoxPrint::in_rect(Rect::new((2, 2), (8, 4)))
+			.set_border(false)
+			.set_fill(&fill)
+			.print();
+	});
+	assert_snapshot("box_without_border", &frame);
+}
+
+#[test]
+fn menu_scroll_windows() {
+	let items = menu_items();
+	assert_eq!(get_window_scroll_items_range(&items, 0, 7), 0..7);
+	assert_eq!(get_window_scroll_items_range(&items, 6, 7), 3..10);
+	assert_eq!(get_window_scroll_items_range(&items, 11, 7), 5..12);
+	assert_eq!(get_window_scroll_items_range(&items[..4], 3, 7), 0..4);
+}
+
+#[test]
+fn menu_at_top() {
+	let frame = capture((30, 14), || draw_menu_render(&menu_items(), "Fruit", None, 0));
+	assert_snapshot("menu_at_top", &frame);
+}
+
+#[test]
+fn menu_scrolled_to_middle() {
+	let frame = capture((30, 14), || draw_menu_render(&menu_items(), "Fruit", None, 6));
+	assert_snapshot("menu_scrolled_to_middle", &frame);
+}
+
+#[test]
+fn menu_scrolled_to_end() {
+	let frame = capture((30, 14), || draw_menu_render(&menu_items(), "Fruit", None, 11));
+	assert_snapshot("menu_scrolled_to_end", &frame);
+}
+
+#[test]
+fn menu_with_banner() {
+	let items = &menu_items()[..3];
+	let frame = capture((40, 16), || draw_menu_render(items, "Fruit", Some("Fruit"), 1));
+	assert_snapshot("menu_with_banner", &frame);
+}